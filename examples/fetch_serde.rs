@@ -11,7 +11,7 @@ fn main() {
     .expect("bad url");
 
     // Same as `serde = "*"` in Cargo.toml
-    let crates_io = fetcher.resolve_first("serde", None, PackageSource::CratesIo, None).expect("can't find serde");
+    let crates_io = fetcher.resolve_first("serde", None, &PackageSource::CratesIo, None).expect("can't find serde");
     let custom_registry = Package::from_str_ver("serde", "1.0.0", &custom_source).expect("bad semver");
     let git = Package::from_str_ver(
         "serde",     // name
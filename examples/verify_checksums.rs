@@ -0,0 +1,117 @@
+//! Benchmarks fetching a batch of packages from crates.io, with and without verifying checksums of their archives.
+//!
+//! Run it with `cargo run --release --example verify_checksums [<package>...]`. Every run uses a fresh cargo home,
+//! so all the archives are downloaded, `config.toml` of the cargo home of the user is copied into it, so mirrors
+//! configured there are used. Only fetching is timed, packages are resolved beforehand.
+
+use cargo_fetch::{Package, PackageFetcher, PackageSource};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+const PACKAGES: &[&str] = &[
+    "aho-corasick",
+    "anyhow",
+    "base64",
+    "bitflags",
+    "bytes",
+    "cc",
+    "cfg-if",
+    "chrono",
+    "clap",
+    "crossbeam",
+    "either",
+    "futures",
+    "getrandom",
+    "hashbrown",
+    "http",
+    "hyper",
+    "indexmap",
+    "itertools",
+    "itoa",
+    "libc",
+    "log",
+    "memchr",
+    "num-traits",
+    "once_cell",
+    "parking_lot",
+    "proc-macro2",
+    "quote",
+    "rand",
+    "regex",
+    "regex-syntax",
+    "reqwest",
+    "ryu",
+    "semver",
+    "serde",
+    "serde_json",
+    "smallvec",
+    "syn",
+    "thiserror",
+    "time",
+    "tokio",
+    "toml",
+    "tracing",
+    "unicode-ident",
+    "url",
+    "uuid",
+];
+
+fn main() {
+    let names: Vec<String> = env::args().skip(1).collect();
+    let names: Vec<&str> = if names.is_empty() {
+        PACKAGES.to_vec()
+    } else {
+        names.iter().map(String::as_str).collect()
+    };
+
+    let verified = run(&names, true);
+    let unverified = run(&names, false);
+    println!("packages: {}", names.len());
+    println!("verify_checksums(true):  {verified:?}");
+    println!("verify_checksums(false): {unverified:?}");
+}
+
+/// Fetches the newest versions of `names` with a fresh cargo home, returning how long fetching took.
+fn run(names: &[&str], verify: bool) -> Duration {
+    let cargo_home = env::temp_dir().join(format!("cargo_fetch-verify_checksums-{verify}"));
+    if cargo_home.exists() {
+        fs::remove_dir_all(&cargo_home).expect("failed to remove the cargo home");
+    }
+    fs::create_dir_all(&cargo_home).expect("failed to create the cargo home");
+    if let Some(config) = user_cargo_home()
+        .map(|home| home.join("config.toml"))
+        .filter(|c| c.exists())
+    {
+        fs::copy(config, cargo_home.join("config.toml")).expect("failed to copy the cargo config");
+    }
+
+    let mut fetcher = PackageFetcher::builder()
+        .cargo_home(&cargo_home)
+        .verify_checksums(verify)
+        .build()
+        .expect("failed to construct the fetcher");
+    let packages: Vec<Package> = names
+        .iter()
+        .map(|name| {
+            fetcher
+                .resolve_first(name, None, &PackageSource::CratesIo, None)
+                .unwrap_or_else(|e| panic!("can't find {name}: {e}"))
+        })
+        .collect();
+
+    let start = Instant::now();
+    fetcher.fetch_many(&packages).expect("failed to fetch packages");
+    let elapsed = start.elapsed();
+
+    fs::remove_dir_all(&cargo_home).expect("failed to remove the cargo home");
+    elapsed
+}
+
+fn user_cargo_home() -> Option<PathBuf> {
+    env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cargo")))
+}
@@ -0,0 +1,319 @@
+use crate::{not_found, Package, PackageFetcher, PackageSource};
+use cargo::core::{Dependency, PackageId, QueryKind, Source, SourceId};
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    task::Poll,
+};
+
+/// Request to resolve a single package, part of a batch resolved by [`PackageFetcher::resolve_many`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolveRequest {
+    /// Name of the package.
+    pub name: String,
+    /// Version requirement, [`None`] means any version, or "*" semver requirement.
+    pub version: Option<String>,
+    /// Sources to resolve the package from, in the order they are tried in.
+    pub sources: Vec<PackageSource>,
+}
+
+impl ResolveRequest {
+    /// Constructs [`ResolveRequest`] for a package from a single source.
+    pub fn new<N: Into<String>>(name: N, version: Option<&str>, source: PackageSource) -> Self {
+        Self {
+            name: name.into(),
+            version: version.map(str::to_owned),
+            sources: vec![source],
+        }
+    }
+
+    /// Adds a source to try, if the package can't be resolved from the previous ones.
+    pub fn fallback(mut self, source: PackageSource) -> Self {
+        self.sources.push(source);
+        self
+    }
+}
+
+impl PackageFetcher {
+    /// Resolves the newest package version matching the version requirement, same as [`Self::resolve_first`],
+    /// from the first of `sources` it can be resolved from, same as a single [`ResolveRequest`] with fallbacks.
+    ///
+    /// Sources that fail, e.g. a registry that can't be reached, are skipped the same as the ones that don't have
+    /// the package, so the package is resolved as long as any of the sources has it. Errors of all the sources
+    /// are only returned, if none of them do. To never resolve a package from a later source, when an earlier one
+    /// fails, see [`Self::resolve_with_precedence`].
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{PackageFetcher, PackageSource};
+    /// let fetcher = PackageFetcher::new()?;
+    /// let mirror = PackageSource::remote("https://mirror.example.com/index").unwrap();
+    ///
+    /// // Resolved from crates.io, only if the mirror doesn't have it, or can't be reached
+    /// let foo = fetcher.resolve_first_of("foo", Some("1"), &[mirror, PackageSource::crates_io()])?;
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn resolve_first_of<N: AsRef<str>>(
+        &self,
+        name: N,
+        version: Option<&str>,
+        sources: &[PackageSource],
+    ) -> Result<Package, String> {
+        let request = ResolveRequest {
+            name: name.as_ref().to_owned(),
+            version: version.map(str::to_owned),
+            sources: sources.to_vec(),
+        };
+        self.resolve_many(&[request])
+            .pop()
+            .expect("a result is returned for the request")
+    }
+
+    /// Resolves the newest package version matching the version requirement, same as [`Self::resolve_first`],
+    /// from whichever of `sources` resolves it first, for sources that are interchangeable, unlike
+    /// [`Self::resolve_first_of`], which prefers the earlier ones.
+    ///
+    /// All of the sources are queried at once, the same way [`Self::resolve_many`] queries sources of its requests,
+    /// and the first one to resolve the package wins. Sources answering from data already on the disk, e.g. local
+    /// registries, indexes this fetcher already updated, or git repositories locked to a commit, answer right away,
+    /// so the package is resolved from them without waiting for any of the remote ones. Remote sources with pending
+    /// queries are then waited on, see [`Self::resolve_many`] for how much of the waiting overlaps for each kind of
+    /// source. Sources that fail are skipped, and errors of all the sources are only returned, if none of them
+    /// resolve the package.
+    ///
+    /// Once the package is resolved, queries still pending on the other sources are cancelled, by dropping the
+    /// sources. Index files of sparse registries that weren't downloaded yet are never downloaded then, and
+    /// nothing incomplete is written to the cargo home, while indexes and repositories already updated stay so.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{PackageFetcher, PackageSource};
+    /// let fetcher = PackageFetcher::new()?;
+    /// let mirror = PackageSource::remote("https://mirror.example.com/index").unwrap();
+    ///
+    /// let foo = fetcher.resolve_any_of("foo", Some("1"), &[mirror, PackageSource::crates_io()])?;
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn resolve_any_of<N: AsRef<str>>(
+        &self,
+        name: N,
+        version: Option<&str>,
+        sources: &[PackageSource],
+    ) -> Result<Package, String> {
+        let name = name.as_ref();
+        if sources.is_empty() {
+            return Err(format!("no sources to resolve `{name}` from"));
+        }
+        let _lock = self.lock_package_cache()?;
+
+        let requests: Vec<ResolveRequest> = sources
+            .iter()
+            .map(|source| ResolveRequest::new(name, version, source.clone()))
+            .collect();
+        let mut loaded = HashMap::new();
+        let mut states: Vec<RequestState> = requests.iter().map(|_| RequestState::default()).collect();
+        let mut pending: Vec<usize> = (0..requests.len()).collect();
+        while !pending.is_empty() {
+            for i in std::mem::take(&mut pending) {
+                match self.poll_request(&requests[i], &mut states[i], &mut loaded) {
+                    Poll::Ready(Ok(package)) => return Ok(package),
+                    Poll::Ready(Err(_)) => {},
+                    Poll::Pending => pending.push(i),
+                }
+            }
+
+            let mut waiting = std::mem::take(&mut pending);
+            waiting.sort_by_key(|&i| {
+                let source_id = states[i].waiting_on.expect("pending request waits on a source");
+                !self.is_on_disk(&*loaded[&source_id])
+            });
+            for i in waiting {
+                let source_id = states[i].waiting_on.expect("pending request waits on a source");
+                let source = loaded.get_mut(&source_id).expect("waited on source is loaded");
+                if let Err(e) = source.block_until_ready() {
+                    states[i].fail(&requests[i], e.to_string());
+                    continue;
+                }
+                match self.poll_request(&requests[i], &mut states[i], &mut loaded) {
+                    Poll::Ready(Ok(package)) => return Ok(package),
+                    Poll::Ready(Err(_)) => {},
+                    Poll::Pending => pending.push(i),
+                }
+            }
+        }
+
+        let errors: String = states.iter().map(|state| state.errors.as_str()).collect();
+        Err(format!(
+            "`{name}` couldn't be resolved from any of its sources:{errors}"
+        ))
+    }
+
+    /// Resolves a batch of packages, each with [`Self::resolve_first`] from its own sources, returning
+    /// a result for each of the requests, in the same order.
+    ///
+    /// Sources of a request are tried in order, until the package is resolved from one of them.
+    /// If it can't be resolved from any, the error lists the errors of all the sources.
+    ///
+    /// Up to [`PackageFetcherBuilder::resolve_concurrency`](crate::PackageFetcherBuilder::resolve_concurrency)
+    /// requests are resolved at once. Cargo sources can't be used from multiple threads, so requests are resolved
+    /// on the current thread, by querying sources of all of them, and waiting for every source with pending queries
+    /// once, until they are resolved. Each source is loaded only once per batch. Whether waiting for queries at once
+    /// is faster depends on the source:
+    /// * Sparse registries download index files of the pending queries concurrently, so the speedup is up to
+    ///   the limit for servers supporting HTTP/2. For other servers it's at most 2, as cargo opens at most
+    ///   2 connections per host, and more than one only if `http.multiplexing` is disabled. Sparse registries are
+    ///   only supported with the `sparse-registry` unstable feature in the cargo version this crate is built on.
+    /// * Git registries are updated once, when they are first queried, and then read from the disk, so there is no
+    ///   speedup beyond loading them once.
+    /// * Git repositories are fetched once, when they are first queried, so there is no speedup either.
+    pub fn resolve_many(&self, requests: &[ResolveRequest]) -> Vec<Result<Package, String>> {
+        let _lock = match self.lock_package_cache() {
+            Ok(lock) => lock,
+            Err(e) => return requests.iter().map(|_| Err(e.clone())).collect(),
+        };
+
+        let mut sources = HashMap::new();
+        let mut states: Vec<RequestState> = requests.iter().map(|_| RequestState::default()).collect();
+        let mut results: Vec<Option<Result<Package, String>>> = requests.iter().map(|_| None).collect();
+        let mut queued = 0..requests.len();
+        let mut active = vec![];
+        loop {
+            active.extend(queued.by_ref().take(self.resolve_concurrency - active.len()));
+            if active.is_empty() {
+                break;
+            }
+
+            let mut waiting: HashMap<SourceId, Vec<usize>> = HashMap::new();
+            for i in std::mem::take(&mut active) {
+                match self.poll_request(&requests[i], &mut states[i], &mut sources) {
+                    Poll::Ready(result) => results[i] = Some(result),
+                    Poll::Pending => {
+                        let source_id = states[i].waiting_on.expect("pending request waits on a source");
+                        waiting.entry(source_id).or_default().push(i);
+                        active.push(i);
+                    },
+                }
+            }
+
+            for (source_id, waiting) in waiting {
+                let source = sources.get_mut(&source_id).expect("waited on source is loaded");
+                if let Err(e) = source.block_until_ready() {
+                    // Requests move on to their next sources.
+                    for i in waiting {
+                        states[i].fail(&requests[i], e.to_string());
+                    }
+                }
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|result| result.expect("all requests are resolved"))
+            .collect()
+    }
+
+    /// Whether `source` answers queries from data already on the disk, without accessing the network.
+    fn is_on_disk(&self, source: &dyn Source) -> bool {
+        let source_id = source.replaced_source_id();
+        if source_id.is_git() {
+            // Cargo only fetches repositories locked to a commit, if they don't have it yet.
+            source_id.precise().is_some()
+        } else if source_id.is_remote_registry() {
+            self.config.updated_sources().contains(&source_id)
+        } else {
+            true
+        }
+    }
+
+    /// Queries the current source of a request, moving on to the next ones until the package is resolved,
+    /// or a query is pending.
+    fn poll_request<'a>(
+        &'a self,
+        request: &ResolveRequest,
+        state: &mut RequestState,
+        sources: &mut HashMap<SourceId, Box<dyn Source + 'a>>,
+    ) -> Poll<Result<Package, String>> {
+        while let Some(source) = request.sources.get(state.source) {
+            state.waiting_on = None;
+            let source_id = match self.source_id(source) {
+                Ok(source_id) => source_id,
+                Err(e) => {
+                    state.fail(request, e.to_string());
+                    continue;
+                },
+            };
+            let source = match sources.entry(source_id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => match self.load_source(source_id, &HashSet::new()) {
+                    Ok(source) => entry.insert(source),
+                    Err(e) => {
+                        state.fail(request, e);
+                        continue;
+                    },
+                },
+            };
+            let dep = match Dependency::parse(request.name.as_str(), request.version.as_deref(), source.source_id()) {
+                Ok(dep) => dep,
+                Err(e) => {
+                    state.fail(request, e.to_string());
+                    continue;
+                },
+            };
+
+            let mut package: Option<PackageId> = None;
+            let queried = source.query(&dep, QueryKind::Exact, &mut |sum| {
+                let package_id = sum.package_id();
+                if package.map_or(true, |package| self.is_newer(package_id.version(), package.version())) {
+                    package = Some(package_id);
+                }
+            });
+            match queried {
+                Poll::Pending => {
+                    state.waiting_on = Some(source_id);
+                    return Poll::Pending;
+                },
+                Poll::Ready(Err(e)) => state.fail(request, e.to_string()),
+                Poll::Ready(Ok(())) => match package {
+                    Some(package_id) => {
+                        return Poll::Ready(self.check_minimum(Package { package_id }).map_err(String::from))
+                    },
+                    None => {
+                        let error = not_found(&mut **source, &request.name, request.version.as_deref());
+                        state.fail(request, error.to_string())
+                    },
+                },
+            }
+        }
+
+        if state.errors.is_empty() {
+            Poll::Ready(Err(format!("no sources to resolve `{}` from", request.name)))
+        } else {
+            Poll::Ready(Err(format!(
+                "`{}` couldn't be resolved from any of its sources:{}",
+                request.name, state.errors
+            )))
+        }
+    }
+}
+
+/// Progress of resolving a [`ResolveRequest`].
+#[derive(Default)]
+struct RequestState {
+    /// Index of the source being tried.
+    source: usize,
+    /// Errors of the already tried sources.
+    errors: String,
+    /// Source with a pending query for the request.
+    waiting_on: Option<SourceId>,
+}
+
+impl RequestState {
+    /// Records the error of the current source, and moves on to the next one.
+    fn fail(&mut self, request: &ResolveRequest, error: String) {
+        let source = &request.sources[self.source];
+        let source = match source.to_source_id() {
+            Ok(source_id) => source_id.as_url().to_string(),
+            Err(_) => format!("{source:?}"),
+        };
+        self.errors.push_str(&format!("\n  {source}: {error}"));
+        self.source += 1;
+        self.waiting_on = None;
+    }
+}
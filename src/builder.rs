@@ -0,0 +1,659 @@
+use crate::{snapshot::IndexSnapshot, IndexFreshness, LockBehavior, PackageFetcher, RegistryIndexConfig, Verbosity};
+use cargo::util::CanonicalUrl;
+use semver::Version;
+use std::{
+    collections::HashMap,
+    env, fmt,
+    io::Write,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+use url::Url;
+
+/// Builder for [`PackageFetcher`], for when the defaults of [`PackageFetcher::new`] are not enough.
+///
+/// Can be constructed with [`PackageFetcher::builder`].
+#[derive(Default)]
+pub struct PackageFetcherBuilder {
+    out: Option<(Box<dyn Write>, Option<Verbosity>)>,
+    registry_index_configs: HashMap<Url, RegistryIndexConfig>,
+    registry_index_revisions: Vec<(Url, String)>,
+    git_backend: Option<GitBackend>,
+    keep_on_failure: bool,
+    default_members_only: bool,
+    offline: Option<bool>,
+    net_retries: Option<u32>,
+    cargo_home: Option<PathBuf>,
+    lock_behavior: LockBehavior,
+    max_crate_size: Option<u64>,
+    registry_auth: bool,
+    shared_git_cache: Option<PathBuf>,
+    pin_git_revisions: bool,
+    git_env: HashMap<String, String>,
+    normalized_mtime: Option<SystemTime>,
+    normalize_line_endings: bool,
+    resolve_concurrency: Option<usize>,
+    verify_checksums: Option<bool>,
+    index_freshness: Option<IndexFreshness>,
+    ca_bundle: Option<PathBuf>,
+    jobs: Option<usize>,
+    progress_interval: Option<Duration>,
+    minimum_versions: HashMap<String, Version>,
+    config_overrides: Vec<String>,
+}
+
+impl PackageFetcherBuilder {
+    /// Constructs [`PackageFetcherBuilder`] with default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Constructs [`PackageFetcherBuilder`] with options read from the environment variables of the process.
+    ///
+    /// Recognized variables are:
+    /// * `CARGO_FETCH_OFFLINE`: `true` or `false`, see [`Self::offline`]
+    /// * `CARGO_FETCH_RETRIES`: number of retries, see [`Self::net_retries`]
+    /// * `CARGO_FETCH_CARGO_HOME`: path to the cargo home, see [`Self::cargo_home`]
+    /// * `CARGO_FETCH_GIT_BACKEND`: `libgit2` or `git-cli`, see [`Self::git_backend`]
+    ///
+    /// Unset variables leave the options at their defaults. The variables take precedence over the cargo config
+    /// (including cargo's own environment variables, e.g. `CARGO_NET_OFFLINE`), and options set on the builder
+    /// afterwards take precedence over the variables.
+    ///
+    /// Errors, if any of the variables has an invalid value.
+    pub fn from_env() -> Result<Self, String> {
+        let mut builder = Self::new();
+        if let Some(offline) = env_var("CARGO_FETCH_OFFLINE")? {
+            builder = builder.offline(match offline.as_str() {
+                "true" => true,
+                "false" => false,
+                _ => {
+                    return Err(format!(
+                        "`CARGO_FETCH_OFFLINE` must be `true` or `false`, got `{offline}`"
+                    ))
+                },
+            });
+        }
+        if let Some(retries) = env_var("CARGO_FETCH_RETRIES")? {
+            builder = builder.net_retries(
+                retries
+                    .parse()
+                    .map_err(|e| format!("`CARGO_FETCH_RETRIES` is not a valid number of retries: {e}"))?,
+            );
+        }
+        if let Some(cargo_home) = env_var("CARGO_FETCH_CARGO_HOME")? {
+            builder = builder.cargo_home(cargo_home);
+        }
+        if let Some(backend) = env_var("CARGO_FETCH_GIT_BACKEND")? {
+            builder = builder.git_backend(match backend.as_str() {
+                "libgit2" => GitBackend::Libgit2,
+                "git-cli" => GitBackend::GitCli,
+                _ => {
+                    return Err(format!(
+                        "`CARGO_FETCH_GIT_BACKEND` must be `libgit2` or `git-cli`, got `{backend}`"
+                    ))
+                },
+            });
+        }
+        Ok(builder)
+    }
+
+    /// Sets user-provided stream for cargo to output status to, see [`PackageFetcher::with_out`].
+    pub fn out(mut self, out: Box<dyn Write>, verbosity: Option<Verbosity>) -> Self {
+        self.out = Some((out, verbosity));
+        self
+    }
+
+    /// Registers overrides for the `config.json` of the registry with the given index url.
+    ///
+    /// See [`RegistryIndexConfig`] for how the overrides are applied. Registering overrides for the same
+    /// index twice replaces the previous ones.
+    pub fn registry_index_config(mut self, index: Url, config: RegistryIndexConfig) -> Self {
+        self.registry_index_configs.insert(index, config);
+        self
+    }
+
+    /// Pins the git index of the registry with the given index url to a commit, for reproducible resolution.
+    ///
+    /// Only the versions published as of the commit are available then, regardless of what's published later.
+    /// The commit is fetched into a snapshot of the index in the cargo home the first time it's used, afterwards
+    /// the snapshot works offline too. The snapshot replaces the index with cargo's source replacement, packages are
+    /// still downloaded from the `dl` url of the registry. [`RegistryIndexConfig`] overrides apply to the url of the
+    /// snapshot, as reported by [`PackageFetcher::effective_source_url`].
+    ///
+    /// Sparse indexes (`sparse+https://...`) are served as separate files without history, so they can't be pinned,
+    /// and [`PackageFetcherBuilder::build`] errors for them. crates.io can be pinned by the url of its git index
+    /// instead, for other sparse registries a lock file is the only way to pin the resolved versions,
+    /// see [`ResolveOptions`](crate::ResolveOptions).
+    ///
+    /// `commit` must be a full commit hash. Pinning the same index twice pins it to the last commit.
+    pub fn registry_index_revision<C: Into<String>>(mut self, index: Url, commit: C) -> Self {
+        self.registry_index_revisions.retain(|(url, _)| *url != index);
+        self.registry_index_revisions.push((index, commit.into()));
+        self
+    }
+
+    /// Selects the backend cargo uses to fetch git repositories.
+    ///
+    /// By default, the backend is selected by the cargo config, which is [`GitBackend::Libgit2`],
+    /// unless `net.git-fetch-with-cli` is set.
+    pub fn git_backend(mut self, backend: GitBackend) -> Self {
+        self.git_backend = Some(backend);
+        self
+    }
+
+    /// Lists only the `default-members` of workspaces in [`PackageFetcher::git_members`], the packages `cargo build`
+    /// builds in the workspace root without `--workspace`, instead of all members.
+    ///
+    /// Same as cargo, these are all members of a virtual workspace without `default-members`, and only the root
+    /// package of a workspace with one. Packages of a repository that aren't in a workspace are all listed.
+    /// Resolving packages by name isn't affected, any member can still be resolved. Disabled by default.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{PackageFetcherBuilder, PackageSource};
+    /// let fetcher = PackageFetcherBuilder::new().default_members_only(true).build()?;
+    /// let source = PackageSource::git("https://github.com/serde-rs/serde", None).unwrap();
+    ///
+    /// for member in fetcher.git_members(&source)? {
+    ///     println!("{}", member.lock_id());
+    /// }
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn default_members_only(mut self, default_only: bool) -> Self {
+        self.default_members_only = default_only;
+        self
+    }
+
+    /// Reports incomplete extractions of registry packages, when fetching them fails.
+    ///
+    /// Cargo doesn't remove partially extracted packages, but extracts over them the next time they are fetched.
+    /// When set, paths of those left by a failed fetch are logged as warnings, so they can be inspected
+    /// before fetching again. Disabled by default.
+    pub fn keep_on_failure(mut self, keep: bool) -> Self {
+        self.keep_on_failure = keep;
+        self
+    }
+
+    /// Disables network access, same as `--offline`.
+    ///
+    /// By default, it's controlled by the `net.offline` cargo config.
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = Some(offline);
+        self
+    }
+
+    /// Sets the number of times cargo retries failed network requests.
+    ///
+    /// Cargo retries timeouts, failed connections and 5xx responses. Missing packages and git references, and
+    /// registries rejecting the credentials fail right away. Cargo retries every network failure of libgit2, so git
+    /// repositories over HTTP rejecting the credentials are retried too, see [`ResolveError`](crate::ResolveError)
+    /// for how the failures are told apart.
+    ///
+    /// By default, it's controlled by the `net.retry` cargo config.
+    pub fn net_retries(mut self, retries: u32) -> Self {
+        self.net_retries = Some(retries);
+        self
+    }
+
+    /// Sets the cargo home directory, cargo config is read from and packages are cached in.
+    ///
+    /// By default, `CARGO_HOME`, or `.cargo` in the home directory of the user is used.
+    pub fn cargo_home<P: Into<PathBuf>>(mut self, cargo_home: P) -> Self {
+        self.cargo_home = Some(cargo_home.into());
+        self
+    }
+
+    /// Sets what to do, when the package cache is locked by another process.
+    ///
+    /// By default, the lock is waited for, see [`LockBehavior`].
+    pub fn lock_behavior(mut self, behavior: LockBehavior) -> Self {
+        self.lock_behavior = behavior;
+        self
+    }
+
+    /// Sets the maximum size of `.crate` archives downloaded from remote registries, in bytes.
+    ///
+    /// Fetching a package with a larger archive fails, before the archive is extracted or cached. Registry indexes of
+    /// the cargo version this crate is built on don't contain sizes of the archives, so they are checked while
+    /// downloading: downloads are rejected before they start, if the server reports a larger size, and aborted
+    /// once they exceed the limit otherwise. Cargo only checks a download once it's complete, so with a limit,
+    /// archives are downloaded by the fetcher itself, one at a time, instead of concurrently by cargo. Archives
+    /// cached before the limit was set are checked too. Unlimited by default.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Package, PackageFetcherBuilder, PackageSource, ResolveError};
+    /// let mut fetcher = PackageFetcherBuilder::new().max_crate_size(10 * 1024 * 1024).build()?;
+    /// let foo = Package::from_str_ver("foo", "0.1.0", &PackageSource::crates_io())?;
+    ///
+    /// match fetcher.fetch(foo) {
+    ///     Ok(root) => println!("fetched into {}", root.display()),
+    ///     Err(ResolveError::TooLarge { size, limit, .. }) => println!("{size} bytes is over {limit} bytes"),
+    ///     Err(e) => return Err(e.into()),
+    /// }
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn max_crate_size(mut self, bytes: u64) -> Self {
+        self.max_crate_size = Some(bytes);
+        self
+    }
+
+    /// Sets whether packages can be downloaded from registries that require authentication, `false` by default.
+    ///
+    /// Authenticated registries are an unstable feature of the cargo version this crate is built on (they were
+    /// stabilized in cargo 1.74), so this enables cargo's `-Z registry-auth`, on any toolchain. Without it, cargo
+    /// doesn't send tokens to registries, even if they have `auth-required` in their `config.json`, so downloads
+    /// from them fail, no credential providers are run (see [`PackageFetcher`] for the supported ones), and
+    /// [`PackageSource::RegistryWithAuth`](crate::PackageSource::RegistryWithAuth) can't be used.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{PackageFetcherBuilder, PackageSource};
+    /// let fetcher = PackageFetcherBuilder::new().registry_auth(true).build()?;
+    /// let source = PackageSource::remote_with_token("https://registry.example.com/index", "token").unwrap();
+    ///
+    /// let foo = fetcher.resolve_first("foo", None, &source, None)?;
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn registry_auth(mut self, enabled: bool) -> Self {
+        self.registry_auth = enabled;
+        self
+    }
+
+    /// Sets a directory to share checkouts of git packages in, between fetchers with different cargo homes.
+    ///
+    /// Checkouts are stored in the directory by the full hash of their commit. Packages from a git source locked
+    /// to a commit (e.g. [`GitReference::Revision`](crate::GitReference::Revision) with a full hash) are loaded
+    /// from it without accessing the repository, others are fetched by cargo first, and then copied into it.
+    /// Roots of git packages are in this directory then, except for [`PackageFetcher::fetch_many`] and
+    /// [`PackageFetcher::bundle`], which always use the cargo's checkouts.
+    ///
+    /// Checkouts are never modified once stored, and are stored by atomically renaming a complete copy, so the directory
+    /// can be used by multiple processes at once without locking. Nothing is ever removed from it.
+    pub fn shared_git_cache<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.shared_git_cache = Some(dir.into());
+        self
+    }
+
+    /// Sets whether git sources that aren't locked to a commit are locked to the commit they are first resolved to,
+    /// for all the following operations of the fetcher, `false` by default.
+    ///
+    /// Cargo fetches a git repository every time a source of it is loaded, so e.g. resolving and then fetching
+    /// a package from a branch fetches the repository twice, and can see different commits. With this set, the
+    /// repository is only fetched once, but new commits pushed in the meantime aren't seen, until
+    /// [`PackageFetcher::clear_source_cache`] is called. Only git sources are affected, registry sources are loaded
+    /// for every operation, and their indexes are refreshed according to [`Self::index_freshness`].
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{PackageFetcherBuilder, PackageSource};
+    /// let mut fetcher = PackageFetcherBuilder::new().pin_git_revisions(true).build()?;
+    /// let source = PackageSource::git("https://github.com/serde-rs/serde", None).unwrap();
+    ///
+    /// // Both from the same commit
+    /// let serde = fetcher.resolve_first("serde", None, &source, None)?;
+    /// let root = fetcher.fetch(serde)?;
+    ///
+    /// // Sees new commits again
+    /// fetcher.clear_source_cache();
+    /// # Ok::<(), cargo_fetch::ResolveError>(())
+    /// ```
+    pub fn pin_git_revisions(mut self, pin: bool) -> Self {
+        self.pin_git_revisions = pin;
+        self
+    }
+
+    /// Sets environment variables for git operations of the fetcher, e.g. for credential helpers reading a token
+    /// from them, or `GIT_ASKPASS`. Setting them again replaces the previous ones.
+    ///
+    /// Cargo runs git operations in the fetcher's process, and credential helpers and [`GitBackend::GitCli`] inherit
+    /// its environment, so the variables are set in the environment of the process, while the fetcher uses git
+    /// sources or lists references of a git repository, including resolving the git dependencies of a graph, and the
+    /// previous values are restored then. The values of the variables aren't included in the [`fmt::Debug`] output
+    /// of the builder.
+    ///
+    /// **The environment is shared by the whole process.** Other threads see the variables while they are set,
+    /// and reading or modifying the environment from other threads meanwhile is a data race on some platforms, so
+    /// other threads mustn't access it while the fetcher is used, including through libraries reading it, e.g.
+    /// for resolving host names. Fetchers with their own variables mustn't be used at once from multiple threads.
+    pub fn git_env(mut self, vars: HashMap<String, String>) -> Self {
+        self.git_env = vars;
+        self
+    }
+
+    /// Sets the modification time of the files of fetched packages, so extracting the same package on different
+    /// machines, or at different times, produces the same tree, e.g. for comparing or hashing the sources with
+    /// tools taking the modification times into account. Disabled by default.
+    ///
+    /// Only packages cargo extracted or checked out into the cargo home are changed, path packages, vendored
+    /// packages, and checkouts in the [`Self::shared_git_cache`] are left as they are. Modification times are
+    /// set every time a package is fetched with [`PackageFetcher::fetch`], [`PackageFetcher::resolve_and_fetch`],
+    /// [`PackageFetcher::fetch_many`] or [`PackageFetcher::fetch_many_with_progress`], symbolic links and
+    /// directories are skipped.
+    ///
+    /// Packages are changed in place, and cargo builds everything using the same cargo home from them, so it
+    /// requires a cargo home of the fetcher's own, set with [`Self::cargo_home`], [`Self::build`] errors otherwise.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Package, PackageFetcherBuilder, PackageSource};
+    /// # use std::time::SystemTime;
+    /// let mut fetcher = PackageFetcherBuilder::new()
+    ///     .cargo_home("/tmp/cargo-home")
+    ///     .normalized_mtime(SystemTime::UNIX_EPOCH)
+    ///     .normalize_line_endings(true)
+    ///     .build()?;
+    ///
+    /// let foo = Package::from_str_ver("foo", "0.1.0", &PackageSource::crates_io())?;
+    /// let root = fetcher.fetch(foo)?;
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn normalized_mtime(mut self, mtime: SystemTime) -> Self {
+        self.normalized_mtime = Some(mtime);
+        self
+    }
+
+    /// Sets whether `\r\n` line endings in text files of fetched packages are converted to `\n`, `false` by default,
+    /// so packages checked out on Windows, where git may convert line endings, have the same contents as elsewhere.
+    ///
+    /// Files are considered text if they are valid UTF-8 without zero bytes. The same packages are changed as with
+    /// [`Self::normalized_mtime`], and it's applied before the modification times are set. Same as it, it requires
+    /// a cargo home of the fetcher's own.
+    pub fn normalize_line_endings(mut self, normalize: bool) -> Self {
+        self.normalize_line_endings = normalize;
+        self
+    }
+
+    /// Sets how many requests [`PackageFetcher::resolve_many`] resolves at once, 1 by default, 0 is treated as 1.
+    ///
+    /// See [`PackageFetcher::resolve_many`] for when resolving requests at once is faster.
+    pub fn resolve_concurrency(mut self, limit: usize) -> Self {
+        self.resolve_concurrency = Some(limit);
+        self
+    }
+
+    /// Sets whether checksums of `.crate` archives downloaded from remote registries are verified, `true` by default.
+    ///
+    /// **Disabling this is dangerous.** Without verification, a compromised registry mirror, proxy or network can
+    /// substitute the sources of any package, and the substituted archive is cached and used by cargo too, as cargo
+    /// doesn't verify cached archives. Only disable it in fully trusted pipelines, e.g. a local mirror, where
+    /// the archives are already verified, and even then, the gain is small: hashing an archive is much cheaper than
+    /// downloading and extracting it.
+    ///
+    /// Archives downloaded from a [`RegistryIndexConfig::dl`] override, and packages from local registries are always
+    /// verified.
+    pub fn verify_checksums(mut self, verify: bool) -> Self {
+        self.verify_checksums = Some(verify);
+        self
+    }
+
+    /// Sets when remote registry indexes are refreshed before they are queried, see [`IndexFreshness`] for how
+    /// it applies to git and sparse indexes.
+    ///
+    /// By default, cargo decides it: methods querying an index directly (e.g. [`PackageFetcher::resolve_first`])
+    /// use its cached copy, fetching only what isn't cached, and [`PackageFetcher::resolve_graph`] refreshes each
+    /// index once per fetcher. When set, it applies to both, except that the first [`PackageFetcher::resolve_graph`]
+    /// using a git index refreshes it regardless, and every [`PackageFetcher::resolve_graph`] using a sparse index
+    /// refreshes the files it needs, as cargo's resolver requests it.
+    pub fn index_freshness(mut self, freshness: IndexFreshness) -> Self {
+        self.index_freshness = Some(freshness);
+        self
+    }
+
+    /// Sets a file with certificates of the certificate authorities to trust for HTTPS, in PEM format, same as the
+    /// `http.cainfo` cargo config, e.g. for a registry with a certificate signed by a private CA.
+    ///
+    /// It replaces the system trust store for registry indexes, downloads and git repositories fetched with
+    /// [`GitBackend::Libgit2`], git repositories fetched with [`GitBackend::GitCli`] use the config of `git` instead.
+    ///
+    /// **It applies process-wide to git fetches.** `libgit2` can only be given one HTTP transport per process, the
+    /// first [`PackageFetcher`] built with a CA bundle registers cargo's transport configured by its cargo config,
+    /// which all the later fetches over HTTPS of all the fetchers use, regardless of their own CA bundles. It must not
+    /// be built while other threads fetch git repositories with `libgit2`.
+    pub fn ca_bundle<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.ca_bundle = Some(path.into());
+        self
+    }
+
+    /// Sets the number of jobs operations building packages run in parallel, same as the `build.jobs` cargo config,
+    /// or `cargo build --jobs`.
+    ///
+    /// Only the verification build of [`PackageFetcher::package_crate`] builds packages, fetching and resolving
+    /// aren't affected. By default, it's controlled by the `build.jobs` cargo config, which defaults to the number
+    /// of CPUs. Building fails with 0.
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    /// Sets the minimum time between the calls of the progress callback of
+    /// [`PackageFetcher::fetch_many_with_progress`], 100 milliseconds by default, so fast downloads don't report
+    /// progress faster than it can be displayed.
+    ///
+    /// Packages finishing within the interval since the last call aren't reported separately, the next call counts
+    /// them in its [`FetchProgress::fetched`]. The first and the last package are always reported. Progress is only
+    /// reported once packages finish, as cargo doesn't report the bytes of downloads in progress. [`Duration::ZERO`]
+    /// reports every package.
+    ///
+    /// [`FetchProgress::fetched`]: crate::FetchProgress::fetched
+    pub fn progress_interval(mut self, interval: Duration) -> Self {
+        self.progress_interval = Some(interval);
+        self
+    }
+
+    /// Sets the minimum version of the package named `name` resolving is allowed to select, regardless of its source,
+    /// e.g. to never select versions with known vulnerabilities. Setting it for the same name twice replaces the
+    /// previous one.
+    ///
+    /// Methods selecting a version (e.g. [`PackageFetcher::resolve_first`]) error with
+    /// [`ResolveError::BelowMinimum`](crate::ResolveError::BelowMinimum), if the version they select is lower.
+    /// The version is checked after selecting it, so an older version isn't selected instead, and so are all
+    /// the resolved packages of [`PackageFetcher::resolve_graph`]. Methods listing versions, e.g.
+    /// [`PackageFetcher::resolve_package`], aren't affected.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{PackageFetcherBuilder, PackageSource, ResolveError};
+    /// let fetcher = PackageFetcherBuilder::new()
+    ///     .minimum_version("openssl", "0.10.55".parse().unwrap())
+    ///     .build()?;
+    ///
+    /// match fetcher.resolve_first("openssl", Some("=0.10.50"), &PackageSource::crates_io(), None) {
+    ///     Err(ResolveError::BelowMinimum { floor, .. }) => println!("versions before {floor} aren't allowed"),
+    ///     resolved => println!("{resolved:?}"),
+    /// }
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn minimum_version<N: Into<String>>(mut self, name: N, floor: Version) -> Self {
+        self.minimum_versions.insert(name.into(), floor);
+        self
+    }
+
+    /// Adds an inline cargo config override, same as `cargo --config <override>`, e.g. `net.retry=5`, or a path to
+    /// an extra config file.
+    ///
+    /// Overrides are parsed by cargo, when the fetcher is built, so building errors, if an override isn't a valid
+    /// TOML `key = value` pair, while values of the wrong type error when cargo reads them, same as the values of
+    /// config files. They take precedence over the cargo config files and cargo's
+    /// environment variables, later overrides over earlier ones, except for arrays, which are concatenated, same as
+    /// in cargo. Options set on the builder take precedence over the overrides of the same config keys.
+    ///
+    /// ```
+    /// # use cargo_fetch::PackageFetcherBuilder;
+    /// let builder = PackageFetcherBuilder::new()
+    ///     .config_override("source.crates-io.replace-with = 'mirror'")
+    ///     .config_override("source.mirror.registry = 'https://mirror.example.com/index'");
+    /// ```
+    pub fn config_override<S: Into<String>>(mut self, config: S) -> Self {
+        self.config_overrides.push(config.into());
+        self
+    }
+
+    /// Constructs [`PackageFetcher`] with the configured options.
+    pub fn build(self) -> Result<PackageFetcher, String> {
+        if (self.normalized_mtime.is_some() || self.normalize_line_endings) && self.cargo_home.is_none() {
+            return Err("normalizing packages requires a cargo home of the fetcher's own, see `cargo_home`".into());
+        }
+        let mut config = match self.cargo_home {
+            Some(cargo_home) => {
+                let cwd = env::current_dir().map_err(|e| e.to_string())?;
+                cargo::Config::new(cargo::core::Shell::new(), cwd, cargo_home)
+            },
+            None => cargo::Config::default().map_err(|e| e.to_string())?,
+        };
+
+        // Cargo merges the later values into the earlier ones, so options of the builder come after the overrides.
+        let mut cli_config = self.config_overrides;
+        if let Some(offline) = self.offline {
+            cli_config.push(format!("net.offline={offline}"));
+        }
+        if let Some(retries) = self.net_retries {
+            cli_config.push(format!("net.retry={retries}"));
+        }
+        if let Some(backend) = self.git_backend {
+            cli_config.push(format!("net.git-fetch-with-cli={}", backend == GitBackend::GitCli));
+        }
+        if let Some(jobs) = self.jobs {
+            cli_config.push(format!("build.jobs={jobs}"));
+        }
+        if let Some(ca_bundle) = &self.ca_bundle {
+            let ca_bundle = ca_bundle
+                .to_str()
+                .ok_or_else(|| format!("CA bundle path `{}` is not valid UTF-8", ca_bundle.display()))?;
+            cli_config.push(format!("http.cainfo={ca_bundle:?}"));
+        }
+        let mut snapshots = vec![];
+        for (n, (index, commit)) in self.registry_index_revisions.into_iter().enumerate() {
+            let snapshot = IndexSnapshot::new(&config, index, commit)?;
+            cli_config.extend(snapshot.replacement(&config, n)?);
+            snapshots.push(snapshot);
+        }
+        // Authenticated registries are unstable in the cargo version this crate is built on, they were stabilized
+        // in cargo 1.74, along with credential providers, which are implemented by this crate.
+        let unstable_flags: &[String] = if self.registry_auth {
+            &["registry-auth".to_owned()]
+        } else {
+            &[]
+        };
+        let nightly_features_allowed = config.nightly_features_allowed;
+        config.nightly_features_allowed |= self.registry_auth;
+        // `configure` also resets the verbosity of the shell from the `term` config.
+        let verbosity = config.shell().verbosity();
+        let configured = config.configure(
+            0,
+            false,
+            None,
+            false,
+            false,
+            false,
+            &None,
+            unstable_flags,
+            &cli_config,
+        );
+        config.nightly_features_allowed = nightly_features_allowed;
+        configured.map_err(|e| e.to_string())?;
+        config.shell().set_verbosity(verbosity);
+
+        if self.ca_bundle.is_some() {
+            // Same as the cargo binary does, as `libgit2` doesn't read the cargo config.
+            let handle = cargo::ops::http_handle(&config).map_err(|e| e.to_string())?;
+            // SAFETY: documented to not be called while other threads create `libgit2` transports, and `git2_curl`
+            // ignores handles registered after the first one.
+            unsafe { git2_curl::register(handle) };
+        }
+
+        if let Some((out, verbosity)) = self.out {
+            let mut shell = cargo::core::Shell::from_write(out);
+            shell.set_verbosity(verbosity.unwrap_or_default().into());
+            *config.shell() = shell;
+        }
+
+        let registry_index_configs = self
+            .registry_index_configs
+            .into_iter()
+            .map(|(url, config)| Ok((CanonicalUrl::new(&url)?, config)))
+            .collect::<cargo::CargoResult<_>>()
+            .map_err(|e| e.to_string())?;
+
+        let fetcher = PackageFetcher {
+            config,
+            registry_index_configs,
+            keep_on_failure: self.keep_on_failure,
+            default_members_only: self.default_members_only,
+            lock_behavior: self.lock_behavior,
+            max_crate_size: self.max_crate_size,
+            shared_git_cache: self.shared_git_cache,
+            pin_git_revisions: self.pin_git_revisions,
+            git_env: self.git_env,
+            git_env_guard: Default::default(),
+            normalized_mtime: self.normalized_mtime,
+            normalize_line_endings: self.normalize_line_endings,
+            resolve_concurrency: self.resolve_concurrency.unwrap_or(1).max(1),
+            verify_checksums: self.verify_checksums.unwrap_or(true),
+            index_freshness: self.index_freshness,
+            progress_interval: self.progress_interval.unwrap_or(Duration::from_millis(100)),
+            minimum_versions: self.minimum_versions,
+            url_rewriter: None,
+            version_ordering: None,
+            cache_locks: Default::default(),
+            scoped_tokens: Default::default(),
+            git_revisions: Default::default(),
+            downloaded_bytes: Default::default(),
+        };
+        for snapshot in &snapshots {
+            fetcher.prepare_snapshot(snapshot)?;
+        }
+        Ok(fetcher)
+    }
+}
+
+impl fmt::Debug for PackageFetcherBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PackageFetcherBuilder")
+            .field("verbosity", &self.out.as_ref().map(|(_, verbosity)| verbosity))
+            .field("registry_index_configs", &self.registry_index_configs)
+            .field("registry_index_revisions", &self.registry_index_revisions)
+            .field("git_backend", &self.git_backend)
+            .field("keep_on_failure", &self.keep_on_failure)
+            .field("default_members_only", &self.default_members_only)
+            .field("offline", &self.offline)
+            .field("net_retries", &self.net_retries)
+            .field("cargo_home", &self.cargo_home)
+            .field("lock_behavior", &self.lock_behavior)
+            .field("max_crate_size", &self.max_crate_size)
+            .field("registry_auth", &self.registry_auth)
+            .field("shared_git_cache", &self.shared_git_cache)
+            .field("pin_git_revisions", &self.pin_git_revisions)
+            .field("git_env", &self.git_env.keys().collect::<Vec<_>>())
+            .field("normalized_mtime", &self.normalized_mtime)
+            .field("normalize_line_endings", &self.normalize_line_endings)
+            .field("resolve_concurrency", &self.resolve_concurrency)
+            .field("verify_checksums", &self.verify_checksums)
+            .field("index_freshness", &self.index_freshness)
+            .field("ca_bundle", &self.ca_bundle)
+            .field("jobs", &self.jobs)
+            .field("progress_interval", &self.progress_interval)
+            .field("minimum_versions", &self.minimum_versions)
+            .field("config_overrides", &self.config_overrides)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reads an environment variable, returning [`None`] if it's not set.
+fn env_var(name: &str) -> Result<Option<String>, String> {
+    match env::var(name) {
+        Ok(value) => Ok(Some(value)),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(e) => Err(format!("`{name}`: {e}")),
+    }
+}
+
+/// Backend cargo uses to fetch git repositories, see [`PackageFetcherBuilder::git_backend`].
+///
+/// `gitoxide` backend is not supported by the version of cargo this crate is built on, in newer versions
+/// it's only available on nightly, with `-Zgitoxide`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GitBackend {
+    /// `libgit2`, built into cargo.
+    Libgit2,
+    /// `git` executable from `PATH`, same as setting `net.git-fetch-with-cli` in the cargo config.
+    ///
+    /// Useful for authentication schemes or proxies `libgit2` doesn't support.
+    GitCli,
+}
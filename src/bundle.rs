@@ -0,0 +1,109 @@
+use crate::{Package, PackageFetcher};
+use cargo::{sources::PathSource, util::short_hash, Config};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+impl PackageFetcher {
+    /// Fetches packages, and copies each of them into `dest/<name>-<version>`, returning the paths
+    /// of the copies in the same order as `packages`.
+    ///
+    /// Registry packages are copied as they were extracted from their archives. Of other packages, only
+    /// the files cargo would include in a package archive are copied, so e.g. `target` directory of a path
+    /// package or `.git` directory of a git checkout are not.
+    ///
+    /// If packages with the same name and version from different sources are requested, each of them
+    /// is copied into `dest/<name>-<version>-<source hash>` instead.
+    ///
+    /// Already existing directories in `dest` with the same names are replaced.
+    pub fn bundle<P: AsRef<Path>>(&mut self, packages: &[Package], dest: P) -> Result<Vec<PathBuf>, String> {
+        let dest = dest.as_ref();
+        let _lock = self.lock_package_cache()?;
+        let package_set = self.package_set(packages)?;
+        let fetched = package_set
+            .get_many(package_set.package_ids())
+            .map_err(|e| self.report_failure(packages, e))?;
+
+        let mut sources: HashMap<String, HashSet<_>> = HashMap::new();
+        for package in &fetched {
+            let package_id = package.package_id();
+            sources
+                .entry(format!("{}-{}", package_id.name(), package_id.version()))
+                .or_default()
+                .insert(package_id.source_id());
+        }
+
+        let mut copies = HashMap::new();
+        for package in fetched {
+            let package_id = package.package_id();
+            let mut dir_name = format!("{}-{}", package_id.name(), package_id.version());
+            if sources[&dir_name].len() > 1 {
+                dir_name = format!("{dir_name}-{}", short_hash(&package_id.source_id()));
+            }
+
+            let copy = dest.join(dir_name);
+            copy_package(package, &copy, &self.config).map_err(|e| e.to_string())?;
+            copies.insert(package_id, copy);
+        }
+
+        Ok(packages.iter().map(|p| copies[&p.package_id].clone()).collect())
+    }
+}
+
+/// Copies the files of a package into `dest`, replacing it if it exists.
+pub(crate) fn copy_package(package: &cargo::core::Package, dest: &Path, config: &Config) -> cargo::CargoResult<()> {
+    if dest.exists() {
+        fs::remove_dir_all(dest)?;
+    }
+
+    for relative in package_files(package, config)? {
+        let copy = dest.join(&relative);
+        if let Some(parent) = copy.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(package.root().join(&relative), &copy)?;
+    }
+
+    Ok(())
+}
+
+/// Lists the files of a package, relative to its root.
+///
+/// For registry packages, all extracted files of the archive are listed, for others only the files cargo
+/// would include in the package archive.
+pub(crate) fn package_files(package: &cargo::core::Package, config: &Config) -> cargo::CargoResult<Vec<PathBuf>> {
+    let root = package.root();
+    let files = if package.package_id().source_id().is_registry() {
+        let mut files = vec![];
+        walk(root, &mut files)?;
+        files
+    } else {
+        PathSource::new(root, package.package_id().source_id(), config).list_files(package)?
+    };
+
+    let mut relative = Vec::with_capacity(files.len());
+    for file in files {
+        let path = file.strip_prefix(root)?;
+        // Cargo's marker of a completely extracted package.
+        if path != Path::new(".cargo-ok") {
+            relative.push(path.to_owned());
+        }
+    }
+    relative.sort();
+    Ok(relative)
+}
+
+/// Recursively lists files in `dir`.
+pub(crate) fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            walk(&entry.path(), files)?;
+        } else {
+            files.push(entry.path());
+        }
+    }
+    Ok(())
+}
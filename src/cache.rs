@@ -0,0 +1,115 @@
+use crate::{git_cache::is_full_hash, registry, Package, PackageFetcher};
+use cargo::{
+    core::{GitReference, SourceId},
+    util::short_hash,
+    CargoResult,
+};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+/// Locations of the cached files of a package, returned by [`PackageFetcher::cache_paths`].
+///
+/// Paths are returned whether they exist or not, [`None`] means the package never has the file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CachePaths {
+    /// `.crate` archive of a registry package, in the cargo home for remote registries, and in the registry itself
+    /// for local ones.
+    pub archive: Option<PathBuf>,
+    /// Directory a registry package is extracted to.
+    pub extracted: Option<PathBuf>,
+    /// Bare clone of the repository of a git package, shared by all its packages and commits.
+    pub git_db: Option<PathBuf>,
+    /// Checkout of the commit of a git package, the package is in it, possibly in a subdirectory.
+    ///
+    /// Only known for packages locked to a commit, e.g. resolved ones, or ones with a
+    /// [`GitReference::Revision`](crate::GitReference::Revision) of a full commit hash. Checkouts in the
+    /// [`PackageFetcherBuilder::shared_git_cache`](crate::PackageFetcherBuilder::shared_git_cache) aren't included.
+    pub git_checkout: Option<PathBuf>,
+}
+
+impl PackageFetcher {
+    /// Returns where the cached files of `package` are in the cargo home of the fetcher, see [`CachePaths`].
+    ///
+    /// Paths are the ones of the source `package` is actually fetched from, after applying `[source.*]` replacement
+    /// from the cargo config. Path packages, and packages of directory sources (e.g. vendored ones) are not cached,
+    /// all of their paths are [`None`].
+    ///
+    /// ```
+    /// # use cargo_fetch::{Package, PackageFetcher, PackageSource};
+    /// let fetcher = PackageFetcher::builder().cargo_home("/cargo").build()?;
+    /// let serde = Package::from_str_ver("serde", "1.0.152", &PackageSource::CratesIo)?;
+    /// let paths = fetcher.cache_paths(&serde)?;
+    /// assert!(paths.archive.unwrap().ends_with("serde-1.0.152.crate"));
+    /// assert!(paths.extracted.unwrap().ends_with("serde-1.0.152"));
+    /// assert_eq!(paths.git_db, None);
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn cache_paths(&self, package: &Package) -> Result<CachePaths, String> {
+        let package_id = package.package_id;
+        let source = self.load_source(package_id.source_id(), &HashSet::new())?;
+        let source_id = source.replaced_source_id();
+        let file_name = format!("{}-{}", package_id.name(), package_id.version());
+
+        let mut paths = CachePaths::default();
+        if source_id.is_registry() {
+            let short_name = registry::short_name(source_id);
+            paths.archive = Some(if source_id.is_remote_registry() {
+                self.config
+                    .registry_cache_path()
+                    .join(&short_name)
+                    .into_path_unlocked()
+                    .join(format!("{file_name}.crate"))
+            } else {
+                let root = source_id
+                    .url()
+                    .to_file_path()
+                    .map_err(|()| format!("invalid path of {source_id}"))?;
+                root.join(format!("{file_name}.crate"))
+            });
+            let extracted = self.config.registry_source_path().join(&short_name).join(&file_name);
+            paths.extracted = Some(extracted.into_path_unlocked());
+        } else if source_id.is_git() {
+            let ident = git_ident(source_id);
+            let git = self.config.git_path();
+            let db = git.join("db").join(&ident).into_path_unlocked();
+            let commit = source_id.precise().or(match source_id.git_reference() {
+                Some(GitReference::Rev(rev)) if is_full_hash(rev) => Some(rev),
+                _ => None,
+            });
+            if let Some(commit) = commit {
+                let short_id = git_short_id(&db, commit).map_err(|e| e.to_string())?;
+                paths.git_checkout = Some(git.join("checkouts").join(&ident).join(short_id).into_path_unlocked());
+            }
+            paths.git_db = Some(db);
+        }
+        Ok(paths)
+    }
+}
+
+/// Name of the directories of the repository of `source_id` in the git cache, same as cargo names them.
+pub(crate) fn git_ident(source_id: SourceId) -> String {
+    let url = source_id.canonical_url();
+    let name = url
+        .raw_canonicalized_url()
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("_empty");
+    format!("{name}-{}", short_hash(url))
+}
+
+/// Abbreviated `commit`, naming its checkout, same as cargo abbreviates it with the bare clone at `db`.
+///
+/// Until the commit is fetched into the clone, it's abbreviated to 7 characters, which is what `libgit2` does,
+/// unless that's ambiguous.
+fn git_short_id(db: &Path, commit: &str) -> CargoResult<String> {
+    let oid = git2::Oid::from_str(commit)?;
+    let short_id = git2::Repository::open(db).and_then(|repo| repo.find_object(oid, None)?.short_id());
+    match short_id {
+        Ok(short_id) => Ok(short_id.as_str().unwrap_or(commit).to_owned()),
+        Err(_) => Ok(commit.chars().take(7).collect()),
+    }
+}
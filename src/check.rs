@@ -0,0 +1,264 @@
+use crate::{registry::registry_config, PackageFetcher, PackageSource};
+use cargo::{
+    core::{GitReference, SourceId},
+    sources::RegistrySource,
+};
+use semver::Version;
+use std::{
+    collections::{HashMap, HashSet},
+    process::Command,
+};
+use url::Url;
+
+impl PackageFetcher {
+    /// Checks that `source` is usable, with the minimal operation for its kind, after applying source replacement:
+    /// * Remote registries: reads the `config.json` of the index, updating the index if needed.
+    /// * Git repositories: lists the references of the repository, and checks that the requested branch or tag
+    ///   exists, without fetching anything. Revisions can't be checked without fetching, so for them only
+    ///   the connection to the repository is checked.
+    /// * Paths, local registries and directories: checks that they exist.
+    ///
+    /// Remote sources can't be checked in offline mode.
+    pub fn check_source(&self, source: &PackageSource) -> Result<(), String> {
+        let _lock = self.lock_package_cache()?;
+        let source_id = self.source_id(source).map_err(|e| e.to_string())?;
+        let source_id = self.load_source(source_id, &HashSet::new())?.replaced_source_id();
+
+        if (source_id.is_remote_registry() || source_id.is_git()) && self.config.offline() {
+            return Err(format!("can't check {source_id} in offline mode"));
+        }
+
+        if source_id.is_remote_registry() {
+            let mut registry =
+                RegistrySource::remote(source_id, &HashSet::new(), &self.config).map_err(|e| e.to_string())?;
+            registry_config(&mut registry).map_err(|e| format!("can't read the index of {source_id}: {e}"))?;
+            Ok(())
+        } else if source_id.is_git() {
+            self.check_git(source_id)
+        } else {
+            let path = source_id
+                .url()
+                .to_file_path()
+                .map_err(|_| format!("{source_id} is not a local path"))?;
+            let expected = if source_id.is_path() {
+                path.join("Cargo.toml")
+            } else if source_id.is_registry() {
+                // Local registry
+                path.join("index")
+            } else {
+                // Directory source
+                path
+            };
+            if !expected.exists() {
+                return Err(format!("`{}` doesn't exist", expected.display()));
+            }
+            Ok(())
+        }
+    }
+
+    /// Returns the name of the branch [`GitReference::DefaultBranch`](crate::GitReference::DefaultBranch) resolves
+    /// to in the git repository at `url`, after applying [`PackageFetcher::set_url_rewriter`] and source replacement,
+    /// without fetching anything.
+    ///
+    /// The branch is read from the `HEAD` symbolic reference the repository advertises. For servers that don't
+    /// advertise it, it's guessed from the branches pointing to the same commit as `HEAD`, preferring `main` and
+    /// `master`, if there are several. Errors, if it can't be determined.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::PackageFetcher;
+    /// let fetcher = PackageFetcher::new()?;
+    /// let url = url::Url::parse("https://github.com/serde-rs/serde").unwrap();
+    ///
+    /// println!("default branch: {}", fetcher.default_branch(&url)?);
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn default_branch(&self, url: &Url) -> Result<String, String> {
+        let _lock = self.lock_package_cache()?;
+        let source_id = SourceId::for_git(url, GitReference::DefaultBranch).map_err(|e| e.to_string())?;
+        let source_id = self.rewrite_source_id(source_id).map_err(|e| e.to_string())?;
+        let source_id = self.load_source(source_id, &HashSet::new())?.replaced_source_id();
+        if !source_id.is_git() {
+            return Err(format!(
+                "`{url}` is replaced with {source_id}, which is not a git repository"
+            ));
+        }
+        if self.config.offline() {
+            return Err(format!("can't check {source_id} in offline mode"));
+        }
+
+        let url = source_id.url().as_str();
+        let remote = self.ls_remote(url)?;
+        let head = match remote.head {
+            Some(head) => head,
+            None => {
+                let oid = remote
+                    .refs
+                    .get("HEAD")
+                    .ok_or_else(|| format!("`{url}` doesn't advertise `HEAD`"))?;
+                let mut branches: Vec<&String> = remote
+                    .refs
+                    .iter()
+                    .filter(|(name, branch_oid)| name.starts_with("refs/heads/") && *branch_oid == oid)
+                    .map(|(name, _)| name)
+                    .collect();
+                branches.sort_by_key(|name| {
+                    (
+                        !["refs/heads/main", "refs/heads/master"].contains(&name.as_str()),
+                        *name,
+                    )
+                });
+                match branches.first() {
+                    Some(head) => head.to_string(),
+                    None => return Err(format!("`HEAD` of `{url}` doesn't point to any branch")),
+                }
+            },
+        };
+
+        head.strip_prefix("refs/heads/")
+            .map(str::to_owned)
+            .ok_or_else(|| format!("`HEAD` of `{url}` points to `{head}`, which is not a branch"))
+    }
+
+    /// Returns the newest tag of the git repository at `url` matching the glob `pattern`, after applying
+    /// [`PackageFetcher::set_url_rewriter`] and source replacement, without fetching anything. This is what
+    /// [`GitReference::TagPattern`](crate::GitReference::TagPattern) resolves to.
+    ///
+    /// Tags are compared by the semver version they contain after their prefix, e.g. `v1.10.0` is newer than `v1.9.0`,
+    /// and all tags with a version are newer than tags without one, which are compared by their names.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::PackageFetcher;
+    /// let fetcher = PackageFetcher::new()?;
+    /// let url = url::Url::parse("https://github.com/serde-rs/serde").unwrap();
+    ///
+    /// // e.g. `v1.10.0`, rather than `v1.9.0`
+    /// let tag = fetcher.latest_tag(&url, "v1.*")?;
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn latest_tag(&self, url: &Url, pattern: &str) -> Result<String, String> {
+        let glob = glob::Pattern::new(pattern).map_err(|e| format!("invalid tag pattern `{pattern}`: {e}"))?;
+        let _lock = self.lock_package_cache()?;
+        let source_id = SourceId::for_git(url, GitReference::DefaultBranch).map_err(|e| e.to_string())?;
+        let source_id = self.rewrite_source_id(source_id).map_err(|e| e.to_string())?;
+        let source_id = self.load_source(source_id, &HashSet::new())?.replaced_source_id();
+        if !source_id.is_git() {
+            return Err(format!(
+                "`{url}` is replaced with {source_id}, which is not a git repository"
+            ));
+        }
+        if self.config.offline() {
+            return Err(format!("can't list tags of {source_id} in offline mode"));
+        }
+
+        let url = source_id.url().as_str();
+        self.ls_remote(url)?
+            .refs
+            .keys()
+            // Annotated tags are also listed peeled, as `<tag>^{}`.
+            .filter_map(|name| name.strip_prefix("refs/tags/").filter(|tag| !tag.ends_with("^{}")))
+            .filter(|tag| glob.matches(tag))
+            .max_by_key(|tag| (tag_version(tag), *tag))
+            .map(str::to_owned)
+            .ok_or_else(|| format!("`{url}` doesn't have tags matching `{pattern}`"))
+    }
+
+    fn check_git(&self, source_id: SourceId) -> Result<(), String> {
+        let url = source_id.url().as_str();
+        let reference = match source_id.git_reference() {
+            Some(GitReference::Branch(branch)) => Some(format!("refs/heads/{branch}")),
+            Some(GitReference::Tag(tag)) => Some(format!("refs/tags/{tag}")),
+            Some(GitReference::DefaultBranch) => Some("HEAD".to_owned()),
+            Some(GitReference::Rev(_)) | None => None,
+        };
+
+        let refs = self.ls_remote(url)?.refs;
+        match reference {
+            Some(reference) if !refs.contains_key(&reference) => Err(format!("`{url}` doesn't have `{reference}`")),
+            _ => Ok(()),
+        }
+    }
+
+    /// Lists references of a remote repository, with the git backend selected by the cargo config.
+    fn ls_remote(&self, url: &str) -> Result<RemoteRefs, String> {
+        let _git_env = self.git_env_guard();
+        let net = self.config.net_config().map_err(|e| e.to_string())?;
+        if net.git_fetch_with_cli == Some(true) {
+            ls_remote_cli(url)
+        } else {
+            ls_remote(url).map_err(|e| e.to_string())
+        }
+        .map_err(|e| format!("can't connect to `{url}`: {e}"))
+    }
+}
+
+/// Version a tag contains after its prefix, e.g. `1.0.0` for `v1.0.0` or `release-1.0.0`.
+fn tag_version(tag: &str) -> Option<Version> {
+    let start = tag.find(|c: char| c.is_ascii_digit())?;
+    Version::parse(&tag[start..]).ok()
+}
+
+/// References advertised by a remote repository.
+struct RemoteRefs {
+    /// Names of the references, with the commits they point to.
+    refs: HashMap<String, String>,
+    /// Reference `HEAD` points to, if the remote advertises it.
+    head: Option<String>,
+}
+
+/// Lists references of a remote repository with `libgit2`.
+fn ls_remote(url: &str) -> Result<RemoteRefs, git2::Error> {
+    let git_config = git2::Config::open_default()?;
+    let mut callbacks = git2::RemoteCallbacks::new();
+    callbacks.credentials(|url, username, allowed| {
+        if allowed.contains(git2::CredentialType::SSH_KEY) {
+            git2::Cred::ssh_key_from_agent(username.unwrap_or("git"))
+        } else if allowed.contains(git2::CredentialType::USER_PASS_PLAINTEXT) {
+            git2::Cred::credential_helper(&git_config, url, username)
+        } else {
+            git2::Cred::default()
+        }
+    });
+
+    let mut remote = git2::Remote::create_detached(url)?;
+    let mut connection = remote.connect_auth(git2::Direction::Fetch, Some(callbacks), None)?;
+    let refs = connection
+        .list()?
+        .iter()
+        .map(|head| (head.name().to_owned(), head.oid().to_string()))
+        .collect();
+    let head = match connection.remote().default_branch() {
+        Ok(head) => head.as_str().map(str::to_owned),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => None,
+        Err(e) => return Err(e),
+    };
+    Ok(RemoteRefs { refs, head })
+}
+
+/// Lists references of a remote repository with the `git` executable.
+fn ls_remote_cli(url: &str) -> Result<RemoteRefs, String> {
+    let output = Command::new("git")
+        .args(["ls-remote", "--symref", "--", url])
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_owned());
+    }
+
+    let mut remote = RemoteRefs {
+        refs: HashMap::new(),
+        head: None,
+    };
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        match line.split_once('\t') {
+            // Symbolic references are listed as `ref: <target>\t<name>`.
+            Some((target, "HEAD")) if target.starts_with("ref: ") => {
+                remote.head = Some(target["ref: ".len()..].to_owned());
+            },
+            Some((oid, name)) => {
+                remote.refs.insert(name.to_owned(), oid.to_owned());
+            },
+            None => {},
+        }
+    }
+    Ok(remote)
+}
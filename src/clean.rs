@@ -0,0 +1,90 @@
+use crate::PackageFetcher;
+use std::{fs, io, path::Path};
+
+/// Options of [`PackageFetcher::clean`], selecting the caches to remove.
+///
+/// Default value removes all of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CleanOptions {
+    /// Whether registry caches are removed: downloaded indexes, package archives and their extracted sources.
+    ///
+    /// Indexes pinned with [`PackageFetcherBuilder::registry_index_revision`](crate::PackageFetcherBuilder::registry_index_revision)
+    /// are kept, as fetchers using them only fetch them when constructed.
+    pub registry: bool,
+    /// Whether git caches are removed: fetched repositories and their checkouts.
+    pub git: bool,
+}
+
+impl Default for CleanOptions {
+    fn default() -> Self {
+        Self {
+            registry: true,
+            git: true,
+        }
+    }
+}
+
+impl PackageFetcher {
+    /// Removes the caches selected by `options` from the cargo home of the fetcher, to reclaim disk space.
+    ///
+    /// Only the cache directories cargo manages are removed, configuration, credentials and installed binaries are
+    /// kept. Cache directories that are symbolic links are unlinked, without removing what they point to, and nothing
+    /// is removed from `registry` or `git` directories that are links to outside of the cargo home. The directory of
+    /// [`PackageFetcherBuilder::shared_git_cache`](crate::PackageFetcherBuilder::shared_git_cache) is never removed.
+    ///
+    /// Note that with [`PackageFetcher::new`], the cargo home is the one of the current user, also used by cargo
+    /// itself. Packages are fetched again, when they are needed next time.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{CleanOptions, PackageFetcher};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let registry_only = CleanOptions { registry: true, git: false };
+    ///
+    /// fetcher.clean(&registry_only)?;
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn clean(&mut self, options: &CleanOptions) -> Result<(), String> {
+        let _lock = self.lock_package_cache()?;
+        let home = self.config.home().as_path_unlocked();
+
+        let mut caches = vec![];
+        if options.registry {
+            caches.extend([["registry", "index"], ["registry", "cache"], ["registry", "src"]]);
+        }
+        if options.git {
+            caches.extend([["git", "db"], ["git", "checkouts"]]);
+        }
+
+        for [parent, cache] in caches {
+            remove_cache(home, parent, cache).map_err(|e| format!("failed to remove `{parent}/{cache}`: {e}"))?;
+        }
+
+        // Cargo updates indexes at most once, they have to be downloaded again.
+        if options.registry {
+            self.config.updated_sources().clear();
+        }
+        Ok(())
+    }
+}
+
+/// Removes `home/parent/cache`, if it's inside of `home`.
+fn remove_cache(home: &Path, parent: &str, cache: &str) -> io::Result<()> {
+    let parent = home.join(parent);
+    let cache = parent.join(cache);
+    if cache.symlink_metadata().is_err() {
+        return Ok(());
+    }
+
+    // `remove_dir_all` doesn't follow the link it's given, but does follow the links on the way to it.
+    if !parent.canonicalize()?.starts_with(home.canonicalize()?) {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("`{}` is outside of the cargo home", parent.display()),
+        ));
+    }
+    if cache.symlink_metadata()?.file_type().is_symlink() {
+        fs::remove_file(cache)
+    } else {
+        fs::remove_dir_all(cache)
+    }
+}
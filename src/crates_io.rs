@@ -0,0 +1,185 @@
+use crate::{Package, PackageFetcher, PackageSource};
+use semver::Version;
+use serde_json::Value;
+use std::collections::HashMap;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+/// Crate info from the crates.io API, returned by [`PackageFetcher::crates_io_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CratesIoInfo {
+    /// Name of the crate, as it was published.
+    pub name: String,
+    /// Total downloads of all versions of the crate.
+    pub downloads: u64,
+    /// Downloads of all versions of the crate in the last 90 days.
+    pub recent_downloads: Option<u64>,
+    /// Highest version of the crate, excluding yanked versions, unless all of them are yanked.
+    pub max_version: Version,
+    /// Highest stable version of the crate, excluding yanked versions, [`None`] if there are only pre-releases.
+    pub max_stable_version: Option<Version>,
+    /// Most recently published version of the crate.
+    pub newest_version: Version,
+    /// Description of the crate, from the manifest of its newest version.
+    pub description: Option<String>,
+    /// Repository of the crate, from the manifest of its newest version.
+    pub repository: Option<String>,
+}
+
+/// Crate endpoint of the crates.io API.
+const CRATES_IO_API: &str = "https://crates.io/api/v1/crates";
+
+impl PackageFetcher {
+    /// Queries the crates.io API for info about a crate, that the registry index doesn't have, e.g. its downloads.
+    ///
+    /// This is only about crates.io, regardless of the source replacement of `crates-io`, as other registries don't
+    /// necessarily implement the API. The request is made with the `http` configuration of cargo, and fails in
+    /// offline mode. Requires `crates-io-api` feature.
+    pub fn crates_io_info<N: AsRef<str>>(&self, name: N) -> Result<CratesIoInfo, String> {
+        let name = name.as_ref();
+        if self.config.offline() {
+            return Err(format!("can't query crates.io for `{name}` in offline mode"));
+        }
+
+        let body = self
+            .crates_io_get(&format!("{CRATES_IO_API}/{name}"))
+            .map_err(|e| format!("failed to query crates.io for `{name}`: {e}"))?;
+        let json: Value = serde_json::from_slice(&body).map_err(|e| e.to_string())?;
+        if let Some(errors) = json["errors"].as_array() {
+            let details: Vec<&str> = errors.iter().filter_map(|e| e["detail"].as_str()).collect();
+            return Err(format!("crates.io couldn't find `{name}`: {}", details.join(", ")));
+        }
+
+        let krate = &json["crate"];
+        let version = |key: &str| -> Result<Option<Version>, String> {
+            krate[key]
+                .as_str()
+                .map(|version| Version::parse(version).map_err(|e| format!("crates.io returned invalid `{key}`: {e}")))
+                .transpose()
+        };
+        let required = |key: &str| version(key)?.ok_or_else(|| format!("crates.io didn't return `{key}` of `{name}`"));
+        let string = |key: &str| krate[key].as_str().map(str::to_owned);
+
+        Ok(CratesIoInfo {
+            name: string("name").unwrap_or_else(|| name.to_owned()),
+            downloads: krate["downloads"]
+                .as_u64()
+                .ok_or_else(|| format!("crates.io didn't return `downloads` of `{name}`"))?,
+            recent_downloads: krate["recent_downloads"].as_u64(),
+            max_version: required("max_version")?,
+            max_stable_version: version("max_stable_version")?,
+            newest_version: required("newest_version")?,
+            description: string("description"),
+            repository: string("repository"),
+        })
+    }
+
+    /// Resolves the newest version of a crate that was published on or before `date`, according to the crates.io
+    /// API, e.g. to reproduce how a dependency was resolved at that time.
+    ///
+    /// Versions are selected from the ones `source` has, same as [`Self::resolve_first`] without a version
+    /// requirement, so yanked versions and pre-releases aren't selected, even if they weren't yanked at that
+    /// time. `source` has to be crates.io, or a registry replacing it, as only the crates.io API has publish dates,
+    /// the registry index of the cargo version this crate is built on doesn't. The request is made the same as
+    /// by [`Self::crates_io_info`]. Requires `crates-io-api` feature.
+    ///
+    /// Errors, if `source` isn't crates.io, or none of the versions were published on or before `date`.
+    pub fn resolve_as_of<N: AsRef<str>>(
+        &self,
+        name: N,
+        source: &PackageSource,
+        date: OffsetDateTime,
+    ) -> Result<Package, String> {
+        let name = name.as_ref();
+        let source_id = self.source_id(source).map_err(|e| e.to_string())?;
+        if !source_id.is_crates_io() {
+            return Err(format!(
+                "publish dates of `{name}` are only available for crates.io, not `{}`",
+                source_id.as_url()
+            ));
+        }
+
+        let published = self.publish_dates(name)?;
+        let newest = self
+            .resolve_package(name, None, source, None)?
+            .into_iter()
+            .filter(|package| {
+                published
+                    .get(package.package_id.version())
+                    .is_some_and(|published| *published <= date)
+            })
+            .reduce(|newest, package| {
+                if self.is_newer(package.package_id.version(), newest.package_id.version()) {
+                    package
+                } else {
+                    newest
+                }
+            });
+        match newest {
+            Some(package) => Ok(self.check_minimum(package)?),
+            None => Err(format!(
+                "no version of `{name}` was published on or before {}",
+                date.format(&Rfc3339).map_err(|e| e.to_string())?
+            )),
+        }
+    }
+
+    /// Queries the crates.io API for the times all versions of a crate were published at.
+    fn publish_dates(&self, name: &str) -> Result<HashMap<Version, OffsetDateTime>, String> {
+        if self.config.offline() {
+            return Err(format!("can't query crates.io for `{name}` in offline mode"));
+        }
+
+        let mut dates = HashMap::new();
+        let mut query = String::new();
+        loop {
+            let body = self
+                .crates_io_get(&format!("{CRATES_IO_API}/{name}/versions{query}"))
+                .map_err(|e| format!("failed to query crates.io for `{name}`: {e}"))?;
+            let json: Value = serde_json::from_slice(&body).map_err(|e| e.to_string())?;
+            if let Some(errors) = json["errors"].as_array() {
+                let details: Vec<&str> = errors.iter().filter_map(|e| e["detail"].as_str()).collect();
+                return Err(format!("crates.io couldn't find `{name}`: {}", details.join(", ")));
+            }
+
+            for version in json["versions"].as_array().into_iter().flatten() {
+                let (Some(num), Some(created_at)) = (version["num"].as_str(), version["created_at"].as_str()) else {
+                    return Err(format!("crates.io returned a version of `{name}` without a publish date"));
+                };
+                let num = Version::parse(num).map_err(|e| format!("crates.io returned invalid version: {e}"))?;
+                let created_at = OffsetDateTime::parse(created_at, &Rfc3339)
+                    .map_err(|e| format!("crates.io returned invalid publish date of `{name}` {num}: {e}"))?;
+                dates.insert(num, created_at);
+            }
+            // Versions are paginated, the next page is given as the query of the url.
+            match json["meta"]["next_page"].as_str() {
+                Some(next_page) if !next_page.is_empty() => query = next_page.to_owned(),
+                _ => return Ok(dates),
+            }
+        }
+    }
+
+    /// Makes a GET request, returning the body of the response, which is also returned for not found errors,
+    /// as the API describes them in it.
+    fn crates_io_get(&self, url: &str) -> cargo::CargoResult<Vec<u8>> {
+        let mut handle = cargo::ops::http_handle(&self.config)?;
+        handle.get(true)?;
+        handle.url(url)?;
+        handle.accept_encoding("")?;
+
+        let mut body = vec![];
+        {
+            let mut transfer = handle.transfer();
+            transfer.write_function(|data| {
+                body.extend_from_slice(data);
+                Ok(data.len())
+            })?;
+            transfer.perform()?;
+        }
+
+        match handle.response_code()? {
+            200 | 404 => Ok(body),
+            code => anyhow::bail!("crates.io responded with {code}"),
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use crate::{
+    registry::{registry_config, registry_name},
+    GitReference, PackageFetcher, PackageSource,
+};
+use cargo::{
+    core::SourceId,
+    sources::RegistrySource,
+    util::config::{Config, PathAndArgs},
+    CargoResult,
+};
+use serde_json::{json, Value};
+use std::{
+    collections::HashSet,
+    fmt,
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+/// Registry token carried by [`PackageSource::RegistryWithAuth`].
+///
+/// The token is never printed, its [`Debug`](fmt::Debug) output is redacted, and its memory is overwritten
+/// with zeros when it's dropped.
+#[derive(PartialEq, Eq)]
+pub struct RegistryToken(String);
+
+impl RegistryToken {
+    /// Constructs [`RegistryToken`] from the token, as it's sent in the `Authorization` header.
+    pub fn new<T: Into<String>>(token: T) -> Self {
+        Self(token.into())
+    }
+}
+
+impl fmt::Debug for RegistryToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RegistryToken(<redacted>)")
+    }
+}
+
+impl Drop for RegistryToken {
+    fn drop(&mut self) {
+        // SAFETY: zero bytes are valid UTF-8.
+        for byte in unsafe { self.0.as_bytes_mut() } {
+            // Volatile, so the writes to memory that is about to be freed aren't optimized out.
+            unsafe { std::ptr::write_volatile(byte, 0) };
+        }
+    }
+}
+
+impl PackageFetcher {
+    /// Converts `source` to cargo's representation, rewriting its url with [`PackageFetcher::set_url_rewriter`],
+    /// resolving [`GitReference::TagPattern`] to a tag, and making the token of [`PackageSource::RegistryWithAuth`]
+    /// available to cargo for requests to the registry, until the package cache lock is released.
+    pub(crate) fn source_id(&self, source: &PackageSource) -> CargoResult<SourceId> {
+        let source_id = match source {
+            PackageSource::Git {
+                url,
+                git_ref: GitReference::TagPattern(pattern),
+            } => {
+                let tag = self.latest_tag(url, pattern).map_err(anyhow::Error::msg)?;
+                SourceId::for_git(url, GitReference::Tag(tag).into())?
+            },
+            _ => source.to_source_id()?,
+        };
+        let source_id = self.rewrite_source_id(source_id)?;
+        if let PackageSource::RegistryWithAuth { token, .. } = source {
+            if !self.config.cli_unstable().registry_auth {
+                anyhow::bail!("registries with tokens require `PackageFetcherBuilder::registry_auth` to be enabled");
+            }
+            self.scope_token(source_id, token);
+        }
+        Ok(source_id)
+    }
+
+    /// Puts `token` into the credential cache, cargo reads tokens from first, for both index requests and
+    /// downloads, until the package cache lock is released.
+    ///
+    /// Requests are only made with the lock held, so the token isn't put into the cache without it.
+    fn scope_token(&self, source_id: SourceId, token: &RegistryToken) {
+        if self.cache_locks.get() == 0 {
+            return;
+        }
+        let url = source_id.canonical_url();
+        let previous = self.config.credential_cache().insert(url.clone(), token.0.clone());
+        let mut scoped = self.scoped_tokens.borrow_mut();
+        if scoped.iter().any(|(scoped, _)| scoped == url) {
+            // Token put into the cache earlier in the same call.
+            drop(previous.map(RegistryToken));
+        } else {
+            scoped.push((url.clone(), previous));
+        }
+    }
+
+    /// Removes the tokens put into the credential cache by [`Self::scope_token`], restoring the tokens they
+    /// replaced, and overwriting them with zeros.
+    pub(crate) fn release_tokens(&self) {
+        let mut cache = self.config.credential_cache();
+        for (url, previous) in self.scoped_tokens.borrow_mut().drain(..) {
+            let removed = match previous {
+                Some(previous) => cache.insert(url, previous),
+                None => cache.remove(&url),
+            };
+            drop(removed.map(RegistryToken));
+        }
+    }
+}
+
+/// Makes the token of the registry of `source_id` available to cargo, if the registry requires authentication.
+///
+/// Cargo this crate is built on only reads tokens from its config, so the token is obtained from the credential
+/// providers configured for the registry, and put into the credential cache of `config`, cargo reads from first.
+pub(crate) fn provide_token(config: &Config, source_id: SourceId) -> CargoResult<()> {
+    if config.credential_cache().contains_key(source_id.canonical_url()) {
+        return Ok(());
+    }
+
+    let mut registry = RegistrySource::remote(source_id, &HashSet::new(), config)?;
+    let auth_required = registry_config(&mut registry)
+        .map(|registry| registry.auth_required)
+        .unwrap_or(false);
+    if !auth_required {
+        return Ok(());
+    }
+
+    let name = registry_name(config, source_id);
+    for provider in providers(config, source_id, name.as_deref())? {
+        if let Some(token) = run_provider(config, &provider, source_id, name.as_deref())? {
+            config
+                .credential_cache()
+                .insert(source_id.canonical_url().clone(), token);
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+/// Returns the credential providers for the registry, in the order they are tried in.
+fn providers(config: &Config, source_id: SourceId, name: Option<&str>) -> CargoResult<Vec<Vec<String>>> {
+    let table = match name {
+        _ if source_id.is_crates_io() => Some("registry".to_owned()),
+        Some(name) => Some(format!("registries.{name}")),
+        None => None,
+    };
+    if let Some(table) = table {
+        if let Some(provider) = config.get::<Option<PathAndArgs>>(&format!("{table}.credential-provider"))? {
+            // Built-in providers are kept as is, other programs are resolved relative to the config defining them.
+            let program = match provider.path.raw_value() {
+                builtin if builtin.starts_with("cargo:") => builtin.to_owned(),
+                _ => provider.path.resolve_program(config).display().to_string(),
+            };
+            return Ok(vec![std::iter::once(program).chain(provider.args).collect()]);
+        }
+    }
+
+    // Providers later in the list take precedence.
+    let global = config
+        .get::<Option<Vec<String>>>("registry.global-credential-providers")?
+        .unwrap_or_else(|| vec!["cargo:token".to_owned()]);
+    Ok(global
+        .iter()
+        .rev()
+        .map(|provider| provider.split_whitespace().map(str::to_owned).collect())
+        .collect())
+}
+
+/// Runs a credential provider, returning [`None`] if it doesn't have a token for the registry.
+fn run_provider(
+    config: &Config,
+    provider: &[String],
+    source_id: SourceId,
+    name: Option<&str>,
+) -> CargoResult<Option<String>> {
+    let (program, args) = match provider.split_first() {
+        Some(split) => split,
+        None => anyhow::bail!("credential provider is empty"),
+    };
+    let index_url = source_id.url().as_str();
+    match program.as_str() {
+        "cargo:token" => {
+            config.load_credentials()?;
+            let key = match name {
+                _ if source_id.is_crates_io() => "registry.token".to_owned(),
+                Some(name) => format!("registries.{name}.token"),
+                None => return Ok(None),
+            };
+            Ok(config.get::<Option<String>>(&key)?)
+        },
+        "cargo:token-from-stdout" => {
+            let (program, args) = match args.split_first() {
+                Some(split) => split,
+                None => anyhow::bail!("`cargo:token-from-stdout` requires a program to run"),
+            };
+            let output = Command::new(program)
+                .args(args)
+                .env("CARGO_REGISTRY_INDEX_URL", index_url)
+                .envs(name.map(|name| ("CARGO_REGISTRY_NAME_OPT", name)))
+                .stderr(Stdio::inherit())
+                .output()
+                .map_err(|e| anyhow::format_err!("failed to run `{program}`: {e}"))?;
+            if !output.status.success() {
+                anyhow::bail!("`{program}` failed with {}", output.status);
+            }
+            let token = String::from_utf8(output.stdout)?
+                .trim_end_matches(['\r', '\n'])
+                .to_owned();
+            if token.is_empty() {
+                anyhow::bail!("`{program}` didn't output a token");
+            }
+            Ok(Some(token))
+        },
+        builtin if builtin.starts_with("cargo:") => {
+            anyhow::bail!("credential provider `{builtin}` is not supported")
+        },
+        _ => run_plugin(program, args, index_url, name),
+    }
+}
+
+/// Gets a token from a provider speaking version 1 of the cargo credential provider protocol.
+fn run_plugin(program: &str, args: &[String], index_url: &str, name: Option<&str>) -> CargoResult<Option<String>> {
+    let mut child = Command::new(PathBuf::from(program))
+        .arg("--cargo-plugin")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| anyhow::format_err!("failed to run credential provider `{program}`: {e}"))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+    let mut read = || -> CargoResult<Value> {
+        let mut line = String::new();
+        stdout.read_line(&mut line)?;
+        Ok(serde_json::from_str(&line)?)
+    };
+
+    let hello = read()?;
+    let supported = hello["v"]
+        .as_array()
+        .is_some_and(|versions| versions.contains(&json!(1)));
+    if !supported {
+        anyhow::bail!("credential provider `{program}` doesn't support version 1 of the protocol");
+    }
+
+    let mut registry = json!({ "index-url": index_url });
+    if let Some(name) = name {
+        registry["name"] = json!(name);
+    }
+    let request = json!({ "v": 1, "registry": registry, "kind": "get", "operation": "read", "args": args });
+    writeln!(stdin, "{request}")?;
+    let response = read()?;
+    drop(stdin);
+    child.wait()?;
+
+    if let Some(token) = response["Ok"]["token"].as_str() {
+        return Ok(Some(token.to_owned()));
+    }
+    match response["Err"]["kind"].as_str() {
+        Some("not-found") => Ok(None),
+        _ => {
+            let message = response["Err"]["message"].as_str().unwrap_or("unknown error");
+            anyhow::bail!("credential provider `{program}` failed: {message}")
+        },
+    }
+}
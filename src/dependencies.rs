@@ -0,0 +1,264 @@
+use crate::{query_blocking, GitReference, Package, PackageFetcher, PackageSource};
+use cargo::{
+    core::{dependency::DepKind, Dependency, FeatureMap, FeatureValue, SourceId, Summary},
+    util::interning::InternedString,
+};
+use std::collections::HashSet;
+
+/// Direct dependencies declared in the manifest of a package, returned by [`PackageFetcher::direct_dependencies`].
+///
+/// Dependencies of each kind are grouped by their platform, the ones for all platforms first, and sorted by their
+/// names within a platform.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyTable {
+    /// `[dependencies]`
+    pub normal: Vec<DeclaredDependency>,
+    /// `[build-dependencies]`
+    pub build: Vec<DeclaredDependency>,
+    /// `[dev-dependencies]`
+    pub dev: Vec<DeclaredDependency>,
+}
+
+/// Dependency declared in the manifest of a package, see [`DependencyTable`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeclaredDependency {
+    /// Name the package refers to the dependency with, different from [`Self::package`] if it's renamed.
+    pub name: String,
+    /// Name of the package of the dependency.
+    pub package: String,
+    /// Version requirement, `*` if it's not declared, e.g. for path dependencies.
+    pub version: String,
+    /// Source of the dependency.
+    pub source: PackageSource,
+    /// Platform the dependency is declared for with `[target.<platform>]`, as it's written, e.g. `cfg(windows)`.
+    pub target: Option<String>,
+    /// Whether the dependency is optional.
+    pub optional: bool,
+    /// Features of the package enabling the dependency, if it's optional, including the implicit feature named
+    /// after it. Features only enabling features of the dependency if it's enabled (`dep?/feature`) aren't included.
+    pub enabled_by: Vec<String>,
+    /// Features enabled on the dependency.
+    pub features: Vec<String>,
+    /// Whether default features of the dependency are enabled.
+    pub default_features: bool,
+}
+
+/// Optional dependency of a package, returned by [`PackageFetcher::optional_dependencies`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptionalDepInfo {
+    /// Name the package refers to the dependency with.
+    pub name: String,
+    /// Feature cargo implicitly adds for the dependency, named after it, if the features of the package don't
+    /// refer to it with `dep:`, and don't declare a feature with the same name.
+    pub implicit_feature: Option<String>,
+    /// Whether the implicit feature is hidden, because a feature refers to the dependency with `dep:`.
+    pub hidden: bool,
+    /// Features of the package enabling the dependency, including the implicit feature, same as
+    /// [`DeclaredDependency::enabled_by`].
+    pub enabled_by: Vec<String>,
+}
+
+impl PackageFetcher {
+    /// Fetches a package, and returns the direct dependencies declared in its manifest, by their kind.
+    ///
+    /// Unlike the dependencies in the registry index, these are read from the fetched manifest, so they are the
+    /// ones cargo builds path and git packages with. Dependencies inherited from the workspace are included with
+    /// their inherited declarations.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Package, PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let foo = Package::from_str_ver("foo", "0.1.0", &PackageSource::crates_io())?;
+    ///
+    /// let table = fetcher.direct_dependencies(foo)?;
+    /// for dep in &table.normal {
+    ///     println!("{} = {:?}, for {:?}", dep.name, dep.version, dep.target);
+    /// }
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn direct_dependencies(&mut self, package: Package) -> Result<DependencyTable, String> {
+        dependency_table(self.fetch_package(package)?.summary())
+    }
+
+    /// Returns the direct dependencies of a package, same as [`Self::direct_dependencies`], but as the source of the
+    /// package lists them, without fetching it.
+    ///
+    /// For registries, these are the dependencies in the registry index, so the package isn't downloaded. Along with
+    /// [`Self::candidate_versions`], it allows resolving dependencies step by step, with a custom strategy of
+    /// selecting the versions, instead of letting cargo resolve the whole graph at once, as
+    /// [`Self::resolve_graph`] does.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Package, PackageFetcher, PackageSource};
+    /// let fetcher = PackageFetcher::new()?;
+    /// let foo = Package::from_str_ver("foo", "1.0.0", &PackageSource::crates_io())?;
+    ///
+    /// for dep in fetcher.dependency_requirements(foo)?.normal {
+    ///     // Newest first, pick any of them
+    ///     let candidates = fetcher.candidate_versions(&dep)?;
+    /// }
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn dependency_requirements(&self, package: Package) -> Result<DependencyTable, String> {
+        let _lock = self.lock_package_cache()?;
+        let package_id = package.package_id;
+        let mut src = self.load_source(package_id.source_id(), &HashSet::from([package_id]))?;
+        let dep = Dependency::parse(
+            package_id.name(),
+            Some(&format!("={}", package_id.version())),
+            src.source_id(),
+        )
+        .map_err(|e| e.to_string())?;
+
+        let mut summary = None;
+        query_blocking(&mut *src, &dep, &mut |sum| {
+            if sum.package_id() == package_id {
+                summary = Some(sum);
+            }
+        })
+        .map_err(|e| e.to_string())?;
+        dependency_table(&summary.ok_or_else(|| format!("`{package_id}` wasn't found in its source"))?)
+    }
+
+    /// Resolves the versions of a dependency matching its version requirement, from its source, newest first,
+    /// same as [`Self::resolve_package`] does, in the order [`Self::set_version_ordering`] sets, if any.
+    ///
+    /// See [`Self::dependency_requirements`].
+    pub fn candidate_versions(&self, dependency: &DeclaredDependency) -> Result<Vec<Package>, String> {
+        let mut candidates =
+            self.resolve_package(&dependency.package, Some(&dependency.version), &dependency.source, None)?;
+        candidates.sort_by(|a, b| self.compare_versions(b.package_id.version(), a.package_id.version()));
+        Ok(candidates)
+    }
+
+    /// Fetches a package, and returns its optional dependencies, sorted by their names, along with the features
+    /// enabling them.
+    ///
+    /// Optional dependencies declared in multiple tables, e.g. both as a normal and a build dependency, are only
+    /// listed once, as they are enabled by the same features.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Package, PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let foo = Package::from_str_ver("foo", "0.1.0", &PackageSource::crates_io())?;
+    ///
+    /// for dep in fetcher.optional_dependencies(foo)? {
+    ///     println!("{} is enabled by {:?}", dep.name, dep.enabled_by);
+    /// }
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn optional_dependencies(&mut self, package: Package) -> Result<Vec<OptionalDepInfo>, String> {
+        let package = self.fetch_package(package)?;
+        let features = package.summary().features();
+        // Features as declared in the manifest, without the implicit ones cargo adds to the summary.
+        let declared = package.manifest().original().features().cloned().unwrap_or_default();
+
+        let mut names: Vec<_> = package
+            .dependencies()
+            .iter()
+            .filter(|dep| dep.is_optional())
+            .map(|dep| dep.name_in_toml())
+            .collect();
+        names.sort();
+        names.dedup();
+
+        Ok(names
+            .into_iter()
+            .map(|name| {
+                let hidden = declared.values().flatten().any(
+                    |value| matches!(FeatureValue::new(*value), FeatureValue::Dep { dep_name } if dep_name == name),
+                );
+                OptionalDepInfo {
+                    name: name.to_string(),
+                    implicit_feature: (!hidden && !declared.contains_key(&name)).then(|| name.to_string()),
+                    hidden,
+                    enabled_by: enabling_features(features, name),
+                }
+            })
+            .collect())
+    }
+}
+
+/// Returns the features enabling an optional dependency, ignoring the ones only enabling its features if it's
+/// enabled (`dep?/feature`).
+fn enabling_features(features: &FeatureMap, name: InternedString) -> Vec<String> {
+    let mut enabled_by = vec![];
+    for (feature, values) in features {
+        let enables = values.iter().any(|value| match value {
+            FeatureValue::Dep { dep_name } => *dep_name == name,
+            FeatureValue::DepFeature { dep_name, weak, .. } => *dep_name == name && !weak,
+            FeatureValue::Feature(_) => false,
+        });
+        if enables {
+            enabled_by.push(feature.to_string());
+        }
+    }
+    enabled_by
+}
+
+/// Groups the dependencies of a package by their kind, see [`PackageFetcher::direct_dependencies`].
+fn dependency_table(summary: &Summary) -> Result<DependencyTable, String> {
+    let features = summary.features();
+
+    let mut table = DependencyTable::default();
+    for dep in summary.dependencies() {
+        let name = dep.name_in_toml();
+        let enabled_by = if dep.is_optional() {
+            enabling_features(features, name)
+        } else {
+            vec![]
+        };
+
+        let declared = DeclaredDependency {
+            name: name.to_string(),
+            package: dep.package_name().to_string(),
+            version: dep.version_req().to_string(),
+            source: package_source(dep.source_id())?,
+            target: dep.platform().map(ToString::to_string),
+            optional: dep.is_optional(),
+            enabled_by,
+            features: dep.features().iter().map(ToString::to_string).collect(),
+            default_features: dep.uses_default_features(),
+        };
+        match dep.kind() {
+            DepKind::Normal => table.normal.push(declared),
+            DepKind::Build => table.build.push(declared),
+            DepKind::Development => table.dev.push(declared),
+        }
+    }
+
+    for dependencies in [&mut table.normal, &mut table.build, &mut table.dev] {
+        dependencies.sort_by(|a, b| (&a.target, &a.name).cmp(&(&b.target, &b.name)));
+    }
+    Ok(table)
+}
+
+/// Converts the source of a dependency to [`PackageSource`].
+fn package_source(source_id: SourceId) -> Result<PackageSource, String> {
+    let path = || {
+        source_id
+            .url()
+            .to_file_path()
+            .map_err(|()| format!("invalid path of {source_id}"))
+    };
+    Ok(if source_id.is_crates_io() {
+        PackageSource::CratesIo
+    } else if source_id.is_remote_registry() {
+        PackageSource::RemoteRegistry(source_id.url().clone())
+    } else if source_id.is_registry() {
+        PackageSource::LocalRegistry(path()?)
+    } else if let Some(reference) = source_id.git_reference() {
+        let git_ref = match reference {
+            cargo::core::GitReference::Tag(tag) => GitReference::Tag(tag.clone()),
+            cargo::core::GitReference::Branch(branch) => GitReference::Branch(branch.clone()),
+            cargo::core::GitReference::Rev(rev) => GitReference::Revision(rev.clone()),
+            cargo::core::GitReference::DefaultBranch => GitReference::DefaultBranch,
+        };
+        PackageSource::Git {
+            url: source_id.url().clone(),
+            git_ref,
+        }
+    } else {
+        PackageSource::Path(path()?)
+    })
+}
@@ -0,0 +1,115 @@
+use crate::{bundle::package_files, Package, PackageFetcher};
+use cargo_util::Sha256;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// Change of a file between two packages, returned by [`PackageFetcher::diff`].
+///
+/// Hashes are hex encoded sha256 checksums of the file contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "change", rename_all = "lowercase")
+)]
+pub enum FileDiff {
+    /// File is only present in the new package.
+    Added {
+        /// Path of the file, relative to the package root.
+        path: PathBuf,
+        /// Hash of the file.
+        hash: String,
+    },
+    /// File is only present in the old package.
+    Removed {
+        /// Path of the file, relative to the package root.
+        path: PathBuf,
+        /// Hash of the file.
+        hash: String,
+    },
+    /// File is present in both packages, but its contents differ.
+    Modified {
+        /// Path of the file, relative to the package root.
+        path: PathBuf,
+        /// Hash of the file in the old package.
+        old_hash: String,
+        /// Hash of the file in the new package.
+        new_hash: String,
+    },
+}
+
+impl FileDiff {
+    /// Returns the path of the changed file, relative to the package root.
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Added { path, .. } | Self::Removed { path, .. } | Self::Modified { path, .. } => path,
+        }
+    }
+}
+
+impl PackageFetcher {
+    /// Fetches two packages, and compares the files of `old` to the files of `new`, returning the changes
+    /// ordered by path.
+    ///
+    /// Files are compared by the hashes of their contents, see [`PackageFetcher::file_hashes`].
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{FileDiff, Package, PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let old = Package::from_str_ver("foo", "0.1.0", &PackageSource::crates_io())?;
+    /// let new = Package::from_str_ver("foo", "0.2.0", &PackageSource::crates_io())?;
+    ///
+    /// for change in fetcher.diff(&old, &new)? {
+    ///     match change {
+    ///         FileDiff::Added { path, .. } => println!("+ {}", path.display()),
+    ///         FileDiff::Removed { path, .. } => println!("- {}", path.display()),
+    ///         FileDiff::Modified { path, .. } => println!("~ {}", path.display()),
+    ///     }
+    /// }
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn diff(&mut self, old: &Package, new: &Package) -> Result<Vec<FileDiff>, String> {
+        let old = self.file_hashes(*old)?;
+        let mut new = self.file_hashes(*new)?;
+
+        let mut diff = vec![];
+        for (path, old_hash) in old {
+            match new.remove(&path) {
+                Some(new_hash) if new_hash != old_hash => diff.push(FileDiff::Modified {
+                    path,
+                    old_hash,
+                    new_hash,
+                }),
+                Some(_) => {},
+                None => diff.push(FileDiff::Removed { path, hash: old_hash }),
+            }
+        }
+        diff.extend(new.into_iter().map(|(path, hash)| FileDiff::Added { path, hash }));
+        diff.sort_by(|a, b| a.path().cmp(b.path()));
+
+        Ok(diff)
+    }
+
+    /// Fetches a package, and returns hex encoded sha256 checksums of its files, keyed by their paths relative
+    /// to the package root.
+    ///
+    /// The same files are hashed as would be copied by [`PackageFetcher::bundle`]: all files of the archive
+    /// for registry packages, and the files cargo would include in a package archive for others, so e.g.
+    /// `target` directory of a path package is not hashed.
+    pub fn file_hashes(&mut self, package: Package) -> Result<BTreeMap<PathBuf, String>, String> {
+        let package = self.fetch_package(package)?;
+        package_files(&package, &self.config)
+            .and_then(|files| {
+                files
+                    .into_iter()
+                    .map(|path| {
+                        let hash = Sha256::new().update_path(package.root().join(&path))?.finish_hex();
+                        Ok((path, hash))
+                    })
+                    .collect()
+            })
+            .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,47 @@
+use crate::{Package, PackageFetcher};
+use std::path::PathBuf;
+
+/// Fetched package, returned by [`PackageFetcher::fetch_detailed`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FetchDetails {
+    /// Root of the fetched package.
+    pub root: PathBuf,
+    /// Size of the `.crate` archive downloaded for the package, in bytes, `0` if it was already cached.
+    pub downloaded_bytes: u64,
+}
+
+impl PackageFetcher {
+    /// Fetches a single package, same as [`Self::fetch`], and returns its root along with the size of the archive
+    /// actually downloaded for it.
+    ///
+    /// Only `.crate` archives downloaded from remote registries are counted, packages extracted from archives cargo
+    /// already cached, and packages from path, git and local registry sources download `0` bytes. Transfers of
+    /// git repositories and registry indexes aren't counted.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Package, PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let foo = Package::from_str_ver("foo", "0.1.0", &PackageSource::crates_io())?;
+    ///
+    /// let fetched = fetcher.fetch_detailed(foo)?;
+    /// println!("{} bytes downloaded into {}", fetched.downloaded_bytes, fetched.root.display());
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn fetch_detailed(&mut self, package: Package) -> Result<FetchDetails, String> {
+        let before = self.downloaded_bytes.get();
+        let root = self.fetch(package)?;
+        Ok(FetchDetails {
+            root,
+            downloaded_bytes: self.downloaded_bytes.get() - before,
+        })
+    }
+
+    /// Returns the total size of the `.crate` archives downloaded by the fetcher so far, in bytes, by all the methods
+    /// fetching packages, e.g. [`Self::fetch_many`].
+    ///
+    /// Same as [`FetchDetails::downloaded_bytes`], only archives downloaded from remote registries are counted.
+    pub fn downloaded_bytes(&self) -> u64 {
+        self.downloaded_bytes.get()
+    }
+}
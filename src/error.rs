@@ -0,0 +1,275 @@
+use crate::Package;
+use cargo::{
+    core::PackageId,
+    util::{auth::AuthorizationError, errors::HttpNotSuccessful},
+};
+use semver::Version;
+use std::{fmt, path::PathBuf};
+
+/// Error of [`PackageFetcher::resolve_first`](crate::PackageFetcher::resolve_first), telling apart a package that
+/// doesn't exist from a version requirement that doesn't match any of its versions, and failures to reach the source
+/// that were retried from the ones that weren't.
+///
+/// Also the error of [`PackageFetcher::fetch`](crate::PackageFetcher::fetch),
+/// [`PackageFetcher::fetch_many`](crate::PackageFetcher::fetch_many),
+/// [`PackageFetcher::fetch_many_with_progress`](crate::PackageFetcher::fetch_many_with_progress) and
+/// [`PackageFetcher::resolve_and_fetch`](crate::PackageFetcher::resolve_and_fetch), where downloads are classified
+/// the same way. They returned [`String`] before 0.2.0.
+///
+/// Converts into the [`String`] error the rest of the API returns, with the same message as its [`fmt::Display`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveError {
+    /// The source doesn't have any versions of the package.
+    CrateNotFound {
+        /// Name of the package.
+        name: String,
+    },
+    /// The git repository doesn't have a package with the name, but it has other packages, e.g. a repository of
+    /// several crates, which the package is selected from by its name.
+    MemberNotFound {
+        /// Name of the package.
+        name: String,
+        /// Names of the packages in the repository, sorted.
+        members: Vec<String>,
+    },
+    /// The source has versions of the package, but none of them match the version requirement.
+    NoMatchingVersion {
+        /// Name of the package.
+        name: String,
+        /// Version requirement, as it was given, `*` if there was none.
+        req: String,
+        /// Versions of the package in the source, from the oldest to the newest, excluding yanked ones,
+        /// unless they are whitelisted.
+        available: Vec<Version>,
+    },
+    /// The selected version is lower than the minimum version set for the package with
+    /// [`PackageFetcherBuilder::minimum_version`](crate::PackageFetcherBuilder::minimum_version).
+    BelowMinimum {
+        /// Name of the package.
+        name: String,
+        /// The selected version.
+        selected: Version,
+        /// The minimum version.
+        floor: Version,
+    },
+    /// The `.crate` archive of the package is larger than the limit set with
+    /// [`PackageFetcherBuilder::max_crate_size`](crate::PackageFetcherBuilder::max_crate_size).
+    TooLarge {
+        /// The package.
+        package: Package,
+        /// Size of the archive, in bytes. If the server didn't report it, the download is aborted once it exceeds
+        /// the limit, and this is only the amount received until then.
+        size: u64,
+        /// The limit, in bytes.
+        limit: u64,
+    },
+    /// The source rejected the request for a lack of authentication or permissions, e.g. the registry responded
+    /// with 401 or 403, or no credentials for the git repository were accepted.
+    ///
+    /// Registries are never retried after rejecting a request. Cargo retries other network failures of libgit2
+    /// as many times as `net.retry` allows, and a git repository rejecting the credentials over HTTP is one of
+    /// them, so the same credentials are tried again, until the retries run out.
+    Unauthorized(String),
+    /// The source couldn't be reached because of a transient network failure, e.g. a timeout, a dropped
+    /// connection, or a 5xx response.
+    ///
+    /// Transient failures are retried as many times as `net.retry` allows, see
+    /// [`PackageFetcherBuilder::net_retries`](crate::PackageFetcherBuilder::net_retries), this is the failure of
+    /// the last try.
+    Network(String),
+    /// Resolving failed for another reason, e.g. the git reference doesn't exist, or the registry index is invalid.
+    ///
+    /// These failures are never retried.
+    Other(String),
+}
+
+impl ResolveError {
+    /// Classifies an error cargo returned while querying a source, the same way cargo decides whether to retry it.
+    pub(crate) fn from_cargo(error: anyhow::Error) -> Self {
+        if let Some(&CrateTooLarge { package, size, limit }) = error.chain().find_map(|e| e.downcast_ref()) {
+            Self::TooLarge {
+                package: Package { package_id: package },
+                size,
+                limit,
+            }
+        } else if error.chain().any(is_auth_failure) {
+            Self::Unauthorized(format!("{error:#}"))
+        } else if error.chain().any(is_transient) {
+            Self::Network(format!("{error:#}"))
+        } else {
+            Self::Other(format!("{error:#}"))
+        }
+    }
+}
+
+/// Error of a source for an archive larger than [`PackageFetcherBuilder::max_crate_size`], carried through cargo
+/// to be turned into [`ResolveError::TooLarge`].
+///
+/// [`PackageFetcherBuilder::max_crate_size`]: crate::PackageFetcherBuilder::max_crate_size
+#[derive(Debug)]
+pub(crate) struct CrateTooLarge {
+    pub(crate) package: PackageId,
+    pub(crate) size: u64,
+    pub(crate) limit: u64,
+}
+
+impl fmt::Display for CrateTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { package, size, limit } = self;
+        write!(
+            f,
+            "`{package}` is too large: its archive is {size} bytes, maximum allowed is {limit} bytes"
+        )
+    }
+}
+
+impl std::error::Error for CrateTooLarge {}
+
+/// Whether `error` is a failure to authenticate to a registry or a git repository.
+fn is_auth_failure(error: &(dyn std::error::Error + 'static)) -> bool {
+    // Missing or rejected registry token.
+    if error.is::<AuthorizationError>() {
+        return true;
+    }
+    if let Some(error) = error.downcast_ref::<HttpNotSuccessful>() {
+        return matches!(error.code, 401 | 403);
+    }
+    if let Some(error) = error.downcast_ref::<git2::Error>() {
+        return error.code() == git2::ErrorCode::Auth;
+    }
+    // Cargo only tells it apart by the message for git repositories, the error of libgit2 is generic.
+    error
+        .to_string()
+        .starts_with("failed to authenticate when downloading repository")
+}
+
+/// Whether `error` is a network failure cargo retries.
+fn is_transient(error: &(dyn std::error::Error + 'static)) -> bool {
+    if let Some(error) = error.downcast_ref::<HttpNotSuccessful>() {
+        return (500..600).contains(&error.code);
+    }
+    if let Some(error) = error.downcast_ref::<git2::Error>() {
+        return matches!(
+            error.class(),
+            git2::ErrorClass::Net | git2::ErrorClass::Os | git2::ErrorClass::Zlib | git2::ErrorClass::Http
+        );
+    }
+    if let Some(error) = error.downcast_ref::<curl::Error>() {
+        return error.is_couldnt_connect()
+            || error.is_couldnt_resolve_proxy()
+            || error.is_couldnt_resolve_host()
+            || error.is_operation_timedout()
+            || error.is_recv_error()
+            || error.is_send_error()
+            || error.is_http2_error()
+            || error.is_http2_stream_error()
+            || error.is_ssl_connect_error()
+            || error.is_partial_file();
+    }
+    false
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CrateNotFound { name } => write!(f, "no package named `{name}` was found"),
+            Self::MemberNotFound { name, members } => write!(
+                f,
+                "no package named `{name}` was found in the repository, available packages: {}",
+                members.join(", "),
+            ),
+            Self::NoMatchingVersion { name, req, available } => {
+                write!(f, "no version of `{name}` matches `{req}`, available versions: ")?;
+                let available: Vec<String> = available.iter().map(Version::to_string).collect();
+                f.write_str(&available.join(", "))
+            },
+            Self::BelowMinimum { name, selected, floor } => {
+                write!(
+                    f,
+                    "`{name}` resolved to {selected}, which is below its minimum version {floor}"
+                )
+            },
+            Self::TooLarge { package, size, limit } => fmt::Display::fmt(
+                &CrateTooLarge {
+                    package: package.package_id,
+                    size: *size,
+                    limit: *limit,
+                },
+                f,
+            ),
+            Self::Unauthorized(error) | Self::Network(error) | Self::Other(error) => f.write_str(error),
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+impl From<String> for ResolveError {
+    fn from(error: String) -> Self {
+        Self::Other(error)
+    }
+}
+
+impl From<ResolveError> for String {
+    fn from(error: ResolveError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Error of [`PackageFetcher::validate_path_source`](crate::PackageFetcher::validate_path_source), pointing at
+/// the problem with the manifest of a path package.
+///
+/// Converts into the [`String`] error the rest of the API returns, with the same message as its [`fmt::Display`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestError {
+    /// Path of the manifest.
+    pub path: PathBuf,
+    /// Line and column of the problem, both starting at 1, if cargo reports it, e.g. for invalid TOML syntax.
+    pub position: Option<(usize, usize)>,
+    /// Description of the problem, without the path of the manifest.
+    pub message: String,
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid manifest `{}`", self.path.display())?;
+        if let Some((line, column)) = self.position {
+            write!(f, " at line {line}, column {column}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
+impl From<ManifestError> for String {
+    fn from(error: ManifestError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Error of parsing a [`PackageSource`](crate::PackageSource) or a [`GitReference`](crate::GitReference) from
+/// a string.
+///
+/// Converts into the [`String`] error the rest of the API returns, with the same message as its [`fmt::Display`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The string that couldn't be parsed.
+    pub input: String,
+    /// Description of the problem.
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "can't parse `{}`: {}", self.input, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<ParseError> for String {
+    fn from(error: ParseError) -> Self {
+        error.to_string()
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{Package, PackageFetcher};
+use glob::Pattern;
+use std::path::PathBuf;
+
+/// Allowlist and denylist of package names, for [`PackageFetcher::fetch_many_filtered`] and
+/// [`PackageFetcher::fetch_stream_filtered`].
+///
+/// Names are matched against glob patterns, e.g. `serde*`. A package is fetched if its name matches any of the
+/// [`Self::allow`] patterns, or there are none, and none of the [`Self::deny`] patterns.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NameFilter {
+    /// Patterns of the names of packages to fetch, all packages are allowed if it's empty.
+    pub allow: Vec<String>,
+    /// Patterns of the names of packages to skip, even if they are allowed.
+    pub deny: Vec<String>,
+}
+
+impl NameFilter {
+    /// Compiles the patterns of the filter.
+    pub(crate) fn matcher(&self) -> Result<NameMatcher, String> {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| Pattern::new(pattern).map_err(|e| format!("invalid name pattern `{pattern}`: {e}")))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(NameMatcher {
+            allow: compile(&self.allow)?,
+            deny: compile(&self.deny)?,
+        })
+    }
+}
+
+/// Compiled patterns of a [`NameFilter`].
+pub(crate) struct NameMatcher {
+    allow: Vec<Pattern>,
+    deny: Vec<Pattern>,
+}
+
+impl NameMatcher {
+    /// Returns whether `package` passes the filter.
+    pub(crate) fn allows(&self, package: &Package) -> bool {
+        let name = package.package_id.name();
+        (self.allow.is_empty() || self.allow.iter().any(|pattern| pattern.matches(&name)))
+            && !self.deny.iter().any(|pattern| pattern.matches(&name))
+    }
+}
+
+/// Result of [`PackageFetcher::fetch_many_filtered`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilteredFetch {
+    /// Roots of the fetched packages, same as returned by [`PackageFetcher::fetch_many`].
+    pub roots: Vec<PathBuf>,
+    /// Packages that weren't fetched, as their names didn't pass the filter, in the order they were requested in.
+    pub skipped: Vec<Package>,
+}
+
+impl PackageFetcher {
+    /// Same as [`Self::fetch_many`], but only fetches the packages with names passing `filter`, returning the rest
+    /// as skipped.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{NameFilter, Package, PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let package = |name: &str| Package::from_str_ver(name, "1.0.0", &PackageSource::crates_io());
+    /// let packages = [package("serde")?, package("serde_derive")?, package("rand")?];
+    ///
+    /// let filter = NameFilter {
+    ///     allow: vec!["serde*".into()],
+    ///     deny: vec!["*_derive".into()],
+    /// };
+    /// // Only `serde` is fetched
+    /// let fetched = fetcher.fetch_many_filtered(&packages, &filter)?;
+    /// # Ok::<(), String>(())
+    /// ```
+    ///
+    /// Errors, if any of the patterns is invalid, or any of the allowed packages cannot be fetched.
+    pub fn fetch_many_filtered(&mut self, packages: &[Package], filter: &NameFilter) -> Result<FilteredFetch, String> {
+        let matcher = filter.matcher()?;
+        let (allowed, skipped): (Vec<Package>, _) = packages.iter().partition(|package| matcher.allows(package));
+        Ok(FilteredFetch {
+            roots: self.fetch_many(&allowed)?,
+            skipped,
+        })
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{bundle::walk, Package, PackageFetcher, ResolveError};
+use cargo::{
+    core::{source::MaybePackage, GitReference, PackageId, Source},
+    sources::PathSource,
+    CargoResult,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+impl PackageFetcher {
+    /// Fetches a git package through the shared checkout cache in `cache`.
+    pub(crate) fn fetch_shared_git(
+        &self,
+        package: Package,
+        cache: &Path,
+    ) -> Result<cargo::core::Package, ResolveError> {
+        let source_id = package.package_id.source_id();
+        let locked = source_id.precise().or(match source_id.git_reference() {
+            Some(GitReference::Rev(rev)) if is_full_hash(rev) => Some(rev),
+            _ => None,
+        });
+        if let Some(commit) = locked {
+            if let Some(package) = self.load_shared(package, cache, commit).map_err(|e| e.to_string())? {
+                return Ok(package);
+            }
+        }
+
+        let fetched = self.fetch_from_source(package)?;
+        let commit = fetched
+            .package_id()
+            .source_id()
+            .precise()
+            .ok_or_else(|| format!("cargo didn't lock `{}` to a commit", fetched.package_id()))?;
+        self.store_shared(&fetched, cache, commit).map_err(|e| e.to_string())?;
+
+        self.load_shared(package, cache, commit)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("`{}` is missing from the shared git cache", package.package_id).into())
+    }
+
+    /// Loads a package from the checkout of `commit` in the cache, if it's there.
+    fn load_shared(&self, package: Package, cache: &Path, commit: &str) -> CargoResult<Option<cargo::core::Package>> {
+        let checkout = cache.join(commit);
+        if !checkout.exists() {
+            return Ok(None);
+        }
+
+        let source_id = package.package_id.source_id().with_precise(Some(commit.to_owned()));
+        let package_id = PackageId::new(package.package_id.name(), package.package_id.version(), source_id)?;
+        let mut source = PathSource::new_recursive(&checkout, source_id, &self.config);
+        source.update()?;
+        match source.download(package_id)? {
+            MaybePackage::Ready(package) => Ok(Some(package)),
+            MaybePackage::Download { .. } => unreachable!("path sources don't download packages"),
+        }
+    }
+
+    /// Copies the cargo's checkout `package` is in into the cache, if it's not there yet.
+    fn store_shared(&self, package: &cargo::core::Package, cache: &Path, commit: &str) -> CargoResult<()> {
+        let checkout = cache.join(commit);
+        if checkout.exists() {
+            return Ok(());
+        }
+
+        // Cargo checks out into `git/checkouts/<repository>/<short commit hash>`.
+        let checkouts = self.config.git_path().join("checkouts");
+        let checkouts = checkouts.as_path_unlocked();
+        let root = package
+            .root()
+            .ancestors()
+            .find(|dir| dir.parent().and_then(Path::parent) == Some(checkouts))
+            .ok_or_else(|| anyhow::format_err!("`{}` is not in a cargo checkout", package.root().display()))?;
+
+        // Copy is renamed into place only once complete, so other processes never see a partial one.
+        let tmp = cache.join(format!(".{commit}-{}", std::process::id()));
+        if tmp.exists() {
+            fs::remove_dir_all(&tmp)?;
+        }
+        let mut files: Vec<PathBuf> = vec![];
+        walk(root, &mut files)?;
+        for file in files {
+            let relative = file.strip_prefix(root)?;
+            if relative.starts_with(".git") {
+                continue;
+            }
+            let copy = tmp.join(relative);
+            if let Some(parent) = copy.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(&file, &copy)?;
+        }
+
+        if fs::rename(&tmp, &checkout).is_err() {
+            // Another process stored the same checkout first.
+            fs::remove_dir_all(&tmp)?;
+            if !checkout.exists() {
+                anyhow::bail!("failed to store `{}` in the shared git cache", checkout.display());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `rev` is a full hash of a commit, rather than a short one or a reference.
+pub(crate) fn is_full_hash(rev: &str) -> bool {
+    rev.len() == 40 && rev.chars().all(|c| c.is_ascii_hexdigit())
+}
@@ -0,0 +1,133 @@
+use crate::PackageFetcher;
+use cargo::{
+    core::{source::MaybePackage, Dependency, PackageId, QueryKind, Source, SourceId, Summary},
+    CargoResult,
+};
+use std::{collections::HashMap, env, ffi::OsString, rc::Rc, task::Poll};
+
+impl PackageFetcher {
+    /// Sets the environment variables for git operations, until all of the returned guards are dropped.
+    pub(crate) fn git_env_guard(&self) -> Option<Rc<GitEnv>> {
+        if self.git_env.is_empty() {
+            return None;
+        }
+        let mut guard = self.git_env_guard.borrow_mut();
+        if let Some(git_env) = guard.upgrade() {
+            return Some(git_env);
+        }
+        let git_env = Rc::new(GitEnv::set(&self.git_env));
+        *guard = Rc::downgrade(&git_env);
+        Some(git_env)
+    }
+}
+
+/// Environment variables set for git operations, see
+/// [`PackageFetcherBuilder::git_env`](crate::PackageFetcherBuilder::git_env).
+///
+/// Previous values of the variables are restored when it's dropped.
+pub(crate) struct GitEnv {
+    previous: Vec<(String, Option<OsString>)>,
+}
+
+impl GitEnv {
+    fn set(vars: &HashMap<String, String>) -> Self {
+        let previous = vars
+            .iter()
+            .map(|(name, value)| {
+                let previous = env::var_os(name);
+                env::set_var(name, value);
+                (name.clone(), previous)
+            })
+            .collect();
+        Self { previous }
+    }
+}
+
+impl Drop for GitEnv {
+    fn drop(&mut self) {
+        for (name, previous) in self.previous.drain(..) {
+            match previous {
+                Some(value) => env::set_var(name, value),
+                None => env::remove_var(name),
+            }
+        }
+    }
+}
+
+/// Source of a git repository, which fetches it lazily, so the environment variables for git operations are set
+/// for as long as it exists.
+pub(crate) struct GitEnvSource<'a> {
+    inner: Box<dyn Source + 'a>,
+    _git_env: Rc<GitEnv>,
+}
+
+impl<'a> GitEnvSource<'a> {
+    pub(crate) fn new(inner: Box<dyn Source + 'a>, git_env: Rc<GitEnv>) -> Self {
+        Self {
+            inner,
+            _git_env: git_env,
+        }
+    }
+}
+
+impl Source for GitEnvSource<'_> {
+    fn source_id(&self) -> SourceId {
+        self.inner.source_id()
+    }
+
+    fn replaced_source_id(&self) -> SourceId {
+        self.inner.replaced_source_id()
+    }
+
+    fn supports_checksums(&self) -> bool {
+        self.inner.supports_checksums()
+    }
+
+    fn requires_precise(&self) -> bool {
+        self.inner.requires_precise()
+    }
+
+    fn query(&mut self, dep: &Dependency, kind: QueryKind, f: &mut dyn FnMut(Summary)) -> Poll<CargoResult<()>> {
+        self.inner.query(dep, kind, f)
+    }
+
+    fn invalidate_cache(&mut self) {
+        self.inner.invalidate_cache()
+    }
+
+    fn download(&mut self, package: PackageId) -> CargoResult<MaybePackage> {
+        self.inner.download(package)
+    }
+
+    fn finish_download(&mut self, package: PackageId, contents: Vec<u8>) -> CargoResult<cargo::core::Package> {
+        self.inner.finish_download(package, contents)
+    }
+
+    fn fingerprint(&self, pkg: &cargo::core::Package) -> CargoResult<String> {
+        self.inner.fingerprint(pkg)
+    }
+
+    fn verify(&self, pkg: PackageId) -> CargoResult<()> {
+        self.inner.verify(pkg)
+    }
+
+    fn describe(&self) -> String {
+        self.inner.describe()
+    }
+
+    fn is_replaced(&self) -> bool {
+        self.inner.is_replaced()
+    }
+
+    fn add_to_yanked_whitelist(&mut self, pkgs: &[PackageId]) {
+        self.inner.add_to_yanked_whitelist(pkgs)
+    }
+
+    fn is_yanked(&mut self, pkg: PackageId) -> Poll<CargoResult<bool>> {
+        self.inner.is_yanked(pkg)
+    }
+
+    fn block_until_ready(&mut self) -> CargoResult<()> {
+        self.inner.block_until_ready()
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{cache::git_ident, Package, PackageFetcher};
+use cargo::{sources::git::GitRemote, CargoResult};
+use std::collections::HashSet;
+
+impl PackageFetcher {
+    /// Returns the manifest of a package from a git source, as it's written in the repository, without checking
+    /// out the repository.
+    ///
+    /// The repository is fetched into cargo's git database, same as by [`Self::fetch`], and the manifest is read
+    /// from the commit the source is resolved to, so reading the metadata of packages from large repositories
+    /// doesn't write their files to the disk. The manifest is found by the name of the package, same as cargo
+    /// selects a package from a repository of several packages, see [`Self::git_members`]. Keys inherited from
+    /// the workspace aren't resolved.
+    ///
+    /// Falls back to fetching the package, and reading the manifest from the checkout, if the source is replaced
+    /// in the cargo config, cargo is offline, or the manifest isn't found in the repository.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Package, PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let source = PackageSource::git("https://github.com/serde-rs/serde", None).unwrap();
+    /// let serde = fetcher.resolve_first("serde", None, &source, None)?;
+    ///
+    /// let manifest = fetcher.git_manifest(serde)?;
+    /// # Ok::<(), String>(())
+    /// ```
+    ///
+    /// Errors, if the package isn't from a git source.
+    pub fn git_manifest(&mut self, package: Package) -> Result<String, String> {
+        if !package.package_id.source_id().is_git() {
+            return Err(format!("`{}` is not from a git source", package.package_id));
+        }
+        if let Some(manifest) = self.read_git_manifest(package).map_err(|e| e.to_string())? {
+            return Ok(manifest);
+        }
+
+        let package = self.fetch_package(package)?;
+        std::fs::read_to_string(package.manifest_path())
+            .map_err(|e| format!("can't read `{}`: {e}", package.manifest_path().display()))
+    }
+
+    /// Fetches the repository of a git package into cargo's git database, and reads its manifest from it.
+    ///
+    /// Returns [`None`], if the manifest has to be read from a checkout instead.
+    fn read_git_manifest(&self, package: Package) -> CargoResult<Option<String>> {
+        let _lock = self.lock_package_cache().map_err(anyhow::Error::msg)?;
+        let source_id = self.cached_revision(package.package_id.source_id());
+        let replaced = self
+            .load_source(source_id, &HashSet::new())
+            .map_err(anyhow::Error::msg)?
+            .is_replaced();
+        if replaced || self.config.offline() {
+            return Ok(None);
+        }
+
+        let db_path = self.config.git_path().join("db").join(git_ident(source_id));
+        let db_path = self.config.assert_package_cache_locked(&db_path);
+        let remote = GitRemote::new(source_id.url());
+        let locked = source_id.precise().map(git2::Oid::from_str).transpose()?;
+        let db = remote.db_at(db_path).ok();
+        let commit = match (locked, db) {
+            // Same as cargo, the repository isn't fetched again if it already has the commit it's locked to.
+            (Some(commit), Some(db)) if db.contains(commit) => commit,
+            (locked, db) => {
+                let reference = source_id
+                    .git_reference()
+                    .ok_or_else(|| anyhow::format_err!("`{source_id}` is not a git source"))?;
+                self.config
+                    .shell()
+                    .status("Updating", format!("git repository `{}`", remote.url()))?;
+                remote.checkout(db_path, db, reference, locked, &self.config)?.1
+            },
+        };
+        if self.pin_git_revisions && source_id.precise().is_none() {
+            self.git_revisions
+                .borrow_mut()
+                .entry(source_id)
+                .or_insert_with(|| commit.to_string());
+        }
+
+        let repo = git2::Repository::open(db_path)?;
+        let tree = repo.find_commit(commit)?.tree()?;
+        let mut manifest = None;
+        tree.walk(git2::TreeWalkMode::PreOrder, |_, entry| {
+            if entry.name() != Some("Cargo.toml") || entry.kind() != Some(git2::ObjectType::Blob) {
+                return git2::TreeWalkResult::Ok;
+            }
+            let contents = repo
+                .find_blob(entry.id())
+                .ok()
+                .and_then(|blob| String::from_utf8(blob.content().to_vec()).ok());
+            let name = contents.as_deref().and_then(|contents| {
+                let document = contents.parse::<toml_edit::Document>().ok()?;
+                document.get("package")?.get("name")?.as_str().map(str::to_owned)
+            });
+            if name.as_deref() == Some(package.package_id.name().as_str()) {
+                manifest = contents;
+                return git2::TreeWalkResult::Abort;
+            }
+            git2::TreeWalkResult::Ok
+        })
+        .or_else(|e| if manifest.is_some() { Ok(()) } else { Err(e) })?;
+        Ok(manifest)
+    }
+}
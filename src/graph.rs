@@ -0,0 +1,781 @@
+use crate::{query_blocking, Package, PackageFetcher};
+use cargo::{
+    core::{
+        compiler::{CompileKind, RustcTargetData},
+        dependency::DepKind,
+        find_workspace_root,
+        registry::PackageRegistry,
+        resolver::{
+            features::{FeatureOpts, FeatureResolver, FeaturesFor, ResolvedFeatures},
+            CliFeatures, ForceAllTargets, HasDevUnits, ResolveBehavior,
+        },
+        source::MaybePackage,
+        Dependency, PackageId, PackageIdSpec, QueryKind, Resolve, Source, SourceId, Summary, Workspace,
+        WorkspaceConfig,
+    },
+    CargoResult, Config,
+};
+use semver::Version;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    task::Poll,
+};
+
+/// Features to enable on the root package when resolving a [`ResolveGraph`].
+///
+/// Default value enables only the default features of the root package, same as building it without
+/// any feature flags.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Features {
+    /// Features to enable, same as `--features`.
+    pub features: Vec<String>,
+    /// Enables all features, same as `--all-features`.
+    pub all_features: bool,
+    /// Whether the `default` feature is enabled, inverse of `--no-default-features`.
+    pub default_features: bool,
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Self {
+            features: vec![],
+            all_features: false,
+            default_features: true,
+        }
+    }
+}
+
+/// How the `Cargo.lock` shipped with the root package is used when resolving a [`ResolveGraph`].
+///
+/// For path packages that are members of a workspace, it's the `Cargo.lock` of the workspace.
+///
+/// Lock files are read by the version of cargo this crate is built on, which doesn't support
+/// lock files of `version = 4`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum LockMode {
+    /// Ignores the lock file, resolving all dependencies to their newest matching versions.
+    #[default]
+    Unlocked,
+    /// Resolves dependencies to exactly the versions of the lock file, same as `--locked`.
+    ///
+    /// Errors, listing the packages that would change, if the lock file doesn't match the manifest,
+    /// or if the package doesn't have a lock file.
+    Locked,
+    /// Keeps the versions of the lock file, except for the listed packages, which are updated to their
+    /// newest matching versions, same as `cargo update -p`.
+    ///
+    /// Packages are listed as package id specs, e.g. `serde` or `serde@1.0.0`. If the package doesn't
+    /// have a lock file, resolves the same as [`LockMode::Unlocked`].
+    UpdateOnly(Vec<String>),
+}
+
+/// Version of the cargo resolver used to resolve features of a [`ResolveGraph`], see
+/// [resolver versions](https://doc.rust-lang.org/cargo/reference/resolver.html#resolver-versions).
+///
+/// Versions only differ in how features are resolved, dependencies resolve to the same versions with either.
+/// Resolver `"3"` (MSRV-aware version selection) is not supported by the version of cargo this crate is built on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ResolverVersion {
+    /// `resolver = "1"`, default for editions 2015 and 2018.
+    ///
+    /// Features enabled on a package by any of its dependents are unified, so the package is built
+    /// once, with the same features, for every use.
+    V1,
+    /// `resolver = "2"`, default since edition 2021.
+    ///
+    /// Features of build dependencies and proc-macros are resolved separately from the normal dependencies,
+    /// dependencies for platforms other than the host one don't enable features, and development dependencies
+    /// don't enable features, unless they are built. Resolving features this way needs to download every
+    /// package of the graph, to find out which of them are proc-macros.
+    V2,
+}
+
+impl From<ResolverVersion> for ResolveBehavior {
+    fn from(value: ResolverVersion) -> Self {
+        match value {
+            ResolverVersion::V1 => Self::V1,
+            ResolverVersion::V2 => Self::V2,
+        }
+    }
+}
+
+/// Options of [`PackageFetcher::resolve_graph_with`].
+///
+/// Default value resolves the same as [`PackageFetcher::resolve_graph`] with default [`Features`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolveOptions {
+    /// Features to enable on the root package.
+    pub features: Features,
+    /// How the lock file of the root package is used.
+    pub lock: LockMode,
+    /// Resolver version to resolve features with, [`None`] uses the one of the root package, set by its `resolver`
+    /// manifest key, or implied by its edition, or the one of its workspace, see [`PackageFetcher::resolve_graph`].
+    pub resolver: Option<ResolverVersion>,
+    /// Whether development dependencies of the root package are included, same as building its tests.
+    ///
+    /// Development dependencies of other packages are never included, cargo doesn't resolve them, as they are
+    /// not needed to build the packages. Disabled by default.
+    pub dev_dependencies: bool,
+    /// Whether build dependencies are included.
+    ///
+    /// Cargo always resolves build dependencies, when disabled, their edges are removed from the graph, along with
+    /// the packages only reachable through them. Enabled by default.
+    pub build_dependencies: bool,
+    /// Versions to update packages of the graph to, same as `cargo update -p <spec> --precise <version>`.
+    ///
+    /// Packages are listed as package id specs, e.g. `serde` or `serde@1.0.0`, and must be from registries. The rest
+    /// of the graph keeps the versions it resolves to without them, only the dependencies of the updated packages
+    /// can change. Errors, if a version doesn't exist in the registry of its package, or doesn't match the version
+    /// requirements of the packages depending on it, and with [`LockMode::Locked`]. Empty by default.
+    pub precise: BTreeMap<String, Version>,
+}
+
+impl Default for ResolveOptions {
+    fn default() -> Self {
+        Self {
+            features: Features::default(),
+            lock: LockMode::default(),
+            resolver: None,
+            dev_dependencies: false,
+            build_dependencies: true,
+            precise: BTreeMap::new(),
+        }
+    }
+}
+
+/// Kind of a dependency, corresponds to the table it was declared in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "lowercase"))]
+pub enum DependencyKind {
+    /// `[dependencies]`
+    Normal,
+    /// `[dev-dependencies]`
+    Development,
+    /// `[build-dependencies]`
+    Build,
+}
+
+impl From<DepKind> for DependencyKind {
+    fn from(value: DepKind) -> Self {
+        match value {
+            DepKind::Normal => Self::Normal,
+            DepKind::Development => Self::Development,
+            DepKind::Build => Self::Build,
+        }
+    }
+}
+
+/// Resolved dependency graph of a package, returned by [`PackageFetcher::resolve_graph`].
+///
+/// Nodes are ordered by their [`Package`], so the graph is stable across runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ResolveGraph {
+    root: Package,
+    nodes: Vec<ResolveNode>,
+}
+
+impl ResolveGraph {
+    /// Returns the package this graph was resolved for.
+    pub fn root(&self) -> Package {
+        self.root
+    }
+
+    /// Returns all packages of the graph, including the root.
+    pub fn nodes(&self) -> &[ResolveNode] {
+        &self.nodes
+    }
+
+    /// Returns the node of `package`, if it's a part of the graph.
+    pub fn node(&self, package: &Package) -> Option<&ResolveNode> {
+        self.nodes
+            .binary_search_by(|node| node.package.cmp(package))
+            .ok()
+            .map(|idx| &self.nodes[idx])
+    }
+
+    /// Returns direct dependencies of `package`, or an empty slice, if it's not a part of the graph.
+    pub fn dependencies(&self, package: &Package) -> &[ResolveEdge] {
+        self.node(package).map_or(&[], |node| &node.dependencies)
+    }
+
+    /// Returns packages that depend on `package` directly or transitively, through any kind of dependency,
+    /// sorted, without `package` itself. Empty, if it's not a part of the graph.
+    pub fn dependents(&self, package: &Package) -> Vec<Package> {
+        let mut dependents = HashSet::new();
+        let mut queue = vec![*package];
+        while let Some(dependency) = queue.pop() {
+            for node in &self.nodes {
+                let depends = node.dependencies.iter().any(|edge| edge.package == dependency);
+                if depends && node.package != *package && dependents.insert(node.package) {
+                    queue.push(node.package);
+                }
+            }
+        }
+        let mut dependents: Vec<Package> = dependents.into_iter().collect();
+        dependents.sort_unstable();
+        dependents
+    }
+}
+
+/// A package in a [`ResolveGraph`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ResolveNode {
+    /// The resolved package.
+    pub package: Package,
+    /// Features activated on the package.
+    ///
+    /// With [`ResolverVersion::V2`], these are the features the package is built with for the target, empty if
+    /// it's only built for the host, or not built on the host platform at all.
+    pub features: Vec<String>,
+    /// Features activated on the package, when it's built for the host, as a build dependency or a proc-macro.
+    ///
+    /// Same as [`Self::features`] with [`ResolverVersion::V1`], which unifies them.
+    pub host_features: Vec<String>,
+    /// Sha256 checksum of the package archive, only known for registry packages.
+    pub checksum: Option<String>,
+    /// Direct dependencies of the package.
+    pub dependencies: Vec<ResolveEdge>,
+}
+
+/// A dependency edge in a [`ResolveGraph`].
+///
+/// Package depending on the same package more than once (e.g. as a normal and a build dependency)
+/// has an edge for each of the declarations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ResolveEdge {
+    /// Package the dependency resolved to.
+    pub package: Package,
+    /// Kind of the dependency.
+    pub kind: DependencyKind,
+    /// Features requested by the dependency declaration.
+    pub features: Vec<String>,
+    /// Whether the dependency declaration enables default features.
+    pub default_features: bool,
+    /// Whether the dependency is optional.
+    pub optional: bool,
+}
+
+impl PackageFetcher {
+    /// Resolves the full dependency graph of `root`, with `features` enabled on it.
+    ///
+    /// Dependencies are resolved the way cargo resolves them for `root` being the only member
+    /// of a workspace, without a lock file, using its resolver version. Development dependencies are not included.
+    ///
+    /// Path packages that are members of a workspace are resolved in it instead, same as cargo building them, so
+    /// `[patch]` sections and the resolver version of the workspace root apply. Git and registry packages are resolved
+    /// the way cargo resolves them as dependencies, ignoring the workspace they come from, but `[patch]` of their own
+    /// manifest still applies.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Features, Package, PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let foo = Package::from_str_ver("foo", "0.1.0", &PackageSource::crates_io())?;
+    ///
+    /// let graph = fetcher.resolve_graph(foo, &Features::default())?;
+    /// for dependency in graph.dependencies(&foo) {
+    ///     println!("{}", dependency.package.to_spec_string());
+    /// }
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn resolve_graph(&mut self, root: Package, features: &Features) -> Result<ResolveGraph, String> {
+        self.resolve_graph_with(
+            root,
+            &ResolveOptions {
+                features: features.clone(),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Resolves the dependency graph of `root`, same as [`Self::resolve_graph`], and returns the packages of it that
+    /// depend on `target`, see [`ResolveGraph::dependents`]. These are the packages that are rebuilt when `target`
+    /// changes, if they are built for `root`.
+    ///
+    /// Errors, if `target` is not a part of the graph.
+    pub fn dependents_of(
+        &mut self,
+        root: Package,
+        target: Package,
+        features: &Features,
+    ) -> Result<Vec<Package>, String> {
+        let graph = self.resolve_graph(root, features)?;
+        if graph.node(&target).is_none() {
+            return Err(format!(
+                "`{}` is not a dependency of `{}`",
+                target.to_spec_string(),
+                root.to_spec_string()
+            ));
+        }
+        Ok(graph.dependents(&target))
+    }
+
+    /// Resolves the dependency graph of `root`, same as [`Self::resolve_graph`], and returns the features enabled
+    /// on `dependency`, after unifying the features all the packages depending on it enable, sorted.
+    ///
+    /// With [`ResolverVersion::V2`], a package built both for the target and for the host, as a build dependency
+    /// or a dependency of a proc-macro, is built with separately unified features for each, these are the features
+    /// of both builds, see [`ResolveNode::features`] and [`ResolveNode::host_features`] for them separately.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Features, Package, PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let app = Package::from_str_ver("app", "0.1.0", &PackageSource::crates_io())?;
+    /// let serde = Package::from_str_ver("serde", "1.0.152", &PackageSource::crates_io())?;
+    ///
+    /// let features = fetcher.resolved_features(app, &Features::default(), &serde)?;
+    /// # Ok::<(), String>(())
+    /// ```
+    ///
+    /// Errors, if `dependency` is not a part of the graph.
+    pub fn resolved_features(
+        &mut self,
+        root: Package,
+        root_features: &Features,
+        dependency: &Package,
+    ) -> Result<Vec<String>, String> {
+        let graph = self.resolve_graph(root, root_features)?;
+        let node = graph.node(dependency).ok_or_else(|| {
+            format!(
+                "`{}` is not a dependency of `{}`",
+                dependency.to_spec_string(),
+                root.to_spec_string()
+            )
+        })?;
+        let mut features: Vec<String> = node.features.iter().chain(&node.host_features).cloned().collect();
+        features.sort_unstable();
+        features.dedup();
+        Ok(features)
+    }
+
+    /// Resolves the dependency graph of `root`, same as [`Self::resolve_graph`], and returns all of its packages,
+    /// sorted, e.g. to fetch them with [`Self::fetch_many`]. The root itself is only included if `include_root` is set.
+    ///
+    /// Each package is listed once, regardless of how many packages depend on it.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Features, Package, PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let app = Package::from_str_ver("app", "0.1.0", &PackageSource::crates_io())?;
+    ///
+    /// let dependencies = fetcher.flat_dependency_set(app, &Features::default(), false)?;
+    /// let roots = fetcher.fetch_many(&dependencies)?;
+    /// # Ok::<(), cargo_fetch::ResolveError>(())
+    /// ```
+    pub fn flat_dependency_set(
+        &mut self,
+        root: Package,
+        features: &Features,
+        include_root: bool,
+    ) -> Result<Vec<Package>, String> {
+        let graph = self.resolve_graph(root, features)?;
+        // Nodes are already sorted, and there's one for each package.
+        Ok(graph
+            .nodes()
+            .iter()
+            .map(|node| node.package)
+            .filter(|package| include_root || *package != root)
+            .collect())
+    }
+
+    /// Same as [`Self::resolve_graph`], but with additional [`ResolveOptions`].
+    pub fn resolve_graph_with(&mut self, root: Package, options: &ResolveOptions) -> Result<ResolveGraph, String> {
+        if options.lock == LockMode::Locked && !options.precise.is_empty() {
+            return Err("precise versions can't be set with `LockMode::Locked`".to_owned());
+        }
+        let _lock = self.lock_package_cache()?;
+        let package = self.fetch_package(root)?;
+        let ws = workspace(package, &self.config).map_err(|e| e.to_string())?;
+        let features = &options.features;
+        let cli_features =
+            CliFeatures::from_command_line(&features.features, features.all_features, features.default_features)
+                .map_err(|e| e.to_string())?;
+        // Cargo's resolver refreshes each index once per session, so indexes due for a refresh are forgotten.
+        self.config
+            .updated_sources()
+            .retain(|&id| !id.is_remote_registry() || self.index_needs_refresh(id) != Some(true));
+        // The registry loads git sources of the dependencies itself.
+        let _git_env = self.git_env_guard();
+        let mut registry = PackageRegistry::new(&self.config).map_err(|e| e.to_string())?;
+        let behavior = options.resolver.map_or(ws.resolve_behavior(), ResolveBehavior::from);
+        let has_dev_units = if options.dev_dependencies {
+            HasDevUnits::Yes
+        } else {
+            HasDevUnits::No
+        };
+
+        let previous = resolve_lockfile(&ws, &mut registry, &options.lock).map_err(|e| e.to_string())?;
+        let resolve = cargo::ops::resolve_with_previous(
+            &mut registry,
+            &ws,
+            &cli_features,
+            has_dev_units,
+            previous.as_ref(),
+            None,
+            &[PackageIdSpec::from_package_id(root.package_id)],
+            // Patches are already registered when resolving the lock file.
+            previous.is_none(),
+        )
+        .map_err(|e| e.to_string())?;
+        let resolve = if options.precise.is_empty() {
+            resolve
+        } else {
+            self.resolve_precise(
+                &mut registry,
+                &ws,
+                &cli_features,
+                has_dev_units,
+                resolve,
+                &options.precise,
+            )
+            .map_err(|e| e.to_string())?
+        };
+        let resolved_features = match behavior {
+            // Features unified by the dependency resolver are the same as the ones the feature resolver would return.
+            ResolveBehavior::V1 => None,
+            ResolveBehavior::V2 => Some(
+                resolve_features(
+                    &ws,
+                    registry,
+                    &resolve,
+                    &cli_features,
+                    root.package_id,
+                    has_dev_units,
+                    behavior,
+                )
+                .map_err(|e| e.to_string())?,
+            ),
+        };
+        let features = |package_id, features_for| -> Vec<String> {
+            let features = match &resolved_features {
+                Some(resolved) => resolved
+                    .activated_features_unverified(package_id, features_for)
+                    .unwrap_or_default(),
+                None => resolve.features(package_id).to_vec(),
+            };
+            features.iter().map(|f| f.to_string()).collect()
+        };
+
+        let mut nodes: Vec<ResolveNode> = resolve
+            .iter()
+            .map(|package_id| {
+                let mut dependencies: Vec<ResolveEdge> = resolve
+                    .deps(package_id)
+                    .flat_map(|(dep_id, deps)| {
+                        deps.iter()
+                            .filter(|dep| options.build_dependencies || dep.kind() != DepKind::Build)
+                            .map(move |dep| ResolveEdge {
+                                package: Package { package_id: dep_id },
+                                kind: dep.kind().into(),
+                                features: dep.features().iter().map(|f| f.to_string()).collect(),
+                                default_features: dep.uses_default_features(),
+                                optional: dep.is_optional(),
+                            })
+                    })
+                    .collect();
+                dependencies.sort_by_key(|edge| (edge.package, edge.kind));
+
+                ResolveNode {
+                    package: Package { package_id },
+                    features: features(package_id, FeaturesFor::NormalOrDev),
+                    host_features: features(package_id, FeaturesFor::HostDep),
+                    checksum: resolve.checksums().get(&package_id).cloned().flatten(),
+                    dependencies,
+                }
+            })
+            .collect();
+        nodes.sort_by_key(|node| node.package);
+        if !options.build_dependencies {
+            nodes = reachable(root, nodes);
+        }
+        for node in nodes.iter().filter(|node| node.package != root) {
+            self.check_minimum(node.package)?;
+        }
+
+        Ok(ResolveGraph { root, nodes })
+    }
+}
+
+impl PackageFetcher {
+    /// Resolves `previous` again, with the packages of `precise` updated to their versions, same as
+    /// `cargo update -p <spec> --precise <version>`.
+    fn resolve_precise<'cfg>(
+        &'cfg self,
+        registry: &mut PackageRegistry<'cfg>,
+        ws: &Workspace<'cfg>,
+        cli_features: &CliFeatures,
+        has_dev_units: HasDevUnits,
+        previous: Resolve,
+        precise: &BTreeMap<String, Version>,
+    ) -> CargoResult<Resolve> {
+        let mut by_source: HashMap<SourceId, Vec<(PackageId, Version)>> = HashMap::new();
+        for (spec, version) in precise {
+            let package_id = previous.query(spec)?;
+            if !package_id.source_id().is_registry() {
+                anyhow::bail!("precise version can't be set for `{package_id}`, it's not from a registry");
+            }
+            let source_id = package_id.source_id().with_precise(None);
+            by_source
+                .entry(source_id)
+                .or_default()
+                .push((package_id, version.clone()));
+        }
+
+        let mut to_avoid = HashSet::new();
+        for (source_id, precise) in by_source {
+            let mut source = self
+                .load_source(source_id, &HashSet::new())
+                .map_err(anyhow::Error::msg)?;
+            for (package_id, version) in &precise {
+                let dep = Dependency::parse(package_id.name(), Some(&format!("={version}")), source_id)?;
+                let mut found = false;
+                query_blocking(&mut *source, &dep, &mut |summary| found |= summary.version() == version)?;
+                if !found {
+                    anyhow::bail!(
+                        "version {version} of `{}` doesn't exist in {source_id}",
+                        package_id.name()
+                    );
+                }
+                to_avoid.insert(*package_id);
+            }
+            // Replaces the source cargo loaded, cargo's own `--precise` only supports a single package per source.
+            registry.add_preloaded(Box::new(PreciseSource { inner: source, precise }));
+        }
+
+        let root = ws.current()?.package_id();
+        cargo::ops::resolve_with_previous(
+            registry,
+            ws,
+            cli_features,
+            has_dev_units,
+            Some(&previous),
+            Some(&to_avoid),
+            &[PackageIdSpec::from_package_id(root)],
+            false,
+        )
+    }
+}
+
+/// Source offering only the precise versions of packages, for dependencies matching their current versions,
+/// same as cargo's registry sources with a precise version.
+struct PreciseSource<'a> {
+    inner: Box<dyn Source + 'a>,
+    /// Current packages, and the versions to update them to.
+    precise: Vec<(PackageId, Version)>,
+}
+
+impl Source for PreciseSource<'_> {
+    fn source_id(&self) -> SourceId {
+        self.inner.source_id()
+    }
+
+    fn replaced_source_id(&self) -> SourceId {
+        self.inner.replaced_source_id()
+    }
+
+    fn supports_checksums(&self) -> bool {
+        self.inner.supports_checksums()
+    }
+
+    fn requires_precise(&self) -> bool {
+        self.inner.requires_precise()
+    }
+
+    fn query(&mut self, dep: &Dependency, kind: QueryKind, f: &mut dyn FnMut(Summary)) -> Poll<CargoResult<()>> {
+        let precise = self
+            .precise
+            .iter()
+            .find(|(current, _)| current.name() == dep.package_name() && dep.version_req().matches(current.version()));
+        match precise {
+            Some((_, version)) => self.inner.query(dep, kind, &mut |summary| {
+                if summary.version() == version {
+                    f(summary)
+                }
+            }),
+            None => self.inner.query(dep, kind, f),
+        }
+    }
+
+    fn invalidate_cache(&mut self) {
+        self.inner.invalidate_cache()
+    }
+
+    fn download(&mut self, package: PackageId) -> CargoResult<MaybePackage> {
+        self.inner.download(package)
+    }
+
+    fn finish_download(&mut self, package: PackageId, contents: Vec<u8>) -> CargoResult<cargo::core::Package> {
+        self.inner.finish_download(package, contents)
+    }
+
+    fn fingerprint(&self, pkg: &cargo::core::Package) -> CargoResult<String> {
+        self.inner.fingerprint(pkg)
+    }
+
+    fn verify(&self, pkg: PackageId) -> CargoResult<()> {
+        self.inner.verify(pkg)
+    }
+
+    fn describe(&self) -> String {
+        self.inner.describe()
+    }
+
+    fn is_replaced(&self) -> bool {
+        self.inner.is_replaced()
+    }
+
+    fn add_to_yanked_whitelist(&mut self, pkgs: &[PackageId]) {
+        self.inner.add_to_yanked_whitelist(pkgs)
+    }
+
+    fn is_yanked(&mut self, pkg: PackageId) -> Poll<CargoResult<bool>> {
+        self.inner.is_yanked(pkg)
+    }
+
+    fn block_until_ready(&mut self) -> CargoResult<()> {
+        self.inner.block_until_ready()
+    }
+}
+
+/// Workspace to resolve `package` in.
+///
+/// Path packages that are members of a workspace are resolved in it, same as cargo building them, so `[patch]`,
+/// the resolver version and the lock file of the workspace root apply. Others are resolved in an ephemeral workspace
+/// with only them, same as cargo resolves git dependencies, ignoring the workspace they come from.
+fn workspace<'cfg>(package: cargo::core::Package, config: &'cfg Config) -> CargoResult<Workspace<'cfg>> {
+    if package.package_id().source_id().is_path()
+        && matches!(package.manifest().workspace_config(), WorkspaceConfig::Member { .. })
+        && find_workspace_root(package.manifest_path(), config)?.is_some()
+    {
+        return Workspace::new(package.manifest_path(), config);
+    }
+    Workspace::ephemeral(package, config, None, false)
+}
+
+/// Removes nodes that are not reachable from `root` through the edges of `nodes`, which are sorted by their package.
+fn reachable(root: Package, nodes: Vec<ResolveNode>) -> Vec<ResolveNode> {
+    let mut reached = HashSet::from([root]);
+    let mut queue = vec![root];
+    while let Some(package) = queue.pop() {
+        let Ok(idx) = nodes.binary_search_by(|node| node.package.cmp(&package)) else {
+            continue;
+        };
+        for edge in &nodes[idx].dependencies {
+            if reached.insert(edge.package) {
+                queue.push(edge.package);
+            }
+        }
+    }
+    nodes
+        .into_iter()
+        .filter(|node| reached.contains(&node.package))
+        .collect()
+}
+
+/// Resolves features of the packages of `resolve` with the feature resolver of `behavior`, for the host platform.
+fn resolve_features<'cfg>(
+    ws: &Workspace<'cfg>,
+    registry: PackageRegistry<'cfg>,
+    resolve: &Resolve,
+    cli_features: &CliFeatures,
+    root: PackageId,
+    has_dev_units: HasDevUnits,
+    behavior: ResolveBehavior,
+) -> CargoResult<ResolvedFeatures> {
+    let kinds = [CompileKind::Host];
+    let target_data = RustcTargetData::new(ws, &kinds)?;
+    let package_ids: Vec<PackageId> = resolve.iter().collect();
+    let packages = registry.get(&package_ids)?;
+    packages.download_accessible(
+        resolve,
+        &[root],
+        has_dev_units,
+        &kinds,
+        &target_data,
+        ForceAllTargets::No,
+    )?;
+    FeatureResolver::resolve(
+        ws,
+        &target_data,
+        resolve,
+        &packages,
+        cli_features,
+        &[PackageIdSpec::from_package_id(root)],
+        &kinds,
+        FeatureOpts::new_behavior(behavior, has_dev_units),
+    )
+}
+
+/// Resolves the lock file of the workspace according to `lock`, the same way cargo does before
+/// resolving the requested features.
+///
+/// Returns [`None`], if the lock file should be ignored.
+fn resolve_lockfile<'cfg>(
+    ws: &Workspace<'cfg>,
+    registry: &mut PackageRegistry<'cfg>,
+    lock: &LockMode,
+) -> CargoResult<Option<Resolve>> {
+    if *lock == LockMode::Unlocked {
+        return Ok(None);
+    }
+
+    let root = ws.current()?.package_id();
+    let mut previous = match cargo::ops::load_pkg_lockfile(ws)? {
+        Some(previous) => previous,
+        None if *lock == LockMode::Locked => {
+            anyhow::bail!("`{root}` doesn't have a `Cargo.lock`, but `LockMode::Locked` was requested")
+        },
+        None => return Ok(None),
+    };
+
+    let mut to_avoid = HashSet::new();
+    if let LockMode::UpdateOnly(specs) = lock {
+        let mut sources = vec![];
+        for spec in specs {
+            let package_id = previous.query(spec)?;
+            to_avoid.insert(package_id);
+            sources.push(package_id.source_id().with_precise(None));
+        }
+        registry.add_sources(sources)?;
+    }
+
+    let mut resolve = cargo::ops::resolve_with_previous(
+        registry,
+        ws,
+        &CliFeatures::new_all(true),
+        HasDevUnits::Yes,
+        Some(&previous),
+        Some(&to_avoid),
+        &[],
+        true,
+    )?;
+
+    // Compares the serialized lock files same as cargo, resolve loaded from the lock file lacks summaries
+    // of the packages, so it never equals the new one.
+    if *lock == LockMode::Locked
+        && cargo::ops::resolve_to_string(ws, &mut resolve)? != cargo::ops::resolve_to_string(ws, &mut previous)?
+    {
+        let diff = |a: &Resolve, b: &Resolve| {
+            let mut ids: Vec<PackageId> = a.iter().filter(|id| !b.contains(id)).collect();
+            ids.sort();
+            ids.iter().map(|id| format!("\n  {id}")).collect::<String>()
+        };
+        let (added, removed) = (diff(&resolve, &previous), diff(&previous, &resolve));
+        let mut msg = format!("`Cargo.lock` of `{root}` needs to be updated, but `LockMode::Locked` was requested");
+        if !added.is_empty() {
+            msg.push_str(&format!("\nwould add:{added}"));
+        }
+        if !removed.is_empty() {
+            msg.push_str(&format!("\nwould remove:{removed}"));
+        }
+        if added.is_empty() && removed.is_empty() {
+            msg.push_str("\nlocked packages would stay the same, but their dependencies or checksums would change");
+        }
+        anyhow::bail!(msg);
+    }
+
+    Ok(Some(resolve))
+}
@@ -0,0 +1,90 @@
+use crate::{not_found, Package, PackageFetcher, PackageSource, ResolveError};
+use cargo::core::{Dependency, PackageId, QueryKind};
+use std::{collections::HashSet, task::Poll};
+
+impl PackageFetcher {
+    /// Resolves the version of a package `cargo add` would add, and cargo would then build, which is the
+    /// recommended way to resolve a package to depend on.
+    ///
+    /// Without a version requirement, it's the newest version that is neither yanked nor a pre-release, or the
+    /// newest pre-release, if the package only has pre-releases, same as `cargo add <name>`.
+    ///
+    /// With a version requirement, it's the newest version matching it that isn't yanked, same as the cargo resolver
+    /// selects it for a dependency added with `cargo add <name>@<version>`, so pre-releases are only selected,
+    /// if the requirement names a pre-release of the same version, e.g. `2.0.0-rc`, see [`Self::resolve_first`].
+    ///
+    /// Either way, name of the package is matched regardless of `-` and `_`, and the package is returned with its
+    /// actual name then, same as `cargo add` translates it.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{PackageFetcher, PackageSource};
+    /// let fetcher = PackageFetcher::new()?;
+    /// let source = PackageSource::crates_io();
+    ///
+    /// // Same as `cargo add serde_json`, which adds `serde_json` with its newest stable version
+    /// let serde_json = fetcher.resolve_installable("serde_json", None, &source)?;
+    /// // Same as `cargo add tokio@1.0`
+    /// let tokio = fetcher.resolve_installable("tokio", Some("1.0"), &source)?;
+    /// # Ok::<(), cargo_fetch::ResolveError>(())
+    /// ```
+    ///
+    /// Path and git sources only resolve the packages of the same name, the same way:
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{PackageFetcher, PackageSource};
+    /// let fetcher = PackageFetcher::new()?;
+    /// let source = PackageSource::git("https://github.com/serde-rs/serde", None).unwrap();
+    ///
+    /// let serde_derive = fetcher.resolve_installable("serde_derive", None, &source)?;
+    /// # Ok::<(), cargo_fetch::ResolveError>(())
+    /// ```
+    pub fn resolve_installable<N: AsRef<str>>(
+        &self,
+        name: N,
+        version: Option<&str>,
+        source: &PackageSource,
+    ) -> Result<Package, ResolveError> {
+        let name = name.as_ref();
+        let _lock = self.lock_package_cache()?;
+        let source_id = self.source_id(source).map_err(|e| e.to_string())?;
+        let mut src = self.load_source(source_id, &HashSet::new())?;
+        let dep = Dependency::parse(name, None, src.source_id()).map_err(|e| e.to_string())?;
+
+        // Same as `cargo add`, pre-releases sort before all releases.
+        let mut latest: Option<PackageId> = None;
+        let normalized = |name: &str| name.replace('-', "_");
+        let is_newer = |a: PackageId, b: PackageId| {
+            let (a, b) = (a.version(), b.version());
+            match (a.pre.is_empty(), b.pre.is_empty()) {
+                (true, false) => true,
+                (false, true) => false,
+                _ => self.is_newer(a, b),
+            }
+        };
+        loop {
+            let queried = src.query(&dep, QueryKind::Fuzzy, &mut |sum| {
+                // Path and git sources return all of their packages for fuzzy queries, regardless of the name.
+                if normalized(&sum.name()) != normalized(name) {
+                    return;
+                }
+                let package_id = sum.package_id();
+                if latest.map_or(true, |latest| is_newer(package_id, latest)) {
+                    latest = Some(package_id);
+                }
+            });
+            match queried {
+                Poll::Ready(res) => break res.map_err(ResolveError::from_cargo)?,
+                Poll::Pending => src.block_until_ready().map_err(ResolveError::from_cargo)?,
+            }
+        }
+
+        match latest {
+            // The requirement is matched against the package under its actual name.
+            Some(package_id) if version.is_some() => {
+                self.resolve_first(package_id.name().as_str(), version, source, None)
+            },
+            Some(package_id) => self.check_minimum(Package { package_id }),
+            None => Err(not_found(&mut *src, name, version)),
+        }
+    }
+}
@@ -1,12 +1,98 @@
 #![doc = include_str!("../README.md")]
 
+mod batch;
+mod builder;
+mod bundle;
+mod cache;
+mod check;
+mod clean;
+#[cfg(feature = "crates-io-api")]
+mod crates_io;
+mod credential;
+mod dependencies;
+mod diff;
+mod download;
+mod error;
+mod filter;
+mod git_cache;
+mod git_env;
+mod git_manifest;
+mod graph;
+mod installable;
+mod license;
+mod lock;
+mod manifest;
+mod members;
+mod normalize;
+mod ordering;
+mod package;
+mod parse;
+mod precedence;
+mod read;
+mod registry;
+mod registry_handle;
+mod registry_info;
+mod repository;
+mod rewrite;
+mod sbom;
+#[cfg(feature = "gpg")]
+mod signature;
+mod snapshot;
+mod source_cache;
+mod store;
+mod stream;
+mod targets;
+mod tree;
+mod vendor;
+mod versions;
+mod workspace;
+
+pub use batch::ResolveRequest;
+pub use builder::{GitBackend, PackageFetcherBuilder};
+pub use cache::CachePaths;
+pub use clean::CleanOptions;
+pub use credential::RegistryToken;
+pub use dependencies::{DeclaredDependency, DependencyTable, OptionalDepInfo};
+#[cfg(feature = "crates-io-api")]
+pub use crates_io::CratesIoInfo;
+pub use diff::FileDiff;
+pub use download::FetchDetails;
+pub use error::{ManifestError, ParseError, ResolveError};
+pub use filter::{FilteredFetch, NameFilter};
+pub use license::{LICENSE_FILE_KEY, NO_LICENSE_KEY};
+pub use lock::LockBehavior;
+pub use graph::{
+    DependencyKind, Features, LockMode, ResolveEdge, ResolveGraph, ResolveNode, ResolveOptions, ResolverVersion,
+};
+pub use registry::{IndexFreshness, RegistryIndexConfig};
+pub use registry_handle::Registry;
+pub use registry_info::RegistryInfo;
+pub use repository::RepositoryMatch;
+pub use sbom::SbomFormat;
+#[cfg(feature = "gpg")]
+pub use signature::VerifiedTag;
+pub use stream::StreamedPackage;
+pub use targets::TargetKinds;
+pub use tree::TreeOptions;
+pub use versions::VersionInfo;
+pub use workspace::WorkspaceDependency;
+
 use cargo::{
-    core::{PackageId, PackageSet, SourceId, SourceMap},
-    sources::CRATES_IO_INDEX,
-    util::IntoUrl,
+    core::{Dependency, PackageId, PackageSet, QueryKind, Source, SourceId, SourceMap, Summary},
+    sources::{SourceConfigMap, CRATES_IO_INDEX},
+    util::{CanonicalUrl, IntoUrl},
+    CargoResult,
+};
+use semver::{Version, VersionReq};
+use std::{
+    collections::{HashMap, HashSet},
+    io::Write,
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+    task::Poll,
+    time::{Duration, Instant, SystemTime},
 };
-use semver::Version;
-use std::{collections::HashSet, io::Write, path::PathBuf, str::FromStr, task::Poll};
 use url::Url;
 
 /// Main API of this library.
@@ -17,14 +103,51 @@ use url::Url;
 ///
 /// With default [`PackageFetcher`], cargo will try to output status and errors to the `stdout` and `stderr` of
 /// current process. If that is not desirable, you can construct it with
-/// [`PackageFetcher::with_out`], to intercept cargo `write` calls.
+/// [`PackageFetcher::with_out`], to intercept cargo `write` calls. For other options, see [`PackageFetcher::builder`].
 ///
 /// After constructing, you can:
 /// * Resolve package versions with [`PackageFetcher::resolve_package`] and [`PackageFetcher::resolve_first`]
 /// * Fetch packages with [`PackageFetcher::fetch`], or [`PackageFetcher::fetch_many`]
+/// * Resolve dependency graphs of packages with [`PackageFetcher::resolve_graph`]
+///
+/// With [`PackageFetcherBuilder::registry_auth`] enabled, packages from registries that require authentication
+/// (`auth-required` in their `config.json`) are downloaded with a token from the credential providers configured
+/// for the registry, same as cargo does since 1.74:
+/// `registries.<name>.credential-provider` (`registry.credential-provider` for crates.io), otherwise
+/// `registry.global-credential-providers`. Supported providers are:
+/// * `cargo:token`, the token from the cargo config or `credentials.toml`, the default
+/// * `cargo:token-from-stdout <command>`, the output of the command
+/// * External providers, speaking version 1 of the
+///   [credential provider protocol](https://doc.rust-lang.org/cargo/reference/credential-provider-protocol.html)
+///
+/// Platform-specific built-in providers (`cargo:wincred`, `cargo:macos-keychain`, `cargo:libsecret`) are not
+/// supported. Tokens are requested once per [`PackageFetcher`], and only used for downloads, not for fetching
+/// sparse indexes.
 #[derive(Debug)]
 pub struct PackageFetcher {
     config: cargo::Config,
+    registry_index_configs: HashMap<CanonicalUrl, RegistryIndexConfig>,
+    keep_on_failure: bool,
+    default_members_only: bool,
+    lock_behavior: LockBehavior,
+    max_crate_size: Option<u64>,
+    shared_git_cache: Option<PathBuf>,
+    pin_git_revisions: bool,
+    git_env: HashMap<String, String>,
+    git_env_guard: std::cell::RefCell<std::rc::Weak<git_env::GitEnv>>,
+    normalized_mtime: Option<SystemTime>,
+    normalize_line_endings: bool,
+    resolve_concurrency: usize,
+    verify_checksums: bool,
+    index_freshness: Option<IndexFreshness>,
+    progress_interval: Duration,
+    minimum_versions: HashMap<String, Version>,
+    url_rewriter: Option<rewrite::UrlRewriter>,
+    version_ordering: Option<ordering::VersionOrdering>,
+    cache_locks: std::cell::Cell<usize>,
+    scoped_tokens: std::cell::RefCell<Vec<(CanonicalUrl, Option<String>)>>,
+    git_revisions: std::cell::RefCell<HashMap<SourceId, String>>,
+    downloaded_bytes: std::cell::Cell<u64>,
 }
 
 impl PackageFetcher {
@@ -33,23 +156,25 @@ impl PackageFetcher {
     /// Cargo will output its colored status to the `stdout` and `stderr` of the current process by default, if that is not desirable, see
     /// [`PackageFetcher::with_out`].
     pub fn new() -> Result<Self, String> {
-        Ok(Self {
-            config: cargo::Config::default().map_err(|e| e.to_string())?,
-        })
+        PackageFetcherBuilder::new().build()
     }
 
     /// Constructs [`PackageFetcher`] with user-provided stream for cargo to output status to.
     ///
     /// Optionally also accepts [`Verbosity`], which is set to [`Verbosity::Verbose`] if [`None`] is provided.
     pub fn with_out(out: Box<dyn Write>, verbosity: Option<Verbosity>) -> Result<Self, String> {
-        let mut shell = cargo::core::Shell::from_write(out);
-        shell.set_verbosity(verbosity.unwrap_or_default().into());
-        let new_self = Self::new()?;
-        {
-            let mut sh = new_self.config.shell();
-            *sh = shell;
-        }
-        Ok(new_self)
+        PackageFetcherBuilder::new().out(out, verbosity).build()
+    }
+
+    /// Constructs [`PackageFetcher`] with options read from the environment variables of the process,
+    /// see [`PackageFetcherBuilder::from_env`] for the recognized variables.
+    pub fn from_env() -> Result<Self, String> {
+        PackageFetcherBuilder::from_env()?.build()
+    }
+
+    /// Returns [`PackageFetcherBuilder`], to construct [`PackageFetcher`] with non-default options.
+    pub fn builder() -> PackageFetcherBuilder {
+        PackageFetcherBuilder::new()
     }
 
     /// Resolves all available package versions, given a version requirement and a name of the package.
@@ -64,8 +189,8 @@ impl PackageFetcher {
         source: &PackageSource,
         yanked_whitelist: Option<HashSet<Package>>,
     ) -> Result<Vec<Package>, String> {
-        let _lock = self.config.acquire_package_cache_lock().map_err(|e| e.to_string())?;
-        let src = source.to_source_id().map_err(|e| e.to_string())?;
+        let _lock = self.lock_package_cache()?;
+        let src = self.source_id(source).map_err(|e| e.to_string())?;
 
         let whitelist: HashSet<PackageId>;
 
@@ -75,24 +200,49 @@ impl PackageFetcher {
             whitelist = Default::default();
         };
 
-        let mut src = src.load(&self.config, &whitelist).map_err(|e| e.to_string())?;
+        let mut src = self.load_source(src, &whitelist)?;
 
-        let dep = cargo::core::Dependency::parse(name.as_ref(), version, src.source_id())
+        let dep = Dependency::parse(name.as_ref(), version, src.source_id())
             .map_err(|e| e.to_string())?;
 
         let mut pkgs = vec![];
 
-        src.block_until_ready().map_err(|e| e.to_string())?;
-        let Poll::Ready(res) = src.query(&dep, cargo::core::QueryKind::Exact, &mut |sum| {pkgs.push(Package {package_id: sum.package_id()})}) else {
-			return Err("cargo returned a `Poll::Pending` after `block_until_ready`".into());
-		};
-
-        res.map_err(|e| e.to_string())?;
+        query_blocking(&mut src, &dep, &mut |sum| pkgs.push(Package { package_id: sum.package_id() }))
+            .map_err(|e| e.to_string())?;
 
         Ok(pkgs)
     }
 
-    /// Resolves first available package version, given a version requirement and a name of the package.
+    /// Resolves the newest available package version, given a version requirement and a name of the package,
+    /// same as cargo does for a dependency.
+    ///
+    /// Yanked versions can be selected by whitelisting them:
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Package, PackageFetcher, PackageSource};
+    /// # use std::collections::HashSet;
+    /// let fetcher = PackageFetcher::new()?;
+    /// let source = PackageSource::crates_io();
+    /// let yanked = Package::from_str_ver("foo", "1.1.0", &source)?;
+    /// let foo = fetcher.resolve_first("foo", None, &source, Some(HashSet::from([yanked])))?;
+    /// # Ok::<(), String>(())
+    /// ```
+    ///
+    /// When nothing matches, the error tells whether the package doesn't exist in the source, or only the version
+    /// requirement didn't match, along with the versions that exist:
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{PackageFetcher, PackageSource, ResolveError};
+    /// let fetcher = PackageFetcher::new()?;
+    /// match fetcher.resolve_first("foo", Some("2"), &PackageSource::crates_io(), None) {
+    ///     Ok(foo) => println!("resolved {}", foo.to_spec_string()),
+    ///     Err(ResolveError::NoMatchingVersion { available, .. }) => println!("available versions: {available:?}"),
+    ///     Err(e) => return Err(e.into()),
+    /// }
+    /// # Ok::<(), String>(())
+    /// ```
+    ///
+    /// To resolve the version `cargo add` would pick, see [`Self::resolve_installable`].
     ///
     /// For more information see: [`Self::resolve_package`].
     pub fn resolve_first<N: AsRef<str>>(
@@ -101,9 +251,9 @@ impl PackageFetcher {
         version: Option<&str>,
         source: &PackageSource,
         yanked_whitelist: Option<HashSet<Package>>,
-    ) -> Result<Package, String> {
-        let _lock = self.config.acquire_package_cache_lock().map_err(|e| e.to_string())?;
-        let src = source.to_source_id().map_err(|e| e.to_string())?;
+    ) -> Result<Package, ResolveError> {
+        let _lock = self.lock_package_cache()?;
+        let src = self.source_id(source).map_err(|e| e.to_string())?;
 
         let whitelist: HashSet<PackageId>;
 
@@ -113,50 +263,249 @@ impl PackageFetcher {
             whitelist = Default::default();
         };
 
-        let mut src = src.load(&self.config, &whitelist).map_err(|e| e.to_string())?;
+        let mut src = self.load_source(src, &whitelist)?;
 
-        let dep = cargo::core::Dependency::parse(name.as_ref(), version, src.source_id())
+        let dep = Dependency::parse(name.as_ref(), version, src.source_id())
             .map_err(|e| e.to_string())?;
 
         let mut pkg: Option<PackageId> = None;
 
-        src.block_until_ready().map_err(|e| e.to_string())?;
-        let Poll::Ready(res) = src.query(&dep, cargo::core::QueryKind::Exact, &mut |sum| {pkg = Some(sum.package_id())}) else {
-			return Err("cargo returned a `Poll::Pending` after `block_until_ready`".into());
-		};
+        query_blocking(&mut src, &dep, &mut |sum| {
+            let package_id = sum.package_id();
+            if pkg.map_or(true, |pkg| self.is_newer(package_id.version(), pkg.version())) {
+                pkg = Some(package_id);
+            }
+        })
+        .map_err(ResolveError::from_cargo)?;
 
-        res.map_err(|e| e.to_string())?;
+        match pkg {
+            Some(pkg) => self.check_minimum(Package { package_id: pkg }),
+            None => Err(not_found(&mut *src, name.as_ref(), version)),
+        }
+    }
 
-        if let Some(pkg) = pkg {
-            Ok(Package { package_id: pkg })
-        } else {
-            Err("cargo wasn't able to find the requested package".into())
+    /// Same as [`Self::resolve_first`], but also returns the source and the version requirement
+    /// the package was resolved with.
+    pub fn resolve_first_detailed<N: AsRef<str>>(
+        &self,
+        name: N,
+        version: Option<&str>,
+        source: &PackageSource,
+        yanked_whitelist: Option<HashSet<Package>>,
+    ) -> Result<Resolution, String> {
+        let requirement = VersionReq::parse(version.unwrap_or("*")).map_err(|e| e.to_string())?;
+        // Cargo records registries it updated the index of, so it doesn't update them again.
+        let updated = self.config.updated_sources().clone();
+        let last_updated = self.index_last_updated(source);
+        let package = self.resolve_first(name, version, source, yanked_whitelist)?;
+        let index_updated = !self.config.offline()
+            && (self.config.updated_sources().iter().any(|id| !updated.contains(id))
+                || self.index_last_updated(source) != last_updated);
+
+        Ok(Resolution {
+            package,
+            source: source.clone(),
+            requirement,
+            index_updated,
+        })
+    }
+
+    /// Resolves the newest version of a package, whose `rust-version` is satisfied by the `rust` toolchain version.
+    ///
+    /// Registry indexes of the cargo version this crate is built on don't contain `rust-version` of the packages,
+    /// so the versions are fetched from the newest to the oldest, until a compatible one is found.
+    /// Versions that don't specify `rust-version` are considered compatible, yanked versions are skipped.
+    pub fn resolve_compatible_with_rust<N: AsRef<str>>(
+        &mut self,
+        name: N,
+        source: &PackageSource,
+        rust: Version,
+    ) -> Result<Package, String> {
+        let mut packages = self.resolve_package(name.as_ref(), None, source, None)?;
+        if packages.is_empty() {
+            return Err(ResolveError::CrateNotFound {
+                name: name.as_ref().to_owned(),
+            }
+            .into());
+        }
+        packages.sort_unstable_by(|a, b| b.cmp(a));
+
+        let rust = (rust.major, rust.minor, rust.patch);
+        let mut oldest_required = None;
+        for package in packages {
+            let rust_version = match self.fetch_package(package)?.rust_version() {
+                Some(rust_version) => rust_version.to_owned(),
+                None => return Ok(self.check_minimum(package)?),
+            };
+            let required = parse_rust_version(&rust_version)
+                .ok_or_else(|| format!("`{}` has invalid `rust-version`: `{rust_version}`", package.package_id))?;
+            if rust >= required {
+                return Ok(self.check_minimum(package)?);
+            }
+            oldest_required = Some((package, rust_version));
         }
+
+        let (oldest, rust_version) = oldest_required.expect("at least one version was checked");
+        Err(format!(
+            "no version of `{}` is compatible with Rust {}.{}.{}, the oldest version `{}` requires Rust {rust_version}",
+            name.as_ref(),
+            rust.0,
+            rust.1,
+            rust.2,
+            oldest.package_id.version(),
+        ))
     }
 
     /// Fetches a single package, and returns the [`PathBuf`] to the root of it.
-    pub fn fetch(&mut self, package: Package) -> Result<PathBuf, String> {
-        let _lock = self.config.acquire_package_cache_lock().map_err(|e| e.to_string())?;
+    pub fn fetch(&mut self, package: Package) -> Result<PathBuf, ResolveError> {
+        let root: PathBuf = self.fetch_package(package)?.root().into();
+        self.normalize_root(&root)?;
+        Ok(root)
+    }
+
+    /// Resolves the newest package version matching the version requirement, same as [`Self::resolve_first`],
+    /// and fetches it, returning the package along with its root.
+    ///
+    /// Unlike calling [`Self::resolve_first`] and [`Self::fetch`], the package cache is locked once, and the source
+    /// is loaded once, so e.g. a git repository is only checked for updates once. Git packages are fetched through
+    /// the [`PackageFetcherBuilder::shared_git_cache`] if it's set, which loads the source again.
+    pub fn resolve_and_fetch<N: AsRef<str>>(
+        &mut self,
+        name: N,
+        version: Option<&str>,
+        source: &PackageSource,
+    ) -> Result<(Package, PathBuf), ResolveError> {
+        let _lock = self.lock_package_cache()?;
+        let src = self.source_id(source).map_err(|e| e.to_string())?;
+        let mut src = self.load_source(src, &HashSet::new())?;
+
+        let dep = Dependency::parse(name.as_ref(), version, src.source_id()).map_err(|e| e.to_string())?;
+        let mut pkg: Option<PackageId> = None;
+        query_blocking(&mut src, &dep, &mut |sum| {
+            let package_id = sum.package_id();
+            if pkg.map_or(true, |pkg| self.is_newer(package_id.version(), pkg.version())) {
+                pkg = Some(package_id);
+            }
+        })
+        .map_err(ResolveError::from_cargo)?;
+        let package = self.check_minimum(Package {
+            package_id: pkg.ok_or_else(|| not_found(&mut *src, name.as_ref(), version))?,
+        })?;
+
+        if self.shared_git_cache.is_some() && package.package_id.source_id().is_git() {
+            let root: PathBuf = self.fetch_package(package)?.root().into();
+            self.normalize_root(&root)?;
+            return Ok((package, root));
+        }
+
         let mut map = SourceMap::new();
+        map.insert(src);
+        let package_set = PackageSet::new(&[package.package_id], map, &self.config).map_err(ResolveError::from_cargo)?;
+        let root = package_set
+            .get_one(package.package_id)
+            .map_err(|e| self.report_failure(&[package], e))?
+            .root()
+            .to_owned();
+        self.normalize_root(&root)?;
+        Ok((package, root))
+    }
 
-        let whitelist: HashSet<PackageId> = std::iter::once(package.package_id).collect();
+    /// Fetches a single package from a git source, and returns its root along with the commit
+    /// the git reference of the source pointed to at the time of fetching.
+    ///
+    /// Branches, including [`GitReference::DefaultBranch`], can move, the commit can be used to fetch
+    /// the same sources later, with [`GitReference::Revision`]. With [`PackageFetcherBuilder::pin_git_revisions`],
+    /// the fetcher keeps using the commit the source was first resolved to, until
+    /// [`PackageFetcher::clear_source_cache`] is called.
+    ///
+    /// Errors, if the package is not from a git source.
+    pub fn fetch_git_detailed(&mut self, package: Package) -> Result<GitFetch, String> {
+        if !package.package_id.source_id().is_git() {
+            return Err(format!("`{}` is not from a git source", package.package_id));
+        }
 
-        let mut source = package
-            .package_id
+        let package = self.fetch_package(package)?;
+        // Cargo locks git sources to the fetched commit.
+        let commit = package
+            .package_id()
             .source_id()
-            .load(&self.config, &whitelist)
-            .map_err(|e| e.to_string())?;
+            .precise()
+            .ok_or_else(|| format!("cargo didn't lock `{}` to a commit", package.package_id()))?
+            .to_owned();
 
-        source.block_until_ready().map_err(|e| e.to_string())?;
+        Ok(GitFetch {
+            root: package.root().into(),
+            commit,
+        })
+    }
 
-        map.insert(source);
+    /// Fetches a single package, and returns the contents of the `Cargo.lock` it ships with,
+    /// or [`None`], if it doesn't have one.
+    ///
+    /// This is the lock file the package was published with (usually present in binary crates), not one
+    /// generated by resolving its dependencies, for that see [`PackageFetcher::resolve_graph`].
+    pub fn bundled_lockfile(&mut self, package: Package) -> Result<Option<String>, String> {
+        let root = self.fetch(package)?;
+        match std::fs::read_to_string(root.join("Cargo.lock")) {
+            Ok(lockfile) => Ok(Some(lockfile)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
 
-        let package_set = PackageSet::new(&[package.package_id], map, &self.config).map_err(|e| e.to_string())?;
-        Ok(package_set
-            .get_one(package.package_id)
-            .map_err(|e| e.to_string())?
-            .root()
-            .into())
+    /// Fetches a single package, and returns the value of the `links` key from its manifest,
+    /// or [`None`], if it doesn't declare one.
+    ///
+    /// Cargo allows only one package in a build to link a given native library, see
+    /// [the `links` manifest key](https://doc.rust-lang.org/cargo/reference/build-scripts.html#the-links-manifest-key).
+    pub fn links(&mut self, package: Package) -> Result<Option<String>, String> {
+        Ok(self.fetch_package(package)?.manifest().links().map(str::to_owned))
+    }
+
+    /// Fetches a single package, and returns the `include` and `exclude` globs declared in its manifest,
+    /// as they are written.
+    ///
+    /// These are the declared intent, the files cargo actually includes in the package archive are listed by
+    /// [`PackageFetcher::file_hashes`]. Manifests of registry packages keep the globs they were published with,
+    /// though their files already are the result of applying them.
+    pub fn package_globs(&mut self, package: Package) -> Result<PackageGlobs, String> {
+        let package = self.fetch_package(package)?;
+        Ok(PackageGlobs {
+            include: package.manifest().include().to_vec(),
+            exclude: package.manifest().exclude().to_vec(),
+        })
+    }
+
+    /// Fetches a single package, and returns the metadata describing it from its manifest, e.g. its description,
+    /// keywords and categories, as they are declared.
+    ///
+    /// Values aren't validated, crates.io limits the amount of keywords and only accepts its own categories, but
+    /// other sources don't.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Package, PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let foo = Package::from_str_ver("foo", "0.1.0", &PackageSource::crates_io())?;
+    ///
+    /// let metadata = fetcher.manifest_metadata(foo)?;
+    /// println!("{}: {:?}", metadata.description.unwrap_or_default(), metadata.keywords);
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn manifest_metadata(&mut self, package: Package) -> Result<ManifestMetadata, String> {
+        let package = self.fetch_package(package)?;
+        let metadata = package.manifest().metadata();
+        Ok(ManifestMetadata {
+            authors: metadata.authors.clone(),
+            description: metadata.description.clone(),
+            license: metadata.license.clone(),
+            license_file: metadata.license_file.clone(),
+            readme: metadata.readme.clone(),
+            homepage: metadata.homepage.clone(),
+            documentation: metadata.documentation.clone(),
+            repository: metadata.repository.clone(),
+            keywords: metadata.keywords.clone(),
+            categories: metadata.categories.clone(),
+        })
     }
 
     /// Fetches multiple packages, and returns the [`PathBuf`]s to their roots.
@@ -168,34 +517,332 @@ impl PackageFetcher {
     /// which case cargo will cache the package sources, and return the root only once,
     /// no matter what amount of duplicate packages was passed.
     ///
+    /// Different versions of the same package can be requested together, each of them
+    /// is extracted to its own root:
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Package, PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let source = PackageSource::crates_io();
+    /// let old = Package::from_str_ver("foo", "1.0.0", &source)?;
+    /// let new = Package::from_str_ver("foo", "2.0.0", &source)?;
+    ///
+    /// let roots = fetcher.fetch_many(&[old, new])?;
+    /// # Ok::<(), cargo_fetch::ResolveError>(())
+    /// ```
+    ///
+    /// Roots are returned in a deterministic order, regardless of the order of `packages`: sorted by the name of
+    /// the package, then its version, then its source:
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Package, PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let source = PackageSource::crates_io();
+    /// let package = |name: &str| Package::from_str_ver(name, "0.1.0", &source);
+    ///
+    /// // Same as fetching `[package("a")?, package("b")?]`
+    /// let roots = fetcher.fetch_many(&[package("b")?, package("a")?])?;
+    /// # Ok::<(), String>(())
+    /// ```
+    ///
     /// Errors, if any of the requested packages cannot be fetched.
     pub fn fetch_many(
         &mut self,
         packages: &[Package],
-    ) -> Result<Vec<PathBuf>, String> {
-        let _lock = self.config.acquire_package_cache_lock().map_err(|e| e.to_string())?;
+    ) -> Result<Vec<PathBuf>, ResolveError> {
+        let _lock = self.lock_package_cache()?;
+        let package_set = self.package_set(packages)?;
+        let mut fetched = package_set
+            .get_many(package_set.package_ids())
+            .map_err(|e| self.report_failure(packages, e))?;
+        // Package set iterates over its packages in an arbitrary order.
+        fetched.sort_unstable_by_key(|p| p.package_id());
+        let roots: Vec<PathBuf> = fetched.iter().map(|p| p.root().to_owned()).collect();
+        for root in &roots {
+            self.normalize_root(root)?;
+        }
+        Ok(roots)
+    }
+
+    /// Same as [`Self::fetch_many`], but calls `progress` when packages finish fetching.
+    ///
+    /// Progress is reported in the amount of fetched packages, and the size of the archives downloaded so far, once
+    /// each download finishes. The total size of the downloads is only known after the last of them, as registry
+    /// indexes of the cargo version this crate is built on don't contain sizes of the archives. Packages that are
+    /// already available locally (cached, path and git checkouts) are reported before the downloads start. Roots
+    /// are returned in the order the packages were fetched in.
+    ///
+    /// Calls are at least [`PackageFetcherBuilder::progress_interval`] apart, except for the last package,
+    /// which is always reported.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Package, PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let source = PackageSource::crates_io();
+    /// let package = |name: &str| Package::from_str_ver(name, "0.1.0", &source);
+    /// let packages = [package("bar")?, package("foo")?];
+    ///
+    /// fetcher.fetch_many_with_progress(&packages, |progress| {
+    ///     println!("fetched {}, {} bytes downloaded", progress.package.to_spec_string(), progress.downloaded_bytes);
+    /// })?;
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn fetch_many_with_progress(
+        &mut self,
+        packages: &[Package],
+        mut progress: impl FnMut(FetchProgress),
+    ) -> Result<Vec<PathBuf>, ResolveError> {
+        let _lock = self.lock_package_cache()?;
+        let package_set = self.package_set(packages)?;
+        let package_ids: Vec<PackageId> = package_set.package_ids().collect();
+
+        let mut roots = Vec::with_capacity(package_ids.len());
+        let mut last_report: Option<Instant> = None;
+        let downloaded_before = self.downloaded_bytes.get();
+        let mut report = |package: &cargo::core::Package, roots: &mut Vec<PathBuf>| {
+            roots.push(package.root().to_owned());
+            let throttled = last_report.is_some_and(|last| last.elapsed() < self.progress_interval);
+            if throttled && roots.len() < package_ids.len() {
+                return;
+            }
+            last_report = Some(Instant::now());
+            let downloaded_bytes = self.downloaded_bytes.get() - downloaded_before;
+            progress(FetchProgress {
+                package: Package {
+                    package_id: package.package_id(),
+                },
+                fetched: roots.len(),
+                total: package_ids.len(),
+                downloaded_bytes,
+                total_bytes: (roots.len() == package_ids.len()).then_some(downloaded_bytes),
+            });
+        };
+
+        let mut downloads = package_set.enable_download().map_err(ResolveError::from_cargo)?;
+        for &package_id in &package_ids {
+            if let Some(package) = downloads
+                .start(package_id)
+                .map_err(|e| self.report_failure(packages, e))?
+            {
+                report(package, &mut roots);
+            }
+        }
+        while downloads.remaining() > 0 {
+            let package = downloads
+                .wait()
+                .map_err(|e| self.report_failure(packages, e))?;
+            report(package, &mut roots);
+        }
+
+        for root in &roots {
+            self.normalize_root(root)?;
+        }
+        Ok(roots)
+    }
+
+    /// Errors with [`ResolveError::BelowMinimum`], if the version `package` was resolved to is lower than
+    /// the [`PackageFetcherBuilder::minimum_version`] of it.
+    fn check_minimum(&self, package: Package) -> Result<Package, ResolveError> {
+        let name = package.package_id.name();
+        match self.minimum_versions.get(name.as_str()) {
+            Some(floor) if package.package_id.version() < floor => Err(ResolveError::BelowMinimum {
+                name: name.to_string(),
+                selected: package.package_id.version().clone(),
+                floor: floor.clone(),
+            }),
+            _ => Ok(package),
+        }
+    }
+
+    /// Returns the url requests for `source` will actually be sent to, after applying
+    /// `[source.*]` replacement from the cargo config.
+    ///
+    /// If `source` is not replaced, its own url is returned.
+    pub fn effective_source_url(&self, source: &PackageSource) -> Result<Url, String> {
+        let src = self.source_id(source).map_err(|e| e.to_string())?;
+        let src = self.load_source(src, &HashSet::new())?;
+        Ok(src.replaced_source_id().url().clone())
+    }
+
+    /// Fetches a single package, and returns cargo's representation of it.
+    ///
+    /// Git packages are fetched through the [`PackageFetcherBuilder::shared_git_cache`], if it's set.
+    fn fetch_package(&self, package: Package) -> Result<cargo::core::Package, ResolveError> {
+        match &self.shared_git_cache {
+            Some(cache) if package.package_id.source_id().is_git() => self.fetch_shared_git(package, cache),
+            _ => self.fetch_from_source(package),
+        }
+    }
+
+    /// Fetches a single package from its source, and returns cargo's representation of it.
+    fn fetch_from_source(&self, package: Package) -> Result<cargo::core::Package, ResolveError> {
+        let _lock = self.lock_package_cache()?;
         let mut map = SourceMap::new();
 
-        let whitelist: HashSet<PackageId> = packages.iter().map(|p| p.package_id).collect();
+        let whitelist: HashSet<PackageId> = std::iter::once(package.package_id).collect();
+
+        let mut source = self.load_source(package.package_id.source_id(), &whitelist)?;
+
+        source.block_until_ready().map_err(ResolveError::from_cargo)?;
+
+        map.insert(source);
+
+        let package_set = PackageSet::new(&[package.package_id], map, &self.config).map_err(ResolveError::from_cargo)?;
+        Ok(package_set
+            .get_one(package.package_id)
+            .map_err(|e| self.report_failure(&[package], e))?
+            .clone())
+    }
+
+    /// Logs incomplete extractions of `packages` if [`PackageFetcherBuilder::keep_on_failure`] is set,
+    /// and classifies the `error`.
+    ///
+    /// Package cache lock has to be held by the caller.
+    fn report_failure(&self, packages: &[Package], error: anyhow::Error) -> ResolveError {
+        let error = ResolveError::from_cargo(error);
+        if !self.keep_on_failure {
+            return error;
+        }
 
         for package in packages {
-            let mut source = package
-                .package_id
-                .source_id()
-                .load(&self.config, &whitelist)
-                .map_err(|e| e.to_string())?;
-            source.block_until_ready().map_err(|e| e.to_string())?;
+            let package_id = package.package_id;
+            let source_id = match self.load_source(package_id.source_id(), &HashSet::new()) {
+                Ok(source) => source.replaced_source_id(),
+                Err(_) => continue,
+            };
+            if !source_id.is_registry() {
+                continue;
+            }
+
+            let dir = self
+                .config
+                .registry_source_path()
+                .join(registry::short_name(source_id))
+                .join(format!("{}-{}", package_id.name(), package_id.version()));
+            let dir = self.config.assert_package_cache_locked(&dir);
+            // Cargo writes `.cargo-ok` only after the package is completely extracted.
+            if dir.exists() && !dir.join(".cargo-ok").exists() {
+                let _ = self.config.shell().warn(format!(
+                    "kept incomplete extraction of `{package_id}` at `{}`",
+                    dir.display()
+                ));
+            }
+        }
+
+        error
+    }
+
+    /// Loads sources of the packages, and constructs a [`PackageSet`] of them.
+    ///
+    /// Package cache lock has to be held by the caller.
+    fn package_set(&self, packages: &[Package]) -> Result<PackageSet<'_>, ResolveError> {
+        let mut map = SourceMap::new();
+
+        let whitelist: HashSet<PackageId> = packages.iter().map(|p| p.package_id).collect();
+
+        // Every source has to be loaded only once, with the whitelist of all the requested packages,
+        // inserting it again would replace the already loaded one in the `SourceMap`.
+        let source_ids: HashSet<SourceId> = packages.iter().map(|p| p.package_id.source_id()).collect();
+
+        for source_id in source_ids {
+            let mut source = self.load_source(source_id, &whitelist)?;
+            source.block_until_ready().map_err(ResolveError::from_cargo)?;
             map.insert(source);
         }
 
         let packages: Vec<PackageId> = packages.iter().map(|p| p.package_id).collect();
-        let package_set = PackageSet::new(&packages, map, &self.config).map_err(|e| e.to_string())?;
-        Ok(package_set
-            .get_many(package_set.package_ids())
-            .map_err(|e| e.to_string())?
-            .iter()
-            .map(|p| p.root().to_owned())
-            .collect())
+        PackageSet::new(&packages, map, &self.config).map_err(ResolveError::from_cargo)
+    }
+
+    /// Loads a source, following `[source.*]` replacement configured for it.
+    ///
+    /// Registries with an overridden `dl` in their [`RegistryIndexConfig`] are wrapped to download from it,
+    /// and remote registries are wrapped to enforce [`PackageFetcherBuilder::max_crate_size`],
+    /// [`PackageFetcherBuilder::verify_checksums`] and [`PackageFetcherBuilder::index_freshness`], and to authenticate
+    /// downloads with tokens from credential providers. Git sources are locked to the commit they were resolved to
+    /// before, if [`PackageFetcherBuilder::pin_git_revisions`] is set.
+    fn load_source(&self, id: SourceId, yanked_whitelist: &HashSet<PackageId>) -> Result<Box<dyn Source + '_>, String> {
+        let id = self.cached_revision(id);
+        let mut source = SourceConfigMap::new(&self.config)
+            .and_then(|map| map.load(id, yanked_whitelist))
+            .map_err(|e| e.to_string())?;
+        if let Some(git_env) = id.is_git().then(|| self.git_env_guard()).flatten() {
+            source = Box::new(git_env::GitEnvSource::new(source, git_env));
+        }
+        if self.pin_git_revisions && id.is_git() && id.precise().is_none() {
+            return Ok(Box::new(source_cache::RevisionRecordingSource::new(source, &self.git_revisions)));
+        }
+
+        let replaced = source.replaced_source_id();
+        let dl = self
+            .index_config(replaced)
+            .and_then(|config| config.dl.clone())
+            .filter(|_| replaced.is_registry());
+        let max_crate_size = self.max_crate_size.filter(|_| replaced.is_remote_registry());
+        if dl.is_none() && !replaced.is_remote_registry() {
+            return Ok(source);
+        }
+        let mut source = registry::RegistryDownloadSource::new(
+            source,
+            dl,
+            max_crate_size,
+            self.verify_checksums,
+            &self.downloaded_bytes,
+            &self.config,
+        );
+        if replaced.is_remote_registry() && self.index_needs_refresh(replaced) == Some(true) {
+            source.refresh_index();
+        }
+        Ok(Box::new(source))
+    }
+}
+
+/// Parses `rust-version` of a manifest, which can omit the minor and patch versions.
+fn parse_rust_version(rust_version: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = rust_version.split('.').map(|part| part.parse().ok());
+    let version = (parts.next()??, parts.next().unwrap_or(Some(0))?, parts.next().unwrap_or(Some(0))?);
+    parts.next().is_none().then_some(version)
+}
+
+/// Error for a package `name` that has no versions matching the `version` requirement in `source`,
+/// telling whether it has any versions at all, or which packages a git repository has instead.
+fn not_found(source: &mut dyn Source, name: &str, version: Option<&str>) -> ResolveError {
+    let mut available = vec![];
+    let found = Dependency::parse(name, None, source.source_id()).and_then(|dep| {
+        query_blocking(source, &dep, &mut |sum| available.push(sum.version().clone()))
+    });
+    if let Err(e) = found {
+        return ResolveError::from_cargo(e);
+    }
+
+    let name = name.to_owned();
+    if available.is_empty() && source.source_id().is_git() {
+        let members = match members::member_names(source) {
+            Ok(members) => members,
+            Err(e) => return ResolveError::from_cargo(e),
+        };
+        if !members.is_empty() {
+            return ResolveError::MemberNotFound { name, members };
+        }
+    }
+    if available.is_empty() {
+        return ResolveError::CrateNotFound { name };
+    }
+    available.sort_unstable();
+    ResolveError::NoMatchingVersion {
+        name,
+        req: version.unwrap_or("*").to_owned(),
+        available,
+    }
+}
+
+/// Queries `source` for summaries matching `dep`, blocking until cargo is able to provide them.
+fn query_blocking(source: &mut dyn Source, dep: &Dependency, f: &mut dyn FnMut(Summary)) -> CargoResult<()> {
+    loop {
+        match source.query(dep, QueryKind::Exact, f) {
+            Poll::Ready(res) => return res,
+            Poll::Pending => source.block_until_ready()?,
+        }
     }
 }
 
@@ -218,6 +865,88 @@ impl From<Verbosity> for cargo::core::Verbosity {
     }
 }
 
+/// Progress of a batch fetch, reported by [`PackageFetcher::fetch_many_with_progress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FetchProgress {
+    /// Package that finished fetching, packages finishing before it since the last report aren't reported, see
+    /// [`PackageFetcherBuilder::progress_interval`].
+    pub package: Package,
+    /// Amount of packages fetched so far, including `package`.
+    pub fetched: usize,
+    /// Total amount of packages being fetched.
+    pub total: usize,
+    /// Size of the `.crate` archives downloaded so far, in bytes, counted the same way as
+    /// [`FetchDetails::downloaded_bytes`].
+    pub downloaded_bytes: u64,
+    /// Total size of the archives being downloaded, in bytes, once it's known, which is only with the last
+    /// package, see [`PackageFetcher::fetch_many_with_progress`].
+    pub total_bytes: Option<u64>,
+}
+
+/// Describes how a package was resolved, returned by [`PackageFetcher::resolve_first_detailed`] and
+/// [`PackageFetcher::resolve_with_precedence`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolution {
+    /// The resolved package.
+    pub package: Package,
+    /// Source the package was resolved from.
+    pub source: PackageSource,
+    /// Version requirement the package was resolved with, "*" if none was given.
+    pub requirement: VersionReq,
+    /// Whether the registry index was updated from the network while resolving.
+    ///
+    /// Index of a registry is updated at most once for each [`PackageFetcher`], later resolutions
+    /// from the same registry use the local copy of the index, unless
+    /// [`PackageFetcherBuilder::index_freshness`] is set.
+    pub index_updated: bool,
+}
+
+/// Fetched git package, returned by [`PackageFetcher::fetch_git_detailed`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitFetch {
+    /// Root of the fetched package.
+    pub root: PathBuf,
+    /// Full hash of the commit the package was fetched from.
+    pub commit: String,
+}
+
+/// `include` and `exclude` globs declared in a package manifest, returned by [`PackageFetcher::package_globs`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PackageGlobs {
+    /// Globs of the files to include in the package archive, all files are included if it's empty.
+    pub include: Vec<String>,
+    /// Globs of the files to exclude from the package archive, ignored if `include` isn't empty.
+    pub exclude: Vec<String>,
+}
+
+/// Metadata declared in the `[package]` table of a package manifest, returned by [`PackageFetcher::manifest_metadata`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ManifestMetadata {
+    /// `authors`
+    pub authors: Vec<String>,
+    /// `description`
+    pub description: Option<String>,
+    /// `license`, as an SPDX expression.
+    pub license: Option<String>,
+    /// `license-file`, relative to the root of the package.
+    pub license_file: Option<String>,
+    /// `readme`, relative to the root of the package, cargo sets it to a `README.md` file in the root, if it's not
+    /// declared.
+    pub readme: Option<String>,
+    /// `homepage`
+    pub homepage: Option<String>,
+    /// `documentation`
+    pub documentation: Option<String>,
+    /// `repository`
+    pub repository: Option<String>,
+    /// `keywords`
+    pub keywords: Vec<String>,
+    /// `categories`, which are slugs of crates.io categories, e.g. `command-line-utilities`.
+    pub categories: Vec<String>,
+}
+
 /// Package definition to be fetched by cargo.
 ///
 /// This type can either be construct from associated functions, if you have concrete versions of a package.
@@ -225,7 +954,8 @@ impl From<Verbosity> for cargo::core::Verbosity {
 /// struct if you need to resolve a package from name and a version requirement, without requiring a specific version.
 ///
 /// This type is cheap to copy.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
 pub struct Package {
     package_id: PackageId,
 }
@@ -259,6 +989,135 @@ impl Package {
             .map_err(|e| e.to_string())?,
         })
     }
+
+    /// Parses a [`Package`] from a string produced by [`Package::to_spec_string`].
+    pub fn from_spec<S: AsRef<str>>(spec: S) -> Result<Self, String> {
+        let spec = spec.as_ref();
+        let (source, name_version) = spec
+            .rsplit_once('#')
+            .ok_or_else(|| format!("package spec `{spec}` is missing the `#name@version` part"))?;
+        let (name, version) = name_version
+            .split_once('@')
+            .ok_or_else(|| format!("package spec `{spec}` is missing the package version"))?;
+        let (kind, url) = source
+            .split_once('+')
+            .ok_or_else(|| format!("package spec `{spec}` is missing the source kind"))?;
+
+        let file_path = || {
+            Url::parse(url)
+                .ok()
+                .and_then(|url| url.to_file_path().ok())
+                .ok_or_else(|| format!("`{url}` is not a valid file url"))
+        };
+        let source_id = match kind {
+            "registry" => SourceId::for_registry(&url.into_url().map_err(|e| e.to_string())?),
+            "sparse" => SourceId::for_registry(&source.into_url().map_err(|e| e.to_string())?),
+            "git" => SourceId::from_url(source),
+            "path" => SourceId::for_path(&file_path()?),
+            "local-registry" => SourceId::for_local_registry(&file_path()?),
+            "directory" => SourceId::for_directory(&file_path()?),
+            kind => return Err(format!("unsupported source kind `{kind}` in package spec `{spec}`")),
+        }
+        .map_err(|e| e.to_string())?;
+
+        Ok(Package {
+            package_id: PackageId::new(name, version, source_id).map_err(|e| e.to_string())?,
+        })
+    }
+
+    /// Returns the canonical textual identity of the package, in `<kind>+<source url>#<name>@<version>` form,
+    /// e.g. `registry+https://github.com/rust-lang/crates.io-index#serde@1.0.0`.
+    ///
+    /// The string can be parsed back with [`Package::from_spec`]. Exact commit of a git package is not
+    /// a part of it, only the git reference it was requested with.
+    ///
+    /// ```
+    /// # use cargo_fetch::{GitReference, Package, PackageSource};
+    /// let sources = [
+    ///     PackageSource::crates_io(),
+    ///     PackageSource::remote("sparse+https://index.crates.io/").unwrap(),
+    ///     PackageSource::git("https://github.com/serde-rs/serde", Some(GitReference::Tag("v1.0.0".into()))).unwrap(),
+    ///     PackageSource::path("/serde")?,
+    ///     PackageSource::local("/registry"),
+    /// ];
+    /// for source in &sources {
+    ///     let package = Package::from_str_ver("serde", "1.0.0", source)?;
+    ///     assert_eq!(Package::from_spec(package.to_spec_string())?, package);
+    /// }
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn to_spec_string(&self) -> String {
+        format!(
+            "{}#{}@{}",
+            self.package_id.source_id().with_precise(None).as_url(),
+            self.package_id.name(),
+            self.package_id.version()
+        )
+    }
+
+    /// Returns the fully qualified id of the package cargo writes into `Cargo.lock`, in `<name> <version> (<source>)`
+    /// form, e.g. `serde 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)`.
+    ///
+    /// This is the form dependencies of a package are listed with in the lockfile, when the name and version
+    /// alone are ambiguous, same as cargo formats it, so it's the same for every version of the lockfile since v3.
+    /// Path packages don't have a source there, and the exact commit of a git package is only written to
+    /// the `source` of its own entry, so neither is a part of it.
+    ///
+    /// ```
+    /// # use cargo_fetch::{GitReference, Package, PackageSource};
+    /// // Lockfile of a package depending on `serde` both from crates.io and from git, and on a path package
+    /// let lockfile = r#"
+    /// version = 3
+    ///
+    /// [[package]]
+    /// name = "app"
+    /// version = "0.1.0"
+    /// dependencies = [
+    ///  "serde 1.0.152 (registry+https://github.com/rust-lang/crates.io-index)",
+    ///  "serde 1.0.152 (git+https://github.com/serde-rs/serde?tag=v1.0.152)",
+    ///  "utils",
+    /// ]
+    /// "#;
+    /// let git = PackageSource::git("https://github.com/serde-rs/serde", Some(GitReference::Tag("v1.0.152".into())))
+    ///     .unwrap();
+    /// let packages = [
+    ///     Package::from_str_ver("serde", "1.0.152", &git)?,
+    ///     Package::from_str_ver("serde", "1.0.152", &PackageSource::CratesIo)?,
+    ///     Package::from_str_ver("utils", "0.1.0", &PackageSource::path("/app/utils")?)?,
+    /// ];
+    /// let ids: Vec<_> = packages.iter().map(Package::lock_id).collect();
+    /// assert_eq!(ids[2], "utils 0.1.0");
+    /// for id in &ids[..2] {
+    ///     assert!(lockfile.contains(&format!("\"{id}\"")));
+    /// }
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn lock_id(&self) -> String {
+        let source_id = self.package_id.source_id();
+        let mut id = format!("{} {}", self.package_id.name(), self.package_id.version());
+        // Same as `EncodablePackageId` of cargo, which can't be constructed outside of it.
+        if !source_id.is_path() {
+            id += &format!(" ({})", source_id.with_precise(None).as_url());
+        }
+        id
+    }
+
+    /// Returns whether both packages are the same crate, with the same name and from the same source,
+    /// regardless of their versions.
+    ///
+    /// ```
+    /// # use cargo_fetch::{Package, PackageSource};
+    /// let old = Package::from_str_ver("serde", "1.0.0", &PackageSource::CratesIo)?;
+    /// let new = Package::from_str_ver("serde", "1.0.152", &PackageSource::CratesIo)?;
+    /// let local = Package::from_str_ver("serde", "1.0.152", &PackageSource::local("/registry"))?;
+    /// assert!(old.same_crate(&new));
+    /// assert!(!new.same_crate(&local));
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn same_crate(&self, other: &Package) -> bool {
+        self.package_id.name() == other.package_id.name()
+            && self.package_id.source_id() == other.package_id.source_id()
+    }
 }
 
 /// Git reference for [`PackageSource::Git`]
@@ -268,15 +1127,22 @@ pub enum GitReference {
     Branch(String),
     Revision(String),
     Tag(String),
+    /// Newest tag matching a glob pattern, e.g. `v*`, see [`PackageFetcher::latest_tag`].
+    ///
+    /// It's resolved to the tag when a [`PackageFetcher`] method is called with the source, so packages resolved from
+    /// it are from [`GitReference::Tag`]. It can't be used to construct a [`Package`] directly.
+    TagPattern(String),
 }
 
+/// Cargo has no tag patterns, so [`GitReference::TagPattern`] converts to a tag named like the pattern,
+/// resolve it with [`PackageFetcher::latest_tag`] first.
 impl From<GitReference> for cargo::core::GitReference {
     fn from(value: GitReference) -> Self {
         match value {
             GitReference::DefaultBranch => Self::DefaultBranch,
             GitReference::Branch(branch) => Self::Branch(branch),
             GitReference::Revision(rev) => Self::Rev(rev),
-            GitReference::Tag(tag) => Self::Tag(tag),
+            GitReference::Tag(tag) | GitReference::TagPattern(tag) => Self::Tag(tag),
         }
     }
 }
@@ -311,9 +1177,22 @@ pub enum PackageSource {
     /// foo = "1.0.0"
     /// ```
     ///
-    /// Note that this does *not* respect `.cargo/config.toml`, so if `default-registry` or `crates-io`
-    /// are overriden, this would still fetch from `crates.io`
+    /// Note that this does *not* respect `default-registry` or `[registries.crates-io]` overrides in
+    /// `.cargo/config.toml`, so this would still fetch from `crates.io`. `[source.crates-io]` replacement is
+    /// honored, see [`PackageFetcher::effective_source_url`].
     CratesIo,
+    /// Remote registry, with a token to authenticate requests to it with, constructed with
+    /// [`PackageSource::remote_with_token`]. Requires [`PackageFetcherBuilder::registry_auth`], fetchers without it
+    /// error for this source.
+    ///
+    /// When a [`PackageFetcher`] method is called with this source, the token is used for the requests to the
+    /// registry at `url` made by that call, instead of the token from the cargo config or credential providers.
+    /// Later calls, e.g. [`PackageFetcher::fetch`] of a package resolved from this source, use the configured
+    /// token, so [`PackageFetcher::resolve_and_fetch`] should be used to download with it. The cargo config isn't
+    /// modified, and other fetchers aren't affected.
+    ///
+    /// If the registry is replaced with another source in the cargo config, the token isn't sent to the replacement.
+    RegistryWithAuth { url: Url, token: Arc<RegistryToken> },
 }
 
 impl PackageSource {
@@ -339,6 +1218,15 @@ impl PackageSource {
         Ok(Self::RemoteRegistry(url.try_into()?))
     }
 
+    /// Constructs a new [`PackageSource::RegistryWithAuth`] from a registry index url, and a token to authenticate
+    /// with to it.
+    pub fn remote_with_token<U: TryInto<Url>, T: Into<String>>(url: U, token: T) -> Result<Self, U::Error> {
+        Ok(Self::RegistryWithAuth {
+            url: url.try_into()?,
+            token: Arc::new(RegistryToken::new(token)),
+        })
+    }
+
     /// Constructs a new [`PackageSource::LocalRegistry`] from a registry index path.
     pub fn local<P: Into<PathBuf>>(path: P) -> Self {
         Self::LocalRegistry(path.into())
@@ -352,8 +1240,14 @@ impl PackageSource {
     fn to_source_id(&self) -> cargo::CargoResult<SourceId> {
         match self {
             PackageSource::Path(path) => SourceId::for_path(path),
+            PackageSource::Git {
+                git_ref: GitReference::TagPattern(pattern),
+                ..
+            } => anyhow::bail!("tag pattern `{pattern}` has to be resolved to a tag first"),
             PackageSource::Git { url, git_ref } => SourceId::for_git(url, git_ref.clone().into()),
-            PackageSource::RemoteRegistry(url) => SourceId::for_registry(url),
+            PackageSource::RemoteRegistry(url) | PackageSource::RegistryWithAuth { url, .. } => {
+                SourceId::for_registry(url)
+            },
             PackageSource::LocalRegistry(path) => SourceId::for_local_registry(path),
             PackageSource::CratesIo => SourceId::for_registry(&CRATES_IO_INDEX.into_url().unwrap()),
         }
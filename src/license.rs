@@ -0,0 +1,67 @@
+use crate::{Features, Package, PackageFetcher};
+use std::collections::BTreeMap;
+
+/// Key of [`PackageFetcher::license_report`] for packages that only declare `license-file`, a file with
+/// a license that doesn't have an SPDX identifier.
+pub const LICENSE_FILE_KEY: &str = "license-file";
+
+/// Key of [`PackageFetcher::license_report`] for packages that declare neither `license` nor `license-file`.
+pub const NO_LICENSE_KEY: &str = "none";
+
+impl PackageFetcher {
+    /// Resolves the dependency graph of `root`, fetches all of its packages, and groups them by the licenses
+    /// declared in their manifests, sorted.
+    ///
+    /// Licenses are the `license` SPDX expressions, as they are written, so `MIT OR Apache-2.0` and
+    /// `Apache-2.0 OR MIT` are separate keys. Packages that only declare `license-file` are under
+    /// [`LICENSE_FILE_KEY`], and the ones that declare neither under [`NO_LICENSE_KEY`]. The root itself isn't
+    /// included, only its dependencies.
+    ///
+    /// See [`PackageFetcher::resolve_graph`] for details on the resolution.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Features, Package, PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let serde = Package::from_str_ver("serde_json", "1.0.0", &PackageSource::crates_io())?;
+    ///
+    /// for (license, packages) in fetcher.license_report(serde, &Features::default())? {
+    ///     println!("{license}: {} packages", packages.len());
+    /// }
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn license_report(
+        &mut self,
+        root: Package,
+        features: &Features,
+    ) -> Result<BTreeMap<String, Vec<Package>>, String> {
+        let graph = self.resolve_graph(root, features)?;
+        let packages: Vec<Package> = graph
+            .nodes()
+            .iter()
+            .map(|node| node.package)
+            .filter(|package| *package != root)
+            .collect();
+
+        let _lock = self.lock_package_cache()?;
+        let package_set = self.package_set(&packages)?;
+        let mut fetched = package_set
+            .get_many(package_set.package_ids())
+            .map_err(|e| self.report_failure(&packages, e))?;
+        fetched.sort_unstable_by_key(|p| p.package_id());
+
+        let mut report: BTreeMap<String, Vec<Package>> = BTreeMap::new();
+        for pkg in fetched {
+            let metadata = pkg.manifest().metadata();
+            let license = match (&metadata.license, &metadata.license_file) {
+                (Some(license), _) => license.trim(),
+                (None, Some(_)) => LICENSE_FILE_KEY,
+                (None, None) => NO_LICENSE_KEY,
+            };
+            let package = Package {
+                package_id: pkg.package_id(),
+            };
+            report.entry(license.to_owned()).or_default().push(package);
+        }
+        Ok(report)
+    }
+}
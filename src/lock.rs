@@ -0,0 +1,74 @@
+use crate::PackageFetcher;
+use cargo::{core::shell::Verbosity, util::config::PackageCacheLock};
+use fs2::FileExt;
+use std::fs::File;
+
+/// What to do, when the package cache is locked by another process (e.g. by cargo building a project),
+/// see [`PackageFetcherBuilder::lock_behavior`](crate::PackageFetcherBuilder::lock_behavior).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LockBehavior {
+    /// Waits for the lock, printing `Blocking waiting for file lock on package cache` to the output of cargo.
+    #[default]
+    Wait,
+    /// Waits for the lock without printing anything.
+    WaitQuietly,
+    /// Returns an error instead of waiting.
+    ///
+    /// Another process can still acquire the lock between it being checked and acquired,
+    /// in which case the lock is waited for.
+    Error,
+}
+
+/// Package cache lock, held by [`PackageFetcher`].
+pub(crate) struct CacheLock<'a> {
+    _lock: PackageCacheLock<'a>,
+    fetcher: &'a PackageFetcher,
+}
+
+impl Drop for CacheLock<'_> {
+    fn drop(&mut self) {
+        let held = self.fetcher.cache_locks.get() - 1;
+        self.fetcher.cache_locks.set(held);
+        // Tokens of `PackageSource::RegistryWithAuth` are only used for the call they are passed to.
+        if held == 0 {
+            self.fetcher.release_tokens();
+        }
+    }
+}
+
+impl PackageFetcher {
+    /// Acquires the package cache lock according to the [`LockBehavior`] of the fetcher.
+    ///
+    /// Can be acquired multiple times, the lock is released once all of the returned guards are dropped.
+    pub(crate) fn lock_package_cache(&self) -> Result<CacheLock<'_>, String> {
+        let lock = match self.lock_behavior {
+            // Cargo doesn't wait for the lock it already holds.
+            _ if self.cache_locks.get() > 0 => self.config.acquire_package_cache_lock(),
+            LockBehavior::Wait => self.config.acquire_package_cache_lock(),
+            LockBehavior::WaitQuietly => {
+                let verbosity = self.config.shell().verbosity();
+                self.config.shell().set_verbosity(Verbosity::Quiet);
+                let lock = self.config.acquire_package_cache_lock();
+                self.config.shell().set_verbosity(verbosity);
+                lock
+            },
+            LockBehavior::Error => {
+                let path = self.config.home().as_path_unlocked().join(".package-cache");
+                // Nobody holds the lock, if the lock file doesn't exist yet.
+                if let Ok(file) = File::open(path) {
+                    if matches!(file.try_lock_exclusive(), Err(e) if e.kind() == fs2::lock_contended_error().kind()) {
+                        return Err("package cache is locked by another process".into());
+                    }
+                }
+                self.config.acquire_package_cache_lock()
+            },
+        }
+        .map_err(|e| e.to_string())?;
+
+        self.cache_locks.set(self.cache_locks.get() + 1);
+        Ok(CacheLock {
+            _lock: lock,
+            fetcher: self,
+        })
+    }
+}
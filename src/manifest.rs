@@ -0,0 +1,62 @@
+use crate::{ManifestError, PackageFetcher, PackageSource};
+use cargo::{
+    core::{EitherManifest, SourceId},
+    util::toml::read_manifest,
+};
+
+impl PackageFetcher {
+    /// Parses the manifest of a [`PackageSource::Path`] package, same as cargo does when the package is fetched,
+    /// returning where the problem is, if it's invalid.
+    ///
+    /// Fetching doesn't call it, so a valid package isn't parsed twice, it's meant for reporting a malformed
+    /// `Cargo.toml` early, e.g. when the path is given by a user. Manifests of the other sources are only available
+    /// once fetched, so they aren't validated.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{PackageFetcher, PackageSource};
+    /// let fetcher = PackageFetcher::new()?;
+    /// let source = PackageSource::path("path/to/foo")?;
+    ///
+    /// if let Err(error) = fetcher.validate_path_source(&source) {
+    ///     println!("{}: {:?}", error.path.display(), error.position);
+    /// }
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn validate_path_source(&self, source: &PackageSource) -> Result<(), ManifestError> {
+        let PackageSource::Path(root) = source else {
+            return Ok(());
+        };
+        let path = root.join("Cargo.toml");
+        let error = |position, message| ManifestError {
+            path: path.clone(),
+            position,
+            message,
+        };
+
+        let source_id = SourceId::for_path(root).map_err(|e| error(None, e.to_string()))?;
+        match read_manifest(&path, source_id, &self.config) {
+            Ok((EitherManifest::Real(_), _)) => Ok(()),
+            Ok((EitherManifest::Virtual(_), _)) => Err(error(
+                None,
+                "it's a virtual manifest of a workspace, not of a package".to_owned(),
+            )),
+            Err(e) => {
+                // The first cause is cargo's context with the path, which is reported separately.
+                let e = anyhow::Error::from(e);
+                let causes: Vec<_> = e.chain().skip(1).collect();
+                let position = causes.iter().find_map(|cause| {
+                    let position = if let Some(e) = cause.downcast_ref::<toml_edit::TomlError>() {
+                        e.line_col()
+                    } else if let Some(e) = cause.downcast_ref::<toml_edit::de::Error>() {
+                        e.line_col()
+                    } else {
+                        None
+                    };
+                    position.map(|(line, column)| (line + 1, column + 1))
+                });
+                let message = causes.iter().map(ToString::to_string).collect::<Vec<_>>().join(": ");
+                Err(error(position, message.trim_end().to_owned()))
+            },
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{Package, PackageFetcher, PackageSource};
+use cargo::{
+    core::{find_workspace_root, Dependency, QueryKind, Source, Summary, Workspace, WorkspaceConfig},
+    CargoResult, Config,
+};
+use std::{collections::HashSet, task::Poll};
+
+impl PackageFetcher {
+    /// Lists the packages of a git repository, sorted, e.g. the crates of a workspace.
+    ///
+    /// A package is selected from a repository of several packages by its name, same as cargo selects a git
+    /// dependency, so the `name` given to e.g. [`Self::resolve_first`] is one of these, and [`Self::fetch`]
+    /// returns the root of its directory in the checkout. Resolving a name the repository doesn't have errors with
+    /// [`ResolveError::MemberNotFound`](crate::ResolveError::MemberNotFound), listing them. Only the
+    /// `default-members` of a workspace are listed, if
+    /// [`PackageFetcherBuilder::default_members_only`](crate::PackageFetcherBuilder::default_members_only) is set.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let source = PackageSource::git("https://github.com/serde-rs/serde", None).unwrap();
+    ///
+    /// for member in fetcher.git_members(&source)? {
+    ///     println!("{}", member.lock_id());
+    /// }
+    /// let serde_derive = fetcher.resolve_first("serde_derive", None, &source, None)?;
+    /// # Ok::<(), String>(())
+    /// ```
+    ///
+    /// Errors, if `source` isn't a git repository.
+    pub fn git_members(&self, source: &PackageSource) -> Result<Vec<Package>, String> {
+        if !matches!(source, PackageSource::Git { .. }) {
+            return Err(format!("{source:?} is not a git repository"));
+        }
+        let _lock = self.lock_package_cache()?;
+        let source_id = self.source_id(source).map_err(|e| e.to_string())?;
+        let mut src = self.load_source(source_id, &HashSet::new())?;
+
+        let mut members = vec![];
+        query_all(&mut *src, &mut |summary| {
+            members.push(Package {
+                package_id: summary.package_id(),
+            })
+        })
+        .map_err(|e| e.to_string())?;
+        members.sort_unstable();
+
+        if let (true, Some(member)) = (self.default_members_only, members.first()) {
+            let member = self.fetch_package(*member)?;
+            if let Some(names) = default_member_names(&member, &self.config).map_err(|e| e.to_string())? {
+                members.retain(|member| names.contains(&member.package_id.name().to_string()));
+            }
+        }
+        Ok(members)
+    }
+}
+
+/// Names of the `default-members` of the workspace `package` is in, [`None`], if it's not in a workspace.
+fn default_member_names(package: &cargo::core::Package, config: &Config) -> CargoResult<Option<Vec<String>>> {
+    let root = match package.manifest().workspace_config() {
+        WorkspaceConfig::Root(_) => package.manifest_path().to_owned(),
+        WorkspaceConfig::Member { .. } => match find_workspace_root(package.manifest_path(), config)? {
+            Some(root) => root,
+            None => return Ok(None),
+        },
+    };
+    let ws = Workspace::new(&root, config)?;
+    Ok(Some(
+        ws.default_members().map(|member| member.name().to_string()).collect(),
+    ))
+}
+
+/// Names of the packages of a git `source`, sorted.
+pub(crate) fn member_names(source: &mut dyn Source) -> CargoResult<Vec<String>> {
+    let mut names = vec![];
+    query_all(source, &mut |summary| names.push(summary.name().to_string()))?;
+    names.sort_unstable();
+    names.dedup();
+    Ok(names)
+}
+
+/// Queries all packages of a git `source`, blocking until cargo is able to provide them.
+fn query_all(source: &mut dyn Source, f: &mut dyn FnMut(Summary)) -> CargoResult<()> {
+    // Git sources return all packages of the checkout for a fuzzy query, regardless of the name.
+    let dep = Dependency::parse("*", None, source.source_id())?;
+    loop {
+        match source.query(&dep, QueryKind::Fuzzy, f) {
+            Poll::Ready(res) => return res,
+            Poll::Pending => source.block_until_ready()?,
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use crate::{bundle::walk, PackageFetcher};
+use std::{fs, path::Path, time::SystemTime};
+
+impl PackageFetcher {
+    /// Normalizes the files of a fetched package in `root`, according to
+    /// [`PackageFetcherBuilder::normalized_mtime`](crate::PackageFetcherBuilder::normalized_mtime) and
+    /// [`PackageFetcherBuilder::normalize_line_endings`](crate::PackageFetcherBuilder::normalize_line_endings).
+    pub(crate) fn normalize_root(&self, root: &Path) -> Result<(), String> {
+        if self.normalized_mtime.is_none() && !self.normalize_line_endings {
+            return Ok(());
+        }
+        // Packages outside of the cargo home are the user's own, or vendored.
+        if !root.starts_with(self.config.home().as_path_unlocked()) {
+            return Ok(());
+        }
+
+        let _lock = self.lock_package_cache()?;
+        let error = |path: &Path, e: std::io::Error| format!("can't normalize `{}`: {e}", path.display());
+        let mut files = vec![];
+        walk(root, &mut files).map_err(|e| error(root, e))?;
+        for file in files {
+            let metadata = fs::symlink_metadata(&file).map_err(|e| error(&file, e))?;
+            if !metadata.is_file() {
+                continue;
+            }
+            if self.normalize_line_endings {
+                let contents = fs::read(&file).map_err(|e| error(&file, e))?;
+                let text = !contents.contains(&0) && std::str::from_utf8(&contents).is_ok();
+                if text && contents.windows(2).any(|pair| pair == b"\r\n") {
+                    let normalized = String::from_utf8_lossy(&contents).replace("\r\n", "\n");
+                    write_file(&file, &metadata, normalized.as_bytes()).map_err(|e| error(&file, e))?;
+                }
+            }
+            if let Some(mtime) = self.normalized_mtime {
+                set_modified(&file, mtime).map_err(|e| error(&file, e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replaces the contents of `file`, even if it's read-only, as files in package archives can be.
+fn write_file(file: &Path, metadata: &fs::Metadata, contents: &[u8]) -> std::io::Result<()> {
+    let permissions = metadata.permissions();
+    if !permissions.readonly() {
+        return fs::write(file, contents);
+    }
+
+    let mut writable = permissions.clone();
+    #[allow(clippy::permissions_set_readonly_false)]
+    writable.set_readonly(false);
+    fs::set_permissions(file, writable)?;
+    let written = fs::write(file, contents);
+    fs::set_permissions(file, permissions)?;
+    written
+}
+
+/// Sets the modification time of `file`, without opening it for writing, so read-only files can be changed too.
+fn set_modified(file: &Path, mtime: SystemTime) -> std::io::Result<()> {
+    filetime::set_file_mtime(file, filetime::FileTime::from_system_time(mtime))
+}
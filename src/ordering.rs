@@ -0,0 +1,51 @@
+use crate::PackageFetcher;
+use semver::Version;
+use std::{cmp::Ordering, fmt};
+
+/// Function comparing two versions.
+type CompareVersions = dyn Fn(&Version, &Version) -> Ordering;
+
+/// Function ordering versions, set with [`PackageFetcher::set_version_ordering`].
+pub(crate) struct VersionOrdering(Box<CompareVersions>);
+
+impl fmt::Debug for VersionOrdering {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("VersionOrdering")
+    }
+}
+
+impl PackageFetcher {
+    /// Sets a function ordering versions, used instead of the semver ordering to select the newest of the versions
+    /// matching a requirement, e.g. to take build metadata into account in a versioning scheme relying on it.
+    ///
+    /// It's used by the methods selecting the newest version, [`Self::resolve_first`], [`Self::resolve_and_fetch`],
+    /// [`Self::resolve_installable`], [`Self::resolve_many`], `resolve_as_of` and
+    /// [`Registry::resolve`](crate::Registry::resolve). It only affects which of the matching versions is selected,
+    /// versions are still matched against requirements by cargo, with the semver rules. Pre-releases are still only
+    /// selected by [`Self::resolve_installable`], if there are no other versions.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// // Selects the oldest matching version instead
+    /// fetcher.set_version_ordering(|a, b| b.cmp(a));
+    /// let serde = fetcher.resolve_first("serde", Some("1"), &PackageSource::crates_io(), None)?;
+    /// # Ok::<(), cargo_fetch::ResolveError>(())
+    /// ```
+    pub fn set_version_ordering<F: Fn(&Version, &Version) -> Ordering + 'static>(&mut self, ordering: F) {
+        self.version_ordering = Some(VersionOrdering(Box::new(ordering)));
+    }
+
+    /// Compares versions with the version ordering of the fetcher.
+    pub(crate) fn compare_versions(&self, a: &Version, b: &Version) -> Ordering {
+        match &self.version_ordering {
+            Some(ordering) => (ordering.0)(a, b),
+            None => a.cmp(b),
+        }
+    }
+
+    /// Returns whether version `a` is newer than `b`, according to the version ordering of the fetcher.
+    pub(crate) fn is_newer(&self, a: &Version, b: &Version) -> bool {
+        self.compare_versions(a, b) == Ordering::Greater
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{bundle::copy_package, Package, PackageFetcher};
+use cargo::{
+    core::{resolver::CliFeatures, Workspace},
+    ops::{self, PackageOpts, Packages},
+    util::Filesystem,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+impl PackageFetcher {
+    /// Fetches a single package, and builds its `.crate` archive in `dest`, same as `cargo package` does, returning
+    /// the path of the archive.
+    ///
+    /// Files are selected with `include` and `exclude` of the manifest, and the manifest is normalized, same as
+    /// for publishing. The package doesn't have to be committed, so the archive doesn't record the commit of
+    /// the repository in `.cargo_vcs_info.json`. Registry packages already are archives, so their cached `.crate`
+    /// is copied instead.
+    ///
+    /// Same as `cargo package`, this runs the verification steps of packaging: the metadata required to publish
+    /// is checked, warning if it's missing, dependencies are checked to all have versions, and the archive is
+    /// extracted and built with its dependencies, which are resolved and downloaded for it. The build is done in
+    /// a temporary directory in `dest`, which is removed afterwards.
+    pub fn package_crate<P: AsRef<Path>>(&mut self, package: Package, dest: P) -> Result<PathBuf, String> {
+        let dest = dest.as_ref();
+        let pkg = self.fetch_package(package)?;
+        let file_name = format!("{}-{}.crate", pkg.name(), pkg.version());
+        fs::create_dir_all(dest).map_err(|e| e.to_string())?;
+
+        if pkg.package_id().source_id().is_registry() {
+            let archive = self
+                .cache_paths(&package)?
+                .archive
+                .ok_or_else(|| format!("`{}` doesn't have an archive", pkg.package_id()))?;
+            fs::copy(&archive, dest.join(&file_name))
+                .map_err(|e| format!("can't copy `{}`: {e}", archive.display()))?;
+            return Ok(dest.join(file_name));
+        }
+
+        // Packaging writes to the target directory of the workspace, which mustn't be in a shared git checkout.
+        let target_dir = dest.join(format!(".{file_name}-target"));
+        let result = self.package_in(pkg, &target_dir).and_then(|()| {
+            let archive = target_dir.join("package").join(&file_name);
+            fs::rename(&archive, dest.join(&file_name)).map_err(|e| format!("can't move `{}`: {e}", archive.display()))
+        });
+        let _ = fs::remove_dir_all(&target_dir);
+        result.map(|()| dest.join(file_name))
+    }
+
+    /// Runs cargo's packaging for `pkg` in an ephemeral workspace with `target_dir`, which puts the archive into
+    /// `package` in it.
+    fn package_in(&self, pkg: cargo::core::Package, target_dir: &Path) -> Result<(), String> {
+        let pkg = if pkg.package_id().source_id().is_git() {
+            // Checkouts have cargo's `.cargo-ok` marker, which isn't committed, so a copy without it is packaged.
+            let copy = target_dir.join("source");
+            copy_package(&pkg, &copy, &self.config).map_err(|e| e.to_string())?;
+            add_all(&copy).map_err(|e| e.to_string())?;
+            cargo::core::Package::new(pkg.manifest().clone(), &copy.join("Cargo.toml"))
+        } else {
+            pkg
+        };
+
+        let ws = Workspace::ephemeral(pkg, &self.config, Some(Filesystem::new(target_dir.to_owned())), false)
+            .map_err(|e| e.to_string())?;
+        let opts = PackageOpts {
+            config: &self.config,
+            list: false,
+            check_metadata: true,
+            allow_dirty: true,
+            verify: true,
+            jobs: None,
+            keep_going: false,
+            to_package: Packages::Default,
+            targets: vec![],
+            cli_features: CliFeatures::new_all(false),
+        };
+        // Locks of the archives are released, so they can be moved.
+        ops::package(&ws, &opts).map_err(|e| format!("{e:#}"))?;
+        Ok(())
+    }
+}
+
+/// Puts all files in `dir` into the index of a new git repository in it, so cargo lists them all, the same as
+/// the committed files of the checkout they are copied from, instead of skipping hidden files.
+fn add_all(dir: &Path) -> Result<(), git2::Error> {
+    let repo = git2::Repository::init(dir)?;
+    let mut index = repo.index()?;
+    index.add_all(["*"], git2::IndexAddOption::FORCE, None)?;
+    index.write()
+}
@@ -0,0 +1,130 @@
+use crate::{GitReference, PackageSource, ParseError};
+use cargo::{core::SourceId, sources::CRATES_IO_INDEX, util::IntoUrl};
+use std::str::FromStr;
+use url::Url;
+
+/// Parses a [`GitReference`] from `branch:<name>`, `tag:<name>`, `rev:<commit>` or `tag-pattern:<glob>`.
+///
+/// ```
+/// # use cargo_fetch::GitReference;
+/// assert_eq!("branch:next".parse(), Ok(GitReference::Branch("next".into())));
+/// assert_eq!(GitReference::try_from("tag-pattern:v*"), Ok(GitReference::TagPattern("v*".into())));
+/// assert!("next".parse::<GitReference>().is_err());
+/// ```
+impl FromStr for GitReference {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |message: &str| ParseError {
+            input: s.to_owned(),
+            message: message.to_owned(),
+        };
+        let (kind, name) = s
+            .split_once(':')
+            .ok_or_else(|| error("expected `branch:`, `tag:`, `rev:` or `tag-pattern:` followed by a name"))?;
+        if name.is_empty() {
+            return Err(error("missing the name of the reference"));
+        }
+
+        let name = name.to_owned();
+        Ok(match kind {
+            "branch" => Self::Branch(name),
+            "tag" => Self::Tag(name),
+            "rev" => Self::Revision(name),
+            "tag-pattern" => Self::TagPattern(name),
+            _ => return Err(error("unknown kind of git reference")),
+        })
+    }
+}
+
+impl TryFrom<&str> for GitReference {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// Parses a [`PackageSource`], inferring its kind from the string:
+///
+/// - `crates-io` is [`PackageSource::CratesIo`].
+/// - `git+<url>` is [`PackageSource::Git`], with the reference in the query, e.g. `?branch=next`, same as
+///   cargo writes git sources into `Cargo.lock`.
+/// - `registry+<url>`, `sparse+<url>`, and plain `http://` and `https://` urls are [`PackageSource::RemoteRegistry`],
+///   or [`PackageSource::CratesIo`] for its index url.
+/// - `path+file://<path>` and `local-registry+file://<path>` are [`PackageSource::Path`] and
+///   [`PackageSource::LocalRegistry`].
+/// - Anything else is a path, same as with [`PackageSource::path`].
+///
+/// So the source part of [`Package::to_spec_string`](crate::Package::to_spec_string) can be parsed back.
+///
+/// ```
+/// # use cargo_fetch::{GitReference, PackageSource};
+/// let regex = PackageSource::git("https://github.com/rust-lang/regex", Some(GitReference::Branch("next".into())))
+///     .unwrap();
+/// assert_eq!("git+https://github.com/rust-lang/regex?branch=next".parse(), Ok(regex));
+/// assert_eq!("crates-io".parse(), Ok(PackageSource::CratesIo));
+/// assert_eq!(
+///     PackageSource::try_from("sparse+https://index.crates.io/"),
+///     Ok(PackageSource::remote("sparse+https://index.crates.io/").unwrap()),
+/// );
+/// assert_eq!(PackageSource::try_from("local-registry+file:///registry"), Ok(PackageSource::local("/registry")));
+/// ```
+impl FromStr for PackageSource {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |message: String| ParseError {
+            input: s.to_owned(),
+            message,
+        };
+        let file_path = |url: &str| {
+            Url::parse(url)
+                .ok()
+                .and_then(|url| url.to_file_path().ok())
+                .ok_or_else(|| error(format!("`{url}` is not a valid file url")))
+        };
+        let registry = |url: &str| {
+            let url = url.into_url().map_err(|e| error(e.to_string()))?;
+            if url.as_str() == CRATES_IO_INDEX {
+                return Ok(Self::CratesIo);
+            }
+            Ok(Self::RemoteRegistry(url))
+        };
+
+        if s == "crates-io" {
+            return Ok(Self::CratesIo);
+        }
+        if s.starts_with("http://") || s.starts_with("https://") {
+            return registry(s);
+        }
+        match s.split_once('+') {
+            Some(("git", _)) => {
+                let source_id = SourceId::from_url(s).map_err(|e| error(e.to_string()))?;
+                let git_ref = match source_id.git_reference() {
+                    Some(cargo::core::GitReference::Branch(branch)) => GitReference::Branch(branch.clone()),
+                    Some(cargo::core::GitReference::Tag(tag)) => GitReference::Tag(tag.clone()),
+                    Some(cargo::core::GitReference::Rev(rev)) => GitReference::Revision(rev.clone()),
+                    _ => GitReference::DefaultBranch,
+                };
+                Ok(Self::Git {
+                    url: source_id.url().clone(),
+                    git_ref,
+                })
+            },
+            Some(("registry", url)) => registry(url),
+            Some(("sparse", _)) => registry(s),
+            Some(("path", url)) => Ok(Self::Path(file_path(url)?)),
+            Some(("local-registry", url)) => Ok(Self::LocalRegistry(file_path(url)?)),
+            _ => Self::path(s).map_err(error),
+        }
+    }
+}
+
+impl TryFrom<&str> for PackageSource {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{Package, PackageFetcher, PackageSource, Resolution, ResolveError};
+use semver::VersionReq;
+use std::collections::HashSet;
+
+impl PackageFetcher {
+    /// Resolves the newest version of a package matching the version requirement from the first of `sources` that
+    /// has the package, same as [`Self::resolve_first`], and returns the source it was resolved from.
+    ///
+    /// Unlike [`Self::resolve_first_of`] and [`ResolveRequest::fallback`](crate::ResolveRequest::fallback), later
+    /// sources are only tried for packages the earlier ones don't have at all, regardless of their versions.
+    /// A package of a private registry listed first is resolved from it, even if a later public registry has newer
+    /// versions, or versions matching the requirement, when the private one doesn't, and errors of the private
+    /// registry are returned, instead of trying the public one. Packages with only yanked versions count as missing,
+    /// unless they are whitelisted.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{PackageFetcher, PackageSource};
+    /// let fetcher = PackageFetcher::new()?;
+    /// let private = PackageSource::remote("https://mirror.example.com/index").unwrap();
+    /// let sources = [private, PackageSource::crates_io()];
+    ///
+    /// let serde = fetcher.resolve_with_precedence("serde", Some("1"), &sources, None)?;
+    /// println!("{} from {:?}", serde.package.to_spec_string(), serde.source);
+    /// # Ok::<(), cargo_fetch::ResolveError>(())
+    /// ```
+    ///
+    /// Errors with [`ResolveError::CrateNotFound`], if none of the sources have the package, git repositories of
+    /// other packages ([`ResolveError::MemberNotFound`]) are skipped the same.
+    pub fn resolve_with_precedence<N: AsRef<str>>(
+        &self,
+        name: N,
+        version: Option<&str>,
+        sources: &[PackageSource],
+        yanked_whitelist: Option<HashSet<Package>>,
+    ) -> Result<Resolution, ResolveError> {
+        let name = name.as_ref();
+        let requirement = VersionReq::parse(version.unwrap_or("*")).map_err(|e| e.to_string())?;
+        // Same as `resolve_first_detailed`, for whichever source the package is resolved from.
+        let updated = self.config.updated_sources().clone();
+        let last_updated: Vec<_> = sources.iter().map(|source| self.index_last_updated(source)).collect();
+
+        for (source, last_updated) in sources.iter().zip(last_updated) {
+            let package = match self.resolve_first(name, version, source, yanked_whitelist.clone()) {
+                Err(ResolveError::CrateNotFound { .. } | ResolveError::MemberNotFound { .. }) => continue,
+                result => result?,
+            };
+            let index_updated = !self.config.offline()
+                && (self.config.updated_sources().iter().any(|id| !updated.contains(id))
+                    || self.index_last_updated(source) != last_updated);
+            return Ok(Resolution {
+                package,
+                source: source.clone(),
+                requirement,
+                index_updated,
+            });
+        }
+        Err(ResolveError::CrateNotFound { name: name.to_owned() })
+    }
+}
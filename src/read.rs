@@ -0,0 +1,185 @@
+use crate::{registry, Package, PackageFetcher};
+use cargo::{
+    core::{source::MaybePackage, PackageId},
+    util::{errors::HttpNotSuccessful, network, Filesystem},
+    CargoResult, Config,
+};
+use cargo_util::Sha256;
+use flate2::read::GzDecoder;
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Read},
+    path::{Component, Path, PathBuf},
+};
+use tar::Archive;
+
+impl PackageFetcher {
+    /// Reads a single file of a package, at `path` relative to the root of the package.
+    ///
+    /// Registry packages that aren't extracted yet are not extracted: the file is read directly from the `.crate`
+    /// archive, which is downloaded and cached the same way cargo does it, if it isn't cached yet. This makes reading
+    /// the same file (e.g. `LICENSE`) of many packages much cheaper than fetching them. Other packages are fetched,
+    /// and the file is read from their root.
+    ///
+    /// Errors, if the package doesn't have the file.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Package, PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let serde = Package::from_str_ver("serde", "1.0.152", &PackageSource::CratesIo)?;
+    /// let license = fetcher.read_file(serde, "LICENSE-MIT")?;
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn read_file<P: AsRef<Path>>(&mut self, package: Package, path: P) -> Result<Vec<u8>, String> {
+        let path = path.as_ref();
+        let package_id = package.package_id;
+        if path.as_os_str().is_empty() || path.components().any(|c| !matches!(c, Component::Normal(_))) {
+            return Err(format!(
+                "`{}` is not a relative path of a file in `{package_id}`",
+                path.display()
+            ));
+        }
+        let not_found = || format!("file `{}` not found in package `{package_id}`", path.display());
+
+        let mut contents = None;
+        if package_id.source_id().is_registry() {
+            let _lock = self.lock_package_cache()?;
+            let archive = self
+                .crate_archive(package_id)
+                .map_err(|e| format!("failed to download `{package_id}`: {e}"))?;
+            contents = archive.map(|archive| read_entry(&archive, package_id, path));
+        }
+        let contents = match contents {
+            Some(contents) => contents,
+            None => match fs::read(self.fetch(package)?.join(path)) {
+                Ok(contents) => Ok(Some(contents)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            },
+        };
+        contents
+            .map_err(|e| format!("failed to read `{}` of `{package_id}`: {e}", path.display()))?
+            .ok_or_else(not_found)
+    }
+
+    /// Returns the `.crate` archive of a registry package, downloading and caching it if needed.
+    ///
+    /// Returns [`None`], if the package is already extracted, so it's cheaper to read from its root.
+    fn crate_archive(&self, package_id: PackageId) -> CargoResult<Option<PathBuf>> {
+        let whitelist = HashSet::from([package_id]);
+        let mut source = self
+            .load_source(package_id.source_id(), &whitelist)
+            .map_err(anyhow::Error::msg)?;
+        let source_id = source.replaced_source_id();
+        let file_name = format!("{}-{}", package_id.name(), package_id.version());
+
+        if source_id.is_registry() && !source_id.is_remote_registry() {
+            let root = source_id
+                .url()
+                .to_file_path()
+                .map_err(|()| anyhow::format_err!("invalid path of {source_id}"))?;
+            // Missing archives are reported by fetching the package.
+            return Ok(Some(root.join(format!("{file_name}.crate"))).filter(|archive| archive.exists()));
+        }
+        if !source_id.is_remote_registry() {
+            return Ok(None);
+        }
+
+        // Same locations cargo extracts and caches packages at, both are complete once they are not empty.
+        let short_name = registry::short_name(source_id);
+        let complete = |path: &Filesystem| {
+            let path = self.config.assert_package_cache_locked(path);
+            path.metadata().is_ok_and(|metadata| metadata.len() > 0)
+        };
+        let extracted = self.config.registry_source_path().join(&short_name).join(&file_name);
+        if complete(&extracted.join(".cargo-ok")) {
+            return Ok(None);
+        }
+        let cached = self
+            .config
+            .registry_cache_path()
+            .join(&short_name)
+            .join(format!("{file_name}.crate"));
+        if complete(&cached) {
+            return Ok(Some(cached.into_path_unlocked()));
+        }
+
+        let (url, descriptor, authorization) = match source.download(package_id)? {
+            MaybePackage::Ready(_) => return Ok(None),
+            MaybePackage::Download {
+                url,
+                descriptor,
+                authorization,
+            } => (url, descriptor, authorization),
+        };
+        let contents = network::with_retry(&self.config, || http_get(&self.config, &url, authorization.as_deref()))?;
+
+        if let Some(max) = self.max_crate_size.filter(|&max| contents.len() as u64 > max) {
+            anyhow::bail!(
+                "its archive is {} bytes, maximum allowed is {max} bytes",
+                contents.len()
+            );
+        }
+        if self.verify_checksums {
+            let expected = registry::checksum(&mut *source, package_id)?;
+            if Sha256::new().update(&contents).finish_hex() != expected {
+                anyhow::bail!("failed to verify the checksum of `{package_id}`");
+            }
+        }
+        let cached = self.config.assert_package_cache_locked(&cached);
+        fs::create_dir_all(cached.parent().expect("archive is in a directory"))?;
+        fs::write(cached, contents)?;
+        self.config.shell().status("Downloaded", descriptor)?;
+        Ok(Some(cached.to_owned()))
+    }
+}
+
+/// Reads the file at `path` of `package_id` from its `.crate` archive, [`None`] if the archive doesn't have it.
+fn read_entry(archive: &Path, package_id: PackageId, path: &Path) -> CargoResult<Option<Vec<u8>>> {
+    // Archives contain a single directory named after the package.
+    let path = Path::new(&format!("{}-{}", package_id.name(), package_id.version())).join(path);
+    let mut archive = Archive::new(GzDecoder::new(fs::File::open(archive)?));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.header().entry_type().is_file() && *entry.path()? == *path {
+            let mut contents = vec![];
+            entry.read_to_end(&mut contents)?;
+            return Ok(Some(contents));
+        }
+    }
+    Ok(None)
+}
+
+/// Downloads `url`, same as cargo downloads `.crate` archives.
+fn http_get(config: &Config, url: &str, authorization: Option<&str>) -> CargoResult<Vec<u8>> {
+    let mut handle = cargo::ops::http_handle(config)?;
+    handle.get(true)?;
+    handle.url(url)?;
+    handle.follow_location(true)?;
+    if let Some(authorization) = authorization {
+        let mut headers = curl::easy::List::new();
+        headers.append(&format!("Authorization: {authorization}"))?;
+        handle.http_headers(headers)?;
+    }
+
+    let mut body = vec![];
+    {
+        let mut transfer = handle.transfer();
+        transfer.write_function(|data| {
+            body.extend_from_slice(data);
+            Ok(data.len())
+        })?;
+        transfer.perform()?;
+    }
+
+    match handle.response_code()? {
+        200 => Ok(body),
+        code => Err(HttpNotSuccessful {
+            code,
+            url: url.to_owned(),
+            body,
+        }
+        .into()),
+    }
+}
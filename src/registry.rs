@@ -0,0 +1,509 @@
+use crate::{error::CrateTooLarge, query_blocking, PackageFetcher, PackageSource};
+use cargo::{
+    core::{source::MaybePackage, Dependency, PackageId, QueryKind, Source, SourceId, Summary},
+    sources::{RegistrySource, SourceConfigMap},
+    util::{config::ConfigValue, errors::HttpNotSuccessful, short_hash, Config, Filesystem},
+    CargoResult,
+};
+use std::{
+    cell::Cell,
+    collections::HashSet,
+    fs,
+    path::PathBuf,
+    task::Poll,
+    time::{Duration, SystemTime},
+};
+use url::Url;
+
+/// Overrides for the `config.json` of a registry index, for registries that don't provide a standard one.
+///
+/// Registered for an index url with [`PackageFetcherBuilder::registry_index_config`](crate::PackageFetcherBuilder::registry_index_config).
+/// Overrides are matched against the index url after source replacement is applied, see
+/// [`PackageFetcher::effective_source_url`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RegistryIndexConfig {
+    /// Download endpoint, replaces `dl` of the `config.json`.
+    ///
+    /// Same as in the `config.json`, `{crate}`, `{version}`, `{prefix}`, `{lowerprefix}` and `{sha256-checksum}`
+    /// markers are replaced with values of the downloaded package, and if there are no markers,
+    /// `/{crate}/{version}/download` is appended to it.
+    ///
+    /// When set, `.crate` archives of the registry are downloaded from it without reading the `config.json`.
+    /// Downloads from it are not authenticated. It's also used by [`PackageFetcher::download_url`].
+    pub dl: Option<String>,
+    /// API endpoint, replaces `api` of the `config.json`.
+    ///
+    /// Returned by [`PackageFetcher::registry_api_url`].
+    pub api: Option<String>,
+}
+
+/// When registry indexes are refreshed before they are queried, see
+/// [`PackageFetcherBuilder::index_freshness`](crate::PackageFetcherBuilder::index_freshness).
+///
+/// A git index is refreshed by fetching its repository, which downloads all the changes since the last fetch, and its
+/// age is the time since the last fetch. A sparse index has no index-wide refresh, each of its files is cached
+/// separately: refreshing it revalidates the files of the queried packages with conditional requests, downloading only
+/// the changed ones, and files that aren't cached are always downloaded. Its age is the time since the last query that
+/// refreshed it, so the files of the packages that query didn't need can be older.
+///
+/// Indexes that were never fetched are fetched regardless, and a package missing from the cached copy of a git index
+/// makes cargo fetch it, at most once per fetcher. Offline fetchers never refresh indexes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexFreshness {
+    /// Refreshes the index before every query.
+    Always,
+    /// Refreshes the index before a query, if it was last refreshed longer ago than the duration.
+    MaxAge(Duration),
+    /// Never refreshes the index, relying on its cached copy.
+    Never,
+}
+
+impl PackageFetcher {
+    /// Returns the url cargo would download the `.crate` archive of a registry package from.
+    ///
+    /// The url is built from the `dl` template in the `config.json` of the registry index, after
+    /// applying source replacement and [`RegistryIndexConfig`] overrides. Returns [`None`] for packages
+    /// that are not downloaded from a remote registry (git, path and local registry sources).
+    pub fn download_url(&self, package: &crate::Package) -> Result<Option<Url>, String> {
+        let _lock = self.lock_package_cache()?;
+        let whitelist: HashSet<PackageId> = std::iter::once(package.package_id).collect();
+
+        let source_id = self
+            .load_source(package.package_id.source_id(), &whitelist)?
+            .replaced_source_id();
+        if !source_id.is_remote_registry() {
+            return Ok(None);
+        }
+
+        let mut registry = RegistrySource::remote(source_id, &whitelist, &self.config).map_err(|e| e.to_string())?;
+        let dl = match self.index_config(source_id).and_then(|config| config.dl.clone()) {
+            Some(dl) => dl,
+            None => registry_config(&mut registry)?.dl,
+        };
+
+        let package_id = package.package_id.with_source_id(source_id);
+        let checksum = if dl.contains("{sha256-checksum}") {
+            checksum(&mut registry, package_id).map_err(|e| e.to_string())?
+        } else {
+            String::new()
+        };
+
+        Url::parse(&dl_url(&dl, package_id, &checksum))
+            .map(Some)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Returns the API url of a remote registry, after applying source replacement and
+    /// [`RegistryIndexConfig`] overrides.
+    ///
+    /// Returns [`None`] if the registry doesn't support API commands, or if `source` is not a remote registry.
+    pub fn registry_api_url(&self, source: &PackageSource) -> Result<Option<Url>, String> {
+        let _lock = self.lock_package_cache()?;
+
+        let source_id = self.source_id(source).map_err(|e| e.to_string())?;
+        let source_id = self.load_source(source_id, &HashSet::new())?.replaced_source_id();
+        if !source_id.is_remote_registry() {
+            return Ok(None);
+        }
+
+        let api = match self.index_config(source_id).and_then(|config| config.api.clone()) {
+            Some(api) => Some(api),
+            None => {
+                let mut registry =
+                    RegistrySource::remote(source_id, &HashSet::new(), &self.config).map_err(|e| e.to_string())?;
+                registry_config(&mut registry)?.api
+            },
+        };
+
+        api.map(|api| Url::parse(&api)).transpose().map_err(|e| e.to_string())
+    }
+
+    /// Returns [`RegistryIndexConfig`] overrides registered for the given source.
+    pub(crate) fn index_config(&self, source_id: SourceId) -> Option<&RegistryIndexConfig> {
+        self.registry_index_configs.get(source_id.canonical_url())
+    }
+
+    /// Whether the index of a remote registry has to be refreshed according to the [`IndexFreshness`] of the fetcher,
+    /// [`None`] if cargo decides it.
+    pub(crate) fn index_needs_refresh(&self, source_id: SourceId) -> Option<bool> {
+        let freshness = self.index_freshness?;
+        if self.config.offline() {
+            return Some(false);
+        }
+        let age = fs::metadata(last_updated_path(&self.config, source_id).as_path_unlocked())
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .map(|modified| modified.elapsed().unwrap_or_default());
+        Some(match (freshness, age) {
+            (_, None) | (IndexFreshness::Always, _) => true,
+            (IndexFreshness::MaxAge(max), Some(age)) => age > max,
+            (IndexFreshness::Never, _) => false,
+        })
+    }
+
+    /// Returns when the index of a remote registry was last refreshed, [`None`] for other sources.
+    pub(crate) fn index_last_updated(&self, source: &PackageSource) -> Option<SystemTime> {
+        let source_id = self.source_id(source).ok()?;
+        let source_id = SourceConfigMap::new(&self.config)
+            .and_then(|map| map.load(source_id, &HashSet::new()))
+            .ok()?
+            .replaced_source_id();
+        if !source_id.is_remote_registry() {
+            return None;
+        }
+        let last_updated = last_updated_path(&self.config, source_id);
+        fs::metadata(last_updated.as_path_unlocked()).and_then(|metadata| metadata.modified()).ok()
+    }
+}
+
+impl crate::Package {
+    /// Returns the name of the alternate registry the package is from, as configured in the `registries` table
+    /// of the cargo config of `fetcher`.
+    ///
+    /// Returns [`None`] for packages from crates.io, registries that are not configured by name, and sources
+    /// other than remote registries.
+    pub fn registry_name(&self, fetcher: &PackageFetcher) -> Option<String> {
+        registry_name(&fetcher.config, self.package_id.source_id())
+    }
+}
+
+/// Finds the name the `registries` table of the cargo config gives to the registry of `source_id`.
+pub(crate) fn registry_name(config: &Config, source_id: SourceId) -> Option<String> {
+    if !source_id.is_remote_registry() || source_id.is_crates_io() {
+        return None;
+    }
+
+    let registries = match config.values().ok()?.get("registries")? {
+        ConfigValue::Table(registries, _) => registries,
+        _ => return None,
+    };
+    registries
+        .keys()
+        .filter(|name| {
+            SourceId::alt_registry(config, name).is_ok_and(|id| id.canonical_url() == source_id.canonical_url())
+        })
+        .min()
+        .cloned()
+}
+
+/// File the time of the last refresh of a remote registry index is recorded in, by its modification time.
+///
+/// Cargo records it for git indexes, for sparse ones it's recorded by [`RegistryDownloadSource`].
+pub(crate) fn last_updated_path(config: &Config, source_id: SourceId) -> Filesystem {
+    config.registry_index_path().join(short_name(source_id)).join(".last-updated")
+}
+
+/// Name of the directories cargo stores downloaded and extracted packages of a registry in.
+pub(crate) fn short_name(source_id: SourceId) -> String {
+    format!("{}-{}", source_id.url().host_str().unwrap_or(""), short_hash(&source_id))
+}
+
+/// Reads the `config.json` of a remote registry.
+pub(crate) fn registry_config(registry: &mut RegistrySource) -> Result<cargo::sources::registry::RegistryConfig, String> {
+    loop {
+        match registry.config() {
+            Poll::Ready(res) => break res.map_err(|e| e.to_string())?,
+            Poll::Pending => registry.block_until_ready().map_err(|e| e.to_string())?,
+        }
+    }
+    .ok_or_else(|| "registry index doesn't have a `config.json`".into())
+}
+
+/// Looks up the checksum of a package in the registry index.
+pub(crate) fn checksum(source: &mut dyn Source, package_id: PackageId) -> CargoResult<String> {
+    let dep = Dependency::parse(
+        package_id.name(),
+        Some(&format!("={}", package_id.version())),
+        package_id.source_id(),
+    )?;
+    let mut checksum = None;
+    query_blocking(source, &dep, &mut |sum| {
+        if sum.package_id() == package_id {
+            checksum = sum.checksum().map(str::to_owned);
+        }
+    })?;
+    checksum.ok_or_else(|| anyhow::format_err!("registry index doesn't have a checksum for `{package_id}`"))
+}
+
+/// Expands a `dl` template of a registry for a package, the same way cargo does.
+fn dl_url(dl: &str, package_id: PackageId, checksum: &str) -> String {
+    let name = package_id.name();
+    let version = package_id.version().to_string();
+
+    if !["{crate}", "{version}", "{prefix}", "{lowerprefix}", "{sha256-checksum}"]
+        .iter()
+        .any(|marker| dl.contains(marker))
+    {
+        return format!("{dl}/{name}/{version}/download");
+    }
+
+    let prefix = match name.len() {
+        1 => String::from("1"),
+        2 => String::from("2"),
+        3 => format!("3/{}", &name[..1]),
+        _ => format!("{}/{}", &name[0..2], &name[2..4]),
+    };
+    dl.replace("{crate}", &name)
+        .replace("{version}", &version)
+        .replace("{prefix}", &prefix)
+        .replace("{lowerprefix}", &prefix.to_lowercase())
+        .replace("{sha256-checksum}", checksum)
+}
+
+/// Downloads the archive of `package` from `url`, aborting the transfer once it's larger than `limit`.
+fn download_limited(
+    config: &Config,
+    package: PackageId,
+    url: &str,
+    authorization: Option<&str>,
+    limit: u64,
+) -> CargoResult<Vec<u8>> {
+    cargo::util::network::with_retry(config, || {
+        let mut handle = cargo::ops::http_handle(config)?;
+        handle.get(true)?;
+        handle.url(url)?;
+        handle.follow_location(true)?;
+        if let Some(authorization) = authorization {
+            let mut headers = curl::easy::List::new();
+            headers.append(&format!("Authorization: {authorization}"))?;
+            handle.http_headers(headers)?;
+        }
+        // Servers reporting the size are rejected before the transfer starts, others once it exceeds the limit.
+        handle.max_filesize(limit)?;
+
+        let mut contents = Vec::new();
+        let mut received = 0;
+        let performed = {
+            let mut transfer = handle.transfer();
+            transfer.write_function(|data| {
+                received += data.len() as u64;
+                if received > limit {
+                    // Taking less than given aborts the transfer.
+                    return Ok(0);
+                }
+                contents.extend_from_slice(data);
+                Ok(data.len())
+            })?;
+            transfer.perform()
+        };
+        match performed {
+            Err(e) if e.is_filesize_exceeded() || received > limit => {
+                // Size reported by the server, or a lower bound of it, if the transfer was aborted without one.
+                let size = match handle.content_length_download()? {
+                    reported if reported >= 0.0 => reported as u64,
+                    _ => (handle.download_size()? as u64).max(received).max(limit + 1),
+                };
+                return Err(CrateTooLarge { package, size, limit }.into());
+            },
+            performed => performed?,
+        }
+        // `file://` urls don't have a response code.
+        match handle.response_code()? {
+            200 | 0 => Ok(contents),
+            code => Err(HttpNotSuccessful {
+                code,
+                url: url.to_owned(),
+                body: contents,
+            }
+            .into()),
+        }
+    })
+}
+
+/// Registry source, applying download options of [`PackageFetcher`] cargo doesn't support.
+///
+/// Downloads `.crate` archives from an overridden `dl` endpoint, rejects archives larger than the limit, skips
+/// verification of their checksums if it's disabled, provides tokens from credential providers to cargo, and
+/// refreshes the index according to the [`IndexFreshness`] of the fetcher. Everything else is delegated to the wrapped source.
+pub(crate) struct RegistryDownloadSource<'cfg> {
+    inner: Box<dyn Source + 'cfg>,
+    dl: Option<String>,
+    max_crate_size: Option<u64>,
+    verify_checksums: bool,
+    /// Total size of the archives downloaded by the fetcher.
+    downloaded_bytes: &'cfg Cell<u64>,
+    /// Whether the token of the registry was already looked up.
+    token_provided: bool,
+    /// File to record the refresh of the index in, once a query refreshed it.
+    refreshing: Option<Filesystem>,
+    config: &'cfg Config,
+}
+
+impl<'cfg> RegistryDownloadSource<'cfg> {
+    pub(crate) fn new(
+        inner: Box<dyn Source + 'cfg>,
+        dl: Option<String>,
+        max_crate_size: Option<u64>,
+        verify_checksums: bool,
+        downloaded_bytes: &'cfg Cell<u64>,
+        config: &'cfg Config,
+    ) -> Self {
+        Self {
+            inner,
+            dl,
+            max_crate_size,
+            verify_checksums,
+            downloaded_bytes,
+            token_provided: false,
+            refreshing: None,
+            config,
+        }
+    }
+
+    /// Refreshes the index with the next query.
+    pub(crate) fn refresh_index(&mut self) {
+        let source_id = self.inner.replaced_source_id();
+        // Cargo refreshes git indexes only once per session.
+        self.config.updated_sources().remove(&source_id);
+        self.inner.invalidate_cache();
+        self.refreshing = Some(last_updated_path(self.config, source_id));
+    }
+
+    /// Path of the `.crate` archive of `package`, same as cargo caches downloaded archives at.
+    fn cached_archive(&self, package: PackageId) -> PathBuf {
+        let cached = self
+            .config
+            .registry_cache_path()
+            .join(short_name(self.inner.replaced_source_id()))
+            .join(format!("{}-{}.crate", package.name(), package.version()));
+        self.config.assert_package_cache_locked(&cached).to_owned()
+    }
+
+    /// Returns the package, if its archive is cached, or where to download it from.
+    fn start_download(&mut self, package: PackageId) -> CargoResult<MaybePackage> {
+        let source_id = self.inner.replaced_source_id();
+        let cached = self.cached_archive(package);
+
+        let dl = match &self.dl {
+            Some(dl) => dl,
+            None => {
+                if let Ok(metadata) = cached.metadata() {
+                    self.check_size(package, metadata.len())?;
+                }
+                // Cargo doesn't send tokens without `-Z registry-auth`, see `PackageFetcherBuilder::registry_auth`.
+                if !self.token_provided && source_id.is_remote_registry() && self.config.cli_unstable().registry_auth {
+                    crate::credential::provide_token(self.config, source_id)?;
+                    self.token_provided = true;
+                }
+                return self.inner.download(package);
+            },
+        };
+
+        // Reusing the cached archive avoids asking the wrapped source, which would need the `config.json`.
+        if let Ok(contents) = std::fs::read(cached) {
+            if !contents.is_empty() {
+                self.check_size(package, contents.len() as u64)?;
+                return self.inner.finish_download(package, contents).map(MaybePackage::Ready);
+            }
+        }
+
+        let checksum = checksum(&mut *self.inner, package)?;
+        Ok(MaybePackage::Download {
+            url: dl_url(dl, package, &checksum),
+            descriptor: package.to_string(),
+            authorization: None,
+        })
+    }
+
+    fn check_size(&self, package: PackageId, size: u64) -> CargoResult<()> {
+        match self.max_crate_size {
+            Some(limit) if size > limit => Err(CrateTooLarge { package, size, limit }.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Source for RegistryDownloadSource<'_> {
+    fn source_id(&self) -> SourceId {
+        self.inner.source_id()
+    }
+
+    fn replaced_source_id(&self) -> SourceId {
+        self.inner.replaced_source_id()
+    }
+
+    fn supports_checksums(&self) -> bool {
+        self.inner.supports_checksums()
+    }
+
+    fn requires_precise(&self) -> bool {
+        self.inner.requires_precise()
+    }
+
+    fn query(&mut self, dep: &Dependency, kind: QueryKind, f: &mut dyn FnMut(Summary)) -> Poll<CargoResult<()>> {
+        let res = self.inner.query(dep, kind, f);
+        if let (Poll::Ready(Ok(())), Some(last_updated)) = (&res, &self.refreshing) {
+            fs::File::create(self.config.assert_package_cache_locked(last_updated))?;
+            self.refreshing = None;
+        }
+        res
+    }
+
+    fn invalidate_cache(&mut self) {
+        self.inner.invalidate_cache()
+    }
+
+    fn download(&mut self, package: PackageId) -> CargoResult<MaybePackage> {
+        match (self.start_download(package)?, self.max_crate_size) {
+            // Cargo downloads archives into memory whole, so they are downloaded here, to abort the transfer
+            // once it exceeds the limit.
+            (MaybePackage::Download { url, descriptor, authorization }, Some(limit)) => {
+                if self.config.offline() {
+                    anyhow::bail!("can't download `{package}` in offline mode");
+                }
+                let contents = download_limited(self.config, package, &url, authorization.as_deref(), limit)?;
+                self.config.shell().status("Downloaded", descriptor)?;
+                self.finish_download(package, contents).map(MaybePackage::Ready)
+            },
+            (downloaded, _) => Ok(downloaded),
+        }
+    }
+
+    fn finish_download(&mut self, package: PackageId, contents: Vec<u8>) -> CargoResult<cargo::core::Package> {
+        self.check_size(package, contents.len() as u64)?;
+        // Cargo only calls this with archives it downloaded, cached ones are extracted by `download`.
+        self.downloaded_bytes.set(self.downloaded_bytes.get() + contents.len() as u64);
+        if self.verify_checksums || self.dl.is_some() {
+            return self.inner.finish_download(package, contents);
+        }
+
+        // Cargo verifies archives only before caching them, already cached ones are just extracted.
+        let cached = self.cached_archive(package);
+        if let Some(parent) = cached.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&cached, contents)?;
+        match self.inner.download(package)? {
+            MaybePackage::Ready(package) => Ok(package),
+            MaybePackage::Download { .. } => anyhow::bail!("archive of `{package}` wasn't cached"),
+        }
+    }
+
+    fn fingerprint(&self, pkg: &cargo::core::Package) -> CargoResult<String> {
+        self.inner.fingerprint(pkg)
+    }
+
+    fn verify(&self, pkg: PackageId) -> CargoResult<()> {
+        self.inner.verify(pkg)
+    }
+
+    fn describe(&self) -> String {
+        self.inner.describe()
+    }
+
+    fn is_replaced(&self) -> bool {
+        self.inner.is_replaced()
+    }
+
+    fn add_to_yanked_whitelist(&mut self, pkgs: &[PackageId]) {
+        self.inner.add_to_yanked_whitelist(pkgs)
+    }
+
+    fn is_yanked(&mut self, pkg: PackageId) -> Poll<CargoResult<bool>> {
+        self.inner.is_yanked(pkg)
+    }
+
+    fn block_until_ready(&mut self) -> CargoResult<()> {
+        self.inner.block_until_ready()
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{lock::CacheLock, not_found, query_blocking, Package, PackageFetcher, PackageSource, ResolveError};
+use cargo::core::{Dependency, PackageId, Source};
+use std::{cell::RefCell, collections::HashSet, fmt};
+
+/// Registry loaded once to resolve many packages from it, returned by [`PackageFetcher::open_registry`].
+///
+/// Every resolution of [`PackageFetcher`] loads the source again, this reuses the loaded source instead, along with
+/// what cargo keeps of it in memory, e.g. index files it already parsed. The index is refreshed at most once, when
+/// it's opened, according to [`PackageFetcherBuilder::index_freshness`](crate::PackageFetcherBuilder::index_freshness).
+///
+/// It borrows the fetcher it was opened with, so methods of the fetcher that need `&mut self` can't be called, until
+/// it's dropped. Same as [`PackageFetcher`], it can't be shared between or sent to other threads. The package cache
+/// is locked for each resolution, not for the whole lifetime of the registry.
+pub struct Registry<'a> {
+    fetcher: &'a PackageFetcher,
+    source: PackageSource,
+    loaded: RefCell<Box<dyn Source + 'a>>,
+}
+
+impl PackageFetcher {
+    /// Opens a registry to resolve many packages from it, loading it once.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{PackageFetcher, PackageSource};
+    /// let fetcher = PackageFetcher::new()?;
+    /// let registry = fetcher.open_registry(&PackageSource::crates_io())?;
+    ///
+    /// let serde = registry.resolve("serde", None)?;
+    /// let rand = registry.resolve("rand", Some("0.8"))?;
+    /// # Ok::<(), String>(())
+    /// ```
+    ///
+    /// Errors, if `source` is not a registry.
+    pub fn open_registry(&self, source: &PackageSource) -> Result<Registry<'_>, String> {
+        let _lock = self.lock_package_cache()?;
+        let source_id = self.source_id(source).map_err(|e| e.to_string())?;
+        if !source_id.is_registry() {
+            return Err(format!("{source_id} is not a registry"));
+        }
+
+        let mut loaded = self.load_source(source_id, &HashSet::new())?;
+        loaded.block_until_ready().map_err(|e| e.to_string())?;
+        Ok(Registry {
+            fetcher: self,
+            source: source.clone(),
+            loaded: RefCell::new(loaded),
+        })
+    }
+}
+
+impl Registry<'_> {
+    /// Returns the source the registry was opened with.
+    pub fn source(&self) -> &PackageSource {
+        &self.source
+    }
+
+    /// Resolves the newest package version matching the version requirement, same as
+    /// [`PackageFetcher::resolve_first`].
+    pub fn resolve<N: AsRef<str>>(&self, name: N, version: Option<&str>) -> Result<Package, ResolveError> {
+        let name = name.as_ref();
+        let mut newest: Option<PackageId> = None;
+        self.query(name, version, &mut |package_id| {
+            if newest.map_or(true, |newest| self.fetcher.is_newer(package_id.version(), newest.version())) {
+                newest = Some(package_id);
+            }
+        })?;
+
+        match newest {
+            Some(package_id) => self.fetcher.check_minimum(Package { package_id }),
+            None => {
+                let _lock = self.lock()?;
+                Err(not_found(&mut **self.loaded.borrow_mut(), name, version))
+            },
+        }
+    }
+
+    /// Resolves all package versions matching the version requirement, same as [`PackageFetcher::resolve_package`].
+    pub fn resolve_all<N: AsRef<str>>(&self, name: N, version: Option<&str>) -> Result<Vec<Package>, String> {
+        let mut packages = vec![];
+        self.query(name.as_ref(), version, &mut |package_id| {
+            packages.push(Package { package_id })
+        })?;
+        Ok(packages)
+    }
+
+    /// Acquires the package cache lock, making the token of [`PackageSource::RegistryWithAuth`] available again,
+    /// as it's only kept while the lock is held.
+    fn lock(&self) -> Result<CacheLock<'_>, String> {
+        let lock = self.fetcher.lock_package_cache()?;
+        self.fetcher.source_id(&self.source).map_err(|e| e.to_string())?;
+        Ok(lock)
+    }
+
+    fn query(&self, name: &str, version: Option<&str>, f: &mut dyn FnMut(PackageId)) -> Result<(), String> {
+        let _lock = self.lock()?;
+        let mut loaded = self.loaded.borrow_mut();
+        let dep = Dependency::parse(name, version, loaded.source_id()).map_err(|e| e.to_string())?;
+        query_blocking(&mut **loaded, &dep, &mut |sum| f(sum.package_id())).map_err(|e| e.to_string())
+    }
+}
+
+impl fmt::Debug for Registry<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Registry")
+            .field("source", &self.source)
+            .finish_non_exhaustive()
+    }
+}
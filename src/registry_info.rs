@@ -0,0 +1,128 @@
+use crate::PackageFetcher;
+use cargo::{
+    core::SourceId,
+    sources::CRATES_IO_REGISTRY,
+    util::{
+        config::{ConfigRelativePath, ConfigValue},
+        IntoUrl,
+    },
+    CargoResult,
+};
+use url::Url;
+
+/// Registry or source configured in the cargo config, returned by [`PackageFetcher::configured_registries`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RegistryInfo {
+    /// Name of the registry or the source, `crates-io` for crates.io.
+    pub name: String,
+    /// Whether it's defined in the `source` table, so other sources can be replaced with it, otherwise it's defined
+    /// in the `registries` table. Crates.io is a source, even if it isn't configured.
+    pub source: bool,
+    /// Url of the index, or of the directory or the repository of `directory`, `local-registry` and `git` sources.
+    ///
+    /// [`None`] for sources defined only to be replaced, without the location of their own.
+    pub url: Option<Url>,
+    /// Name of the source or the registry it's replaced with, set with `replace-with`.
+    pub replace_with: Option<String>,
+}
+
+impl PackageFetcher {
+    /// Lists crates.io, and the registries and sources configured in the `registries` and `source` tables of the
+    /// cargo config, without accessing the network.
+    ///
+    /// Crates.io is listed first, followed by the rest sorted by their names, with registries before sources of the
+    /// same name. To find out where packages of a [`PackageSource`](crate::PackageSource) are actually fetched from,
+    /// following the replacements, see [`Self::effective_source_url`].
+    ///
+    /// ```
+    /// # use cargo_fetch::{PackageFetcherBuilder, RegistryInfo};
+    /// let fetcher = PackageFetcherBuilder::new()
+    ///     .config_override("registries.internal.index = 'https://git.example.com/index'")
+    ///     .config_override("source.crates-io.replace-with = 'mirror'")
+    ///     .config_override("source.mirror.registry = 'https://mirror.example.com/index'")
+    ///     .build()?;
+    /// let registries = fetcher.configured_registries()?;
+    ///
+    /// assert_eq!(registries[0].name, "crates-io");
+    /// assert_eq!(registries[0].replace_with.as_deref(), Some("mirror"));
+    /// assert!(registries.contains(&RegistryInfo {
+    ///     name: "internal".into(),
+    ///     source: false,
+    ///     url: Some("https://git.example.com/index".parse().unwrap()),
+    ///     replace_with: None,
+    /// }));
+    /// assert!(registries.contains(&RegistryInfo {
+    ///     name: "mirror".into(),
+    ///     source: true,
+    ///     url: Some("https://mirror.example.com/index".parse().unwrap()),
+    ///     replace_with: None,
+    /// }));
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn configured_registries(&self) -> Result<Vec<RegistryInfo>, String> {
+        self.read_configured_registries().map_err(|e| e.to_string())
+    }
+
+    fn read_configured_registries(&self) -> CargoResult<Vec<RegistryInfo>> {
+        let config = &self.config;
+        let names = |table: &str| -> CargoResult<Vec<String>> {
+            Ok(match config.values()?.get(table) {
+                Some(ConfigValue::Table(table, _)) => table.keys().cloned().collect(),
+                _ => vec![],
+            })
+        };
+
+        let crates_io = SourceId::crates_io_maybe_sparse_http(config)?.url().clone();
+        let mut registries = vec![];
+        for name in names("registries")? {
+            // Cargo allows configuring crates.io in `registries`, e.g. its token, but not its index.
+            if name == CRATES_IO_REGISTRY {
+                continue;
+            }
+            let url = config.get_registry_index(&name)?;
+            registries.push(RegistryInfo {
+                name,
+                source: false,
+                url: Some(url),
+                replace_with: None,
+            });
+        }
+
+        for name in names("source")? {
+            let key = |key: &str| format!("source.{name}.{key}");
+            let url = if let Some(registry) = config.get::<Option<String>>(&key("registry"))? {
+                Some(registry.into_url()?)
+            } else if let Some(git) = config.get::<Option<String>>(&key("git"))? {
+                Some(git.into_url()?)
+            } else {
+                let path = match config.get::<Option<ConfigRelativePath>>(&key("local-registry"))? {
+                    Some(path) => Some(path),
+                    None => config.get::<Option<ConfigRelativePath>>(&key("directory"))?,
+                };
+                path.map(|path| path.resolve_path(config).into_url()).transpose()?
+            };
+            registries.push(RegistryInfo {
+                // Crates.io doesn't need to be defined to be replaced.
+                url: url.or_else(|| (name == CRATES_IO_REGISTRY).then(|| crates_io.clone())),
+                replace_with: config.get::<Option<String>>(&key("replace-with"))?,
+                name,
+                source: true,
+            });
+        }
+
+        if !registries.iter().any(|info| info.name == CRATES_IO_REGISTRY) {
+            registries.push(RegistryInfo {
+                name: CRATES_IO_REGISTRY.to_owned(),
+                source: true,
+                url: Some(crates_io),
+                replace_with: None,
+            });
+        }
+        registries.sort_unstable_by(|a, b| {
+            let key = |info: &RegistryInfo| (info.name != CRATES_IO_REGISTRY, info.name.clone(), info.source);
+            key(a).cmp(&key(b))
+        });
+        Ok(registries)
+    }
+}
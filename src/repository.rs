@@ -0,0 +1,61 @@
+use crate::{Package, PackageFetcher};
+use cargo::util::CanonicalUrl;
+use url::Url;
+
+/// Repository declared by a package, compared to its source, returned by [`PackageFetcher::check_repository`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RepositoryMatch {
+    /// `repository` declared in the manifest of the package, as it's written.
+    pub repository: Option<String>,
+    /// Whether the package is from a git source of the declared repository.
+    pub matches: bool,
+}
+
+impl PackageFetcher {
+    /// Fetches a package, and compares the `repository` declared in its manifest to the git repository it's fetched
+    /// from, as a heuristic for detecting packages impersonating others.
+    ///
+    /// Urls are compared the same way cargo compares git sources, ignoring the scheme, a trailing slash, and
+    /// the `.git` suffix, along with the case of GitHub urls. Declared urls pointing into the repository, e.g.
+    /// `https://github.com/rust-lang/cargo/tree/master/crates/cargo-util`, match it too. Packages not from
+    /// git sources never match, as the repository they were published from isn't known.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Package, PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let source = PackageSource::git("https://github.com/serde-rs/serde", None).unwrap();
+    /// let serde = Package::from_str_ver("serde", "1.0.0", &source)?;
+    ///
+    /// let repository = fetcher.check_repository(serde)?;
+    /// if !repository.matches {
+    ///     println!("`serde` declares {:?}", repository.repository);
+    /// }
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn check_repository(&mut self, package: Package) -> Result<RepositoryMatch, String> {
+        let source_id = package.package_id.source_id();
+        let fetched = self.fetch_package(package)?;
+        let repository = fetched.manifest().metadata().repository.clone();
+
+        let matches = source_id.is_git()
+            && repository
+                .as_deref()
+                .and_then(|repository| Url::parse(repository).ok())
+                .is_some_and(|declared| same_repository(&declared, source_id.url()));
+        Ok(RepositoryMatch { repository, matches })
+    }
+}
+
+/// Whether the `declared` url is the `source` repository, or points into it.
+fn same_repository(declared: &Url, source: &Url) -> bool {
+    let location = |url: &Url| {
+        let url = CanonicalUrl::new(url).ok()?;
+        let url = url.raw_canonicalized_url();
+        Some(format!("{}{}", url.host_str().unwrap_or_default(), url.path()))
+    };
+    let (Some(declared), Some(source)) = (location(declared), location(source)) else {
+        return false;
+    };
+    declared == source || declared.starts_with(&format!("{}/", source.trim_end_matches('/')))
+}
@@ -0,0 +1,63 @@
+use crate::PackageFetcher;
+use cargo::{core::SourceId, CargoResult};
+use std::fmt;
+use url::Url;
+
+/// Function rewriting urls of sources, set with [`PackageFetcher::set_url_rewriter`].
+pub(crate) struct UrlRewriter(Box<dyn Fn(&Url) -> Url>);
+
+impl fmt::Debug for UrlRewriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("UrlRewriter")
+    }
+}
+
+impl PackageFetcher {
+    /// Sets a function rewriting urls of git and remote registry sources, including crates.io, e.g. to redirect
+    /// them to a mirror or to normalize them.
+    ///
+    /// It's applied whenever a [`PackageSource`](crate::PackageSource) is given to the fetcher, so packages resolved
+    /// from it are from the source with the rewritten url. Urls of sparse registries are passed with their `sparse+`
+    /// prefix, which decides the protocol of the rewritten url too. Path sources and local registries aren't
+    /// rewritten, and neither are packages constructed with [`Package::from_str_ver`](crate::Package::from_str_ver),
+    /// nor dependencies [`PackageFetcher::resolve_graph`] finds in manifests.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// fetcher.set_url_rewriter(|url| {
+    ///     let mut url = url.clone();
+    ///     if url.host_str() == Some("github.com") {
+    ///         url.set_host(Some("git.example.com")).unwrap();
+    ///     }
+    ///     url
+    /// });
+    ///
+    /// let source = PackageSource::git("https://github.com/serde-rs/serde", None).unwrap();
+    /// let url = fetcher.effective_source_url(&source)?;
+    /// assert_eq!(url.as_str(), "https://git.example.com/serde-rs/serde");
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn set_url_rewriter<F: Fn(&Url) -> Url + 'static>(&mut self, rewriter: F) {
+        self.url_rewriter = Some(UrlRewriter(Box::new(rewriter)));
+    }
+
+    /// Applies the url rewriter to `source_id`, if it's a git or a remote registry source.
+    pub(crate) fn rewrite_source_id(&self, source_id: SourceId) -> CargoResult<SourceId> {
+        let rewriter = match &self.url_rewriter {
+            Some(rewriter) if source_id.is_git() || source_id.is_remote_registry() => rewriter,
+            _ => return Ok(source_id),
+        };
+        let url = (rewriter.0)(source_id.url());
+        if url == *source_id.url() {
+            return Ok(source_id);
+        }
+
+        match source_id.git_reference() {
+            Some(reference) => {
+                Ok(SourceId::for_git(&url, reference.clone())?.with_precise(source_id.precise().map(str::to_owned)))
+            },
+            None => SourceId::for_registry(&url),
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use crate::{Features, Package, PackageFetcher, ResolveNode};
+use serde_json::{json, Value};
+
+/// Format of a software bill of materials, produced by [`PackageFetcher::sbom`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SbomFormat {
+    /// [CycloneDX](https://cyclonedx.org) 1.4, JSON encoded.
+    CycloneDxJson,
+}
+
+impl PackageFetcher {
+    /// Resolves the dependency graph of `root` and describes every resolved package in a software bill of
+    /// materials of the given `format`.
+    ///
+    /// Each package is listed with its name, version, source and, for registry packages, checksum of its archive.
+    /// Output is deterministic for the same resolved graph.
+    ///
+    /// See [`PackageFetcher::resolve_graph`] for details on the resolution.
+    pub fn sbom(&mut self, root: Package, features: &Features, format: SbomFormat) -> Result<String, String> {
+        let graph = self.resolve_graph(root, features)?;
+
+        match format {
+            SbomFormat::CycloneDxJson => {
+                let components: Vec<Value> = graph
+                    .nodes()
+                    .iter()
+                    .filter(|node| node.package != root)
+                    .map(cyclonedx_component)
+                    .collect();
+                let dependencies: Vec<Value> = graph
+                    .nodes()
+                    .iter()
+                    .map(|node| {
+                        let mut depends_on: Vec<String> =
+                            node.dependencies.iter().map(|edge| bom_ref(&edge.package)).collect();
+                        depends_on.dedup();
+                        json!({
+                            "ref": bom_ref(&node.package),
+                            "dependsOn": depends_on,
+                        })
+                    })
+                    .collect();
+                let root = graph.node(&root).ok_or("root package is missing from the resolved graph")?;
+
+                let bom = json!({
+                    "bomFormat": "CycloneDX",
+                    "specVersion": "1.4",
+                    "version": 1,
+                    "metadata": {
+                        "component": cyclonedx_component(root),
+                    },
+                    "components": components,
+                    "dependencies": dependencies,
+                });
+                serde_json::to_string_pretty(&bom).map_err(|e| e.to_string())
+            },
+        }
+    }
+}
+
+fn cyclonedx_component(node: &ResolveNode) -> Value {
+    let package_id = node.package.package_id;
+    let source_id = package_id.source_id();
+
+    let mut component = json!({
+        "type": "library",
+        "bom-ref": bom_ref(&node.package),
+        "name": package_id.name().as_str(),
+        "version": package_id.version().to_string(),
+        "properties": [{ "name": "cargo:source", "value": source_id.as_url().to_string() }],
+    });
+
+    // https://github.com/package-url/purl-spec/blob/master/PURL-TYPES.rst#cargo
+    let purl = format!(
+        "pkg:cargo/{}@{}",
+        package_id.name(),
+        package_id.version().to_string().replace('+', "%2B")
+    );
+    if source_id.is_crates_io() {
+        component["purl"] = purl.into();
+    } else if source_id.is_registry() {
+        component["purl"] = format!("{purl}?repository_url={}", source_id.url()).into();
+    } else if source_id.is_git() {
+        component["purl"] = format!("{purl}?vcs_url=git%2B{}", source_id.url()).into();
+    }
+
+    if let Some(checksum) = &node.checksum {
+        component["hashes"] = json!([{ "alg": "SHA-256", "content": checksum }]);
+    }
+
+    component
+}
+
+/// Reference of a package unique within the bill of materials.
+fn bom_ref(package: &Package) -> String {
+    let package_id = package.package_id;
+    format!("{} {} ({})", package_id.name(), package_id.version(), package_id.source_id().as_url())
+}
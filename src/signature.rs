@@ -0,0 +1,277 @@
+use crate::{cache::git_ident, Package, PackageFetcher};
+use cargo::core::GitReference;
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Git package fetched at a verified tag, returned by [`PackageFetcher::fetch_verified_tag`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedTag {
+    /// Root of the fetched package.
+    pub root: PathBuf,
+    /// Full hash of the commit the tag points to.
+    pub commit: String,
+    /// Fingerprint of the primary key of the trusted key that signed the tag, or the commit, if the tag isn't signed,
+    /// and a signature isn't required.
+    ///
+    /// [`None`], if neither is signed, and a signature isn't required.
+    pub signer: Option<String>,
+}
+
+impl PackageFetcher {
+    /// Fetches a package from a git source pinned to a tag, same as [`Self::fetch_git_detailed`], and verifies the
+    /// OpenPGP signature of the tag against `trusted_keys`.
+    ///
+    /// With `require_signature`, the tag itself has to be an annotated tag signed by one of the trusted keys, same as
+    /// `git verify-tag` checks it, and its name has to be the name of the tag it's fetched as, so a signed tag can't be
+    /// republished under another name, or replaced with a lightweight tag pointing to another signed commit.
+    /// Without it, the signature of the commit the tag points to is verified instead, same as `git verify-commit`
+    /// does, if the tag is lightweight or isn't signed, and nothing is verified, if neither is signed. Signatures
+    /// that are there are always verified, so it errors, if one isn't made by a trusted key, or is invalid.
+    ///
+    /// Trusted keys are ASCII-armored OpenPGP public keys, as exported with `gpg --armor --export`, each of them can
+    /// contain multiple keys. Signatures are verified with the `gpg` executable from `PATH`, which has to be GnuPG
+    /// 2.1 or newer, in a temporary home directory in the cargo home with only the trusted keys, which is removed
+    /// afterwards, so the keyring and the trust settings of the user are never used. Only OpenPGP signatures are
+    /// supported, SSH and X.509 signatures can't be verified.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{GitReference, Package, PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let tag = Some(GitReference::Tag("v1.0.0".into()));
+    /// let source = PackageSource::git("https://github.com/serde-rs/serde", tag).unwrap();
+    /// let serde = Package::from_str_ver("serde", "1.0.0", &source)?;
+    ///
+    /// let key = std::fs::read_to_string("maintainer.asc").unwrap();
+    /// let verified = fetcher.fetch_verified_tag(serde, &[key], true)?;
+    /// println!("{} signed by {:?}", verified.commit, verified.signer);
+    /// # Ok::<(), String>(())
+    /// ```
+    ///
+    /// Errors, if the package isn't from a git source pinned to a tag.
+    pub fn fetch_verified_tag(
+        &mut self,
+        package: Package,
+        trusted_keys: &[String],
+        require_signature: bool,
+    ) -> Result<VerifiedTag, String> {
+        let source_id = package.package_id.source_id();
+        let tag = match source_id.git_reference() {
+            Some(GitReference::Tag(tag)) => tag.clone(),
+            _ => {
+                return Err(format!(
+                    "`{}` is not from a git source pinned to a tag",
+                    package.package_id
+                ))
+            },
+        };
+        let fetched = self.fetch_git_detailed(package)?;
+
+        let _lock = self.lock_package_cache()?;
+        let db = self.config.git_path().join("db").join(git_ident(source_id));
+        let db = self.config.assert_package_cache_locked(&db);
+        let repo = git2::Repository::open(db).map_err(|e| format!("can't open the git database of `{tag}`: {e}"))?;
+        // Cargo fetches tags into `refs/remotes/origin/tags`.
+        let tag_id = repo
+            .refname_to_id(&format!("refs/remotes/origin/tags/{tag}"))
+            .map_err(|e| format!("can't find tag `{tag}` in the git database: {e}"))?;
+        let commit = repo
+            .find_object(tag_id, None)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|e| e.to_string())?
+            .id();
+        if commit.to_string() != fetched.commit {
+            return Err(format!(
+                "tag `{tag}` points to {commit}, but the package was fetched from {}",
+                fetched.commit
+            ));
+        }
+
+        let signed = match tag_signature(&repo, tag_id, &tag)? {
+            Some(signed) => Some(signed),
+            None if require_signature => return Err(format!("tag `{tag}` isn't a signed annotated tag")),
+            None => match repo.extract_signature(&commit, None) {
+                Ok((signature, payload)) => Some((payload.to_vec(), signature.to_vec())),
+                Err(e) if e.code() == git2::ErrorCode::NotFound => None,
+                Err(e) => return Err(e.to_string()),
+            },
+        };
+        let signer = match signed {
+            Some((payload, signature)) => {
+                let home = self.config.assert_package_cache_locked(self.config.home());
+                let signer = gpg_verify(home, trusted_keys, &payload, &signature)
+                    .map_err(|e| format!("can't verify the signature of tag `{tag}`: {e}"))?;
+                Some(signer)
+            },
+            None => None,
+        };
+
+        Ok(VerifiedTag {
+            root: fetched.root,
+            commit: fetched.commit,
+            signer,
+        })
+    }
+}
+
+/// Signed payload, and its signature.
+type Signed = (Vec<u8>, Vec<u8>);
+
+/// Splits an annotated tag into the signed payload and its signature, same as git does, or returns [`None`], if
+/// the tag is lightweight or isn't signed.
+///
+/// Errors, if the tag is annotated, but its name isn't `name`.
+fn tag_signature(repo: &git2::Repository, tag_id: git2::Oid, name: &str) -> Result<Option<Signed>, String> {
+    let odb = repo.odb().map_err(|e| e.to_string())?;
+    let object = odb.read(tag_id).map_err(|e| e.to_string())?;
+    if object.kind() != git2::ObjectType::Tag {
+        return Ok(None);
+    }
+    let data = object.data();
+    // The name in the header is signed, unlike the name of the reference.
+    let header = &data[..find(data, b"\n\n").unwrap_or(data.len())];
+    let tagged = header
+        .split(|byte| *byte == b'\n')
+        .find_map(|line| line.strip_prefix(b"tag "));
+    if tagged != Some(name.as_bytes()) {
+        let tagged = String::from_utf8_lossy(tagged.unwrap_or_default());
+        return Err(format!("tag `{name}` is an annotated tag named `{tagged}`"));
+    }
+
+    let start = [
+        "-----BEGIN PGP SIGNATURE-----",
+        "-----BEGIN SSH SIGNATURE-----",
+        "-----BEGIN SIGNED MESSAGE-----",
+    ]
+    .iter()
+    .filter_map(|marker| find(data, format!("\n{marker}").as_bytes()))
+    .min();
+    Ok(start.map(|start| (data[..=start].to_vec(), data[start + 1..].to_vec())))
+}
+
+/// Finds the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+/// Verifies the OpenPGP `signature` of `payload` with `gpg`, trusting only `trusted_keys`, and returns the fingerprint
+/// of the primary key that made it. Home directory of gpg is created in `parent`.
+fn gpg_verify(parent: &Path, trusted_keys: &[String], payload: &[u8], signature: &[u8]) -> Result<String, String> {
+    let home = GpgHome::new(parent).map_err(|e| format!("can't create a home directory for gpg: {e}"))?;
+    for key in trusted_keys {
+        let mut import = home
+            .gpg()
+            .arg("--import")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("can't run gpg: {e}"))?;
+        import
+            .stdin
+            .take()
+            .expect("stdin of gpg is piped")
+            .write_all(key.as_bytes())
+            .map_err(|e| e.to_string())?;
+        let output = import.wait_with_output().map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(format!(
+                "can't import a trusted key: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+    }
+
+    let payload_path = home.path().join("payload");
+    let signature_path = home.path().join("signature");
+    fs::write(&payload_path, payload).map_err(|e| e.to_string())?;
+    fs::write(&signature_path, signature).map_err(|e| e.to_string())?;
+    let output = home
+        .gpg()
+        .arg("--status-fd=1")
+        .arg("--verify")
+        .arg(&signature_path)
+        .arg(&payload_path)
+        .output()
+        .map_err(|e| format!("can't run gpg: {e}"))?;
+
+    // See `doc/DETAILS` of GnuPG for the status lines.
+    let status = String::from_utf8_lossy(&output.stdout);
+    let mut fingerprint = None;
+    for line in status.lines() {
+        let mut fields = line.split_whitespace().skip_while(|field| *field == "[GNUPG:]");
+        match fields.next() {
+            Some("VALIDSIG") => {
+                let fields: Vec<&str> = fields.collect();
+                // Primary key fingerprint is the last field, if the signature is made by a subkey.
+                fingerprint = fields
+                    .get(9)
+                    .or(fields.first())
+                    .map(|fingerprint| fingerprint.to_string());
+            },
+            Some(bad @ ("BADSIG" | "ERRSIG" | "EXPSIG" | "EXPKEYSIG" | "REVKEYSIG")) => {
+                let reason = match bad {
+                    "BADSIG" => "the signature is invalid",
+                    "ERRSIG" => "the signature isn't made by a trusted key, or can't be checked",
+                    "EXPSIG" => "the signature is expired",
+                    "EXPKEYSIG" => "the key that made the signature is expired",
+                    _ => "the key that made the signature is revoked",
+                };
+                return Err(reason.into());
+            },
+            _ => {},
+        }
+    }
+    match fingerprint {
+        Some(fingerprint) if output.status.success() => Ok(fingerprint),
+        _ => Err(format!(
+            "gpg didn't verify the signature: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+    }
+}
+
+/// Temporary home directory of gpg, removed when dropped.
+struct GpgHome(PathBuf);
+
+impl GpgHome {
+    fn new(parent: &Path) -> std::io::Result<Self> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let dir = parent.join(format!(
+            ".gpg-tmp-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut builder = fs::DirBuilder::new();
+        // gpg warns about home directories other users can access.
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder.create(&dir)?;
+        Ok(Self(dir))
+    }
+
+    /// Command running gpg non-interactively with this home directory, without starting the agent, which is only
+    /// needed for secret keys.
+    fn gpg(&self) -> Command {
+        let mut command = Command::new("gpg");
+        command
+            .arg("--homedir")
+            .arg(self.path())
+            .args(["--batch", "--no-tty", "--quiet", "--no-autostart"]);
+        command
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for GpgHome {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(self.path());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{git_cache::is_full_hash, registry::short_name, PackageFetcher};
+use cargo::{
+    core::{GitReference, SourceId},
+    sources::CRATES_IO_INDEX,
+    util::{config::ConfigValue, CanonicalUrl},
+    CargoResult, Config,
+};
+use std::{fs, path::PathBuf};
+use url::Url;
+
+/// Registry index pinned to a commit, see
+/// [`PackageFetcherBuilder::registry_index_revision`](crate::PackageFetcherBuilder::registry_index_revision).
+#[derive(Debug)]
+pub(crate) struct IndexSnapshot {
+    index: Url,
+    commit: String,
+    /// Repository with `HEAD` detached at `commit`, cargo clones the index from.
+    path: PathBuf,
+}
+
+impl IndexSnapshot {
+    pub(crate) fn new(config: &Config, index: Url, commit: String) -> Result<Self, String> {
+        if index.scheme().starts_with("sparse+") {
+            return Err(format!("sparse registry index `{index}` can't be pinned to a revision"));
+        }
+        if !is_full_hash(&commit) {
+            return Err(format!(
+                "revision of registry index `{index}` must be a full commit hash, got `{commit}`"
+            ));
+        }
+
+        let source_id = SourceId::for_registry(&index).map_err(|e| e.to_string())?;
+        let path = config
+            .home()
+            .as_path_unlocked()
+            .join("registry")
+            .join("index-snapshots")
+            .join(short_name(source_id))
+            .join(&commit);
+        Ok(Self { index, commit, path })
+    }
+
+    /// Cargo config entries replacing the index with the snapshot.
+    ///
+    /// `n` makes names of the sources defined for different snapshots unique.
+    pub(crate) fn replacement(&self, config: &Config, n: usize) -> Result<Vec<String>, String> {
+        let snapshot = Url::from_directory_path(&self.path)
+            .map_err(|_| format!("`{}` can't be used as a registry index url", self.path.display()))?;
+        let name = format!("cargo-fetch-snapshot-{n}");
+        let mut entries = vec![format!("source.{name}.registry={:?}", snapshot.as_str())];
+
+        // Cargo doesn't allow defining the same source twice, so the existing definition is replaced, if there is one.
+        let upstream = match upstream_name(config, &self.index).map_err(|e| e.to_string())? {
+            Some(upstream) => upstream,
+            None => {
+                let upstream = format!("cargo-fetch-upstream-{n}");
+                entries.push(format!("source.{upstream}.registry={:?}", self.index.as_str()));
+                upstream
+            },
+        };
+        entries.push(format!("source.{upstream:?}.replace-with={name:?}"));
+        Ok(entries)
+    }
+}
+
+impl PackageFetcher {
+    /// Fetches the pinned commit of the index into the snapshot, if it's not there yet.
+    pub(crate) fn prepare_snapshot(&self, snapshot: &IndexSnapshot) -> Result<(), String> {
+        if snapshot.path.exists() {
+            return Ok(());
+        }
+        self.config
+            .shell()
+            .status(
+                "Updating",
+                format!("`{}` index at `{}`", snapshot.index, snapshot.commit),
+            )
+            .map_err(|e| e.to_string())?;
+        self.fetch_snapshot(snapshot).map_err(|e| {
+            format!(
+                "failed to pin registry index `{}` to `{}`: {e}",
+                snapshot.index, snapshot.commit
+            )
+        })
+    }
+
+    fn fetch_snapshot(&self, snapshot: &IndexSnapshot) -> CargoResult<()> {
+        // Snapshot is renamed into place only once complete, so other processes never see a partial one.
+        let parent = snapshot.path.parent().expect("snapshot path has a parent");
+        let tmp = parent.join(format!(".{}-{}", snapshot.commit, std::process::id()));
+        if tmp.exists() {
+            fs::remove_dir_all(&tmp)?;
+        }
+        fs::create_dir_all(parent)?;
+
+        let mut repo = git2::Repository::init_bare(&tmp)?;
+        cargo::sources::git::fetch(
+            &mut repo,
+            snapshot.index.as_str(),
+            &GitReference::Rev(snapshot.commit.clone()),
+            &self.config,
+        )?;
+        let commit = repo.find_commit(git2::Oid::from_str(&snapshot.commit)?)?;
+        // Cargo fetches `HEAD` of the registry index.
+        repo.set_head_detached(commit.id())?;
+        drop(commit);
+        drop(repo);
+
+        if fs::rename(&tmp, &snapshot.path).is_err() {
+            // Another process stored the same snapshot first.
+            fs::remove_dir_all(&tmp)?;
+            if !snapshot.path.exists() {
+                anyhow::bail!("failed to store the snapshot in `{}`", snapshot.path.display());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Finds the name of the source the cargo config defines for the registry `index`.
+fn upstream_name(config: &Config, index: &Url) -> CargoResult<Option<String>> {
+    let index = CanonicalUrl::new(index)?;
+    if index == CanonicalUrl::new(&Url::parse(CRATES_IO_INDEX)?)? {
+        return Ok(Some("crates-io".into()));
+    }
+
+    let sources = match config.values()?.get("source") {
+        Some(ConfigValue::Table(sources, _)) => sources,
+        _ => return Ok(None),
+    };
+    for (name, source) in sources {
+        let registry = match source {
+            ConfigValue::Table(source, _) => match source.get("registry") {
+                Some(ConfigValue::String(registry, _)) => registry,
+                _ => continue,
+            },
+            _ => continue,
+        };
+        if Url::parse(registry).is_ok_and(|url| CanonicalUrl::new(&url).ok() == Some(index.clone())) {
+            return Ok(Some(name.clone()));
+        }
+    }
+    Ok(None)
+}
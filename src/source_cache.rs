@@ -0,0 +1,125 @@
+use crate::PackageFetcher;
+use cargo::{
+    core::{source::MaybePackage, Dependency, PackageId, QueryKind, Source, SourceId, Summary},
+    CargoResult,
+};
+use std::{cell::RefCell, collections::HashMap, task::Poll};
+
+impl PackageFetcher {
+    /// Clears the commits git sources were locked to when they were first loaded by the fetcher, so the
+    /// repositories are updated again the next time they are used.
+    ///
+    /// Git sources are only locked with
+    /// [`PackageFetcherBuilder::pin_git_revisions`](crate::PackageFetcherBuilder::pin_git_revisions), see it for
+    /// details.
+    pub fn clear_source_cache(&self) {
+        self.git_revisions.borrow_mut().clear();
+    }
+
+    /// Locks a git source to the commit it was resolved to before, if it isn't locked already.
+    pub(crate) fn cached_revision(&self, id: SourceId) -> SourceId {
+        if !self.pin_git_revisions || !id.is_git() || id.precise().is_some() {
+            return id;
+        }
+        match self.git_revisions.borrow().get(&id) {
+            Some(commit) => id.with_precise(Some(commit.clone())),
+            None => id,
+        }
+    }
+}
+
+/// Source of a git repository that isn't locked to a commit, recording the commit it's resolved to, once
+/// it's known from the packages it returns.
+pub(crate) struct RevisionRecordingSource<'a> {
+    inner: Box<dyn Source + 'a>,
+    revisions: &'a RefCell<HashMap<SourceId, String>>,
+}
+
+impl<'a> RevisionRecordingSource<'a> {
+    pub(crate) fn new(inner: Box<dyn Source + 'a>, revisions: &'a RefCell<HashMap<SourceId, String>>) -> Self {
+        Self { inner, revisions }
+    }
+
+    fn record(&self, package_id: PackageId) {
+        if let Some(commit) = package_id.source_id().precise() {
+            self.revisions
+                .borrow_mut()
+                .entry(self.inner.source_id())
+                .or_insert_with(|| commit.to_owned());
+        }
+    }
+}
+
+impl Source for RevisionRecordingSource<'_> {
+    fn source_id(&self) -> SourceId {
+        self.inner.source_id()
+    }
+
+    fn replaced_source_id(&self) -> SourceId {
+        self.inner.replaced_source_id()
+    }
+
+    fn supports_checksums(&self) -> bool {
+        self.inner.supports_checksums()
+    }
+
+    fn requires_precise(&self) -> bool {
+        self.inner.requires_precise()
+    }
+
+    fn query(&mut self, dep: &Dependency, kind: QueryKind, f: &mut dyn FnMut(Summary)) -> Poll<CargoResult<()>> {
+        let mut recorded = None;
+        let res = self.inner.query(dep, kind, &mut |summary| {
+            recorded.get_or_insert(summary.package_id());
+            f(summary)
+        });
+        if let Some(package_id) = recorded {
+            self.record(package_id);
+        }
+        res
+    }
+
+    fn invalidate_cache(&mut self) {
+        self.inner.invalidate_cache()
+    }
+
+    fn download(&mut self, package: PackageId) -> CargoResult<MaybePackage> {
+        let downloaded = self.inner.download(package)?;
+        if let MaybePackage::Ready(package) = &downloaded {
+            self.record(package.package_id());
+        }
+        Ok(downloaded)
+    }
+
+    fn finish_download(&mut self, package: PackageId, contents: Vec<u8>) -> CargoResult<cargo::core::Package> {
+        self.inner.finish_download(package, contents)
+    }
+
+    fn fingerprint(&self, pkg: &cargo::core::Package) -> CargoResult<String> {
+        self.inner.fingerprint(pkg)
+    }
+
+    fn verify(&self, pkg: PackageId) -> CargoResult<()> {
+        self.inner.verify(pkg)
+    }
+
+    fn describe(&self) -> String {
+        self.inner.describe()
+    }
+
+    fn is_replaced(&self) -> bool {
+        self.inner.is_replaced()
+    }
+
+    fn add_to_yanked_whitelist(&mut self, pkgs: &[PackageId]) {
+        self.inner.add_to_yanked_whitelist(pkgs)
+    }
+
+    fn is_yanked(&mut self, pkg: PackageId) -> Poll<CargoResult<bool>> {
+        self.inner.is_yanked(pkg)
+    }
+
+    fn block_until_ready(&mut self) -> CargoResult<()> {
+        self.inner.block_until_ready()
+    }
+}
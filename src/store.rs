@@ -0,0 +1,100 @@
+use crate::{
+    bundle::{copy_package, package_files},
+    Package, PackageFetcher,
+};
+use cargo::CargoResult;
+use cargo_util::Sha256;
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+impl PackageFetcher {
+    /// Fetches a single package, and copies it into `store_dir/<hash>`, where `<hash>` is the sha256 hash of its
+    /// contents, making the copy read-only, and returns its path.
+    ///
+    /// The same files are copied as by [`PackageFetcher::bundle`]. The hash is computed from the paths of the files
+    /// relative to the package root, and the hashes of their contents, so the same package has the same path on
+    /// every machine, regardless of where it's fetched from, when, and the permissions of its files. If the path
+    /// already exists, it's returned without copying the package again, so the copies are never modified.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Package, PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let serde = Package::from_str_ver("serde", "1.0.0", &PackageSource::crates_io())?;
+    ///
+    /// let stored = fetcher.fetch_reproducible(serde, "store")?;
+    /// println!("{}", stored.display());
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn fetch_reproducible<P: AsRef<Path>>(&mut self, package: Package, store_dir: P) -> Result<PathBuf, String> {
+        let store_dir = store_dir.as_ref();
+        let pkg = self.fetch_package(package)?;
+        let hash = content_hash(&pkg, &self.config).map_err(|e| e.to_string())?;
+        let stored = store_dir.join(&hash);
+        if stored.exists() {
+            return Ok(stored);
+        }
+
+        // The package is copied next to its final path, so it's moved there at once, when it's complete.
+        let staging = store_dir.join(format!(".{hash}-{}", std::process::id()));
+        copy_package(&pkg, &staging, &self.config)
+            .and_then(|()| set_readonly(&staging, false))
+            .map_err(|e| format!("can't copy `{}` into `{}`: {e}", pkg.package_id(), store_dir.display()))?;
+        if let Err(e) = fs::rename(&staging, &stored) {
+            let _ = fs::remove_dir_all(&staging);
+            // Another process stored the same package in the meantime.
+            if !stored.exists() {
+                return Err(format!("can't move `{}`: {e}", staging.display()));
+            }
+            return Ok(stored);
+        }
+        // Directories are made read-only after moving them, so the copy can still be removed if moving fails.
+        set_readonly(&stored, true).map_err(|e| e.to_string())?;
+        Ok(stored)
+    }
+}
+
+/// Hashes the paths of the files of a package, with `/` separators, along with the hashes of their contents.
+fn content_hash(package: &cargo::core::Package, config: &cargo::Config) -> CargoResult<String> {
+    let mut hash = Sha256::new();
+    for relative in package_files(package, config)? {
+        let mut path = vec![];
+        for component in relative.components() {
+            match component {
+                Component::Normal(component) => path.push(
+                    component
+                        .to_str()
+                        .ok_or_else(|| anyhow::format_err!("path `{}` is not valid UTF-8", relative.display()))?,
+                ),
+                _ => anyhow::bail!("unexpected path `{}` in the package", relative.display()),
+            }
+        }
+        let contents = Sha256::new().update_path(package.root().join(&relative))?.finish_hex();
+        hash.update(path.join("/").as_bytes());
+        hash.update(&[0]);
+        hash.update(contents.as_bytes());
+        hash.update(&[0]);
+    }
+    Ok(hash.finish_hex())
+}
+
+/// Makes the files in `dir` read-only, along with the directories themselves, if `dirs` is set.
+fn set_readonly(dir: &Path, dirs: bool) -> CargoResult<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            set_readonly(&path, dirs)?;
+        } else {
+            let mut permissions = fs::metadata(&path)?.permissions();
+            permissions.set_readonly(true);
+            fs::set_permissions(&path, permissions)?;
+        }
+    }
+    if dirs {
+        let mut permissions = fs::metadata(dir)?.permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(dir, permissions)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use crate::{NameFilter, Package, PackageFetcher};
+use cargo::core::PackageId;
+use std::{
+    collections::{HashSet, VecDeque},
+    path::PathBuf,
+};
+
+/// Package yielded by [`PackageFetcher::fetch_stream_filtered`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamedPackage {
+    /// Package was fetched, along with its root.
+    Fetched(Package, PathBuf),
+    /// Package wasn't fetched, as its name didn't pass the filter.
+    Skipped(Package),
+}
+
+/// Number of packages [`PackageFetcher::fetch_stream`] downloads at once.
+const CHUNK_SIZE: usize = 64;
+
+impl PackageFetcher {
+    /// Fetches packages lazily, yielding each of them along with its root, as soon as its chunk is fetched.
+    ///
+    /// Unlike [`Self::fetch_many`], memory doesn't grow with the number of packages, so it's suitable for
+    /// mirroring large amounts of them. `packages` are consumed in chunks of 64, which are downloaded concurrently,
+    /// and the package cache is locked only while a chunk is being fetched. Results of a chunk are yielded in the
+    /// order the packages were fetched in, duplicate packages in a chunk are yielded once.
+    ///
+    /// Failure to fetch a package doesn't stop the stream. When a chunk fails, its remaining packages are fetched
+    /// one by one, so the error is yielded only for the packages that caused it.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Package, PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let packages = (100..200)
+    ///     .filter_map(|patch| Package::from_str_ver("serde", format!("1.0.{patch}"), &PackageSource::CratesIo).ok());
+    /// for fetched in fetcher.fetch_stream(packages) {
+    ///     match fetched {
+    ///         Ok((package, root)) => println!("{} is in {}", package.to_spec_string(), root.display()),
+    ///         Err(e) => eprintln!("{e}"),
+    ///     }
+    /// }
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn fetch_stream<'a, I>(
+        &'a mut self,
+        packages: I,
+    ) -> impl Iterator<Item = Result<(Package, PathBuf), String>> + 'a
+    where
+        I: IntoIterator<Item = Package>,
+        I::IntoIter: 'a,
+    {
+        let mut packages = packages.into_iter();
+        let mut fetched = VecDeque::new();
+        std::iter::from_fn(move || loop {
+            if let Some(result) = fetched.pop_front() {
+                return Some(result);
+            }
+            let chunk: Vec<Package> = packages.by_ref().take(CHUNK_SIZE).collect();
+            if chunk.is_empty() {
+                return None;
+            }
+            fetched.extend(self.fetch_chunk(&chunk));
+        })
+    }
+
+    /// Same as [`Self::fetch_stream`], but only fetches the packages with names passing `filter`, yielding the rest
+    /// as [`StreamedPackage::Skipped`], before the fetched packages of their chunk.
+    ///
+    /// Invalid patterns are yielded as an error, ending the stream.
+    pub fn fetch_stream_filtered<'a, I>(
+        &'a mut self,
+        packages: I,
+        filter: &NameFilter,
+    ) -> impl Iterator<Item = Result<StreamedPackage, String>> + 'a
+    where
+        I: IntoIterator<Item = Package>,
+        I::IntoIter: 'a,
+    {
+        let (matcher, mut error) = match filter.matcher() {
+            Ok(matcher) => (Some(matcher), None),
+            Err(e) => (None, Some(e)),
+        };
+        let mut packages = packages.into_iter();
+        let mut fetched = VecDeque::new();
+        std::iter::from_fn(move || loop {
+            if let Some(e) = error.take() {
+                return Some(Err(e));
+            }
+            if let Some(result) = fetched.pop_front() {
+                return Some(result);
+            }
+            let matcher = matcher.as_ref()?;
+            let (chunk, skipped): (Vec<Package>, Vec<Package>) = packages
+                .by_ref()
+                .take(CHUNK_SIZE)
+                .partition(|package| matcher.allows(package));
+            if chunk.is_empty() && skipped.is_empty() {
+                return None;
+            }
+            fetched.extend(skipped.into_iter().map(|package| Ok(StreamedPackage::Skipped(package))));
+            if !chunk.is_empty() {
+                let chunk = self.fetch_chunk(&chunk).into_iter();
+                fetched
+                    .extend(chunk.map(|result| result.map(|(package, root)| StreamedPackage::Fetched(package, root))));
+            }
+        })
+    }
+
+    /// Fetches `packages` concurrently, falling back to fetching them one by one, if that fails.
+    fn fetch_chunk(&self, packages: &[Package]) -> Vec<Result<(Package, PathBuf), String>> {
+        let mut fetched = vec![];
+        if self.download_chunk(packages, &mut fetched).is_ok() {
+            return fetched.into_iter().map(Ok).collect();
+        }
+
+        // Packages are compared without the commits git sources got locked to.
+        let done: HashSet<PackageId> = fetched.iter().map(|(package, _)| package.package_id).collect();
+        let mut remaining = vec![];
+        for package in packages {
+            if !done.contains(&package.package_id) && !remaining.contains(package) {
+                remaining.push(*package);
+            }
+        }
+
+        let retried = remaining.into_iter().map(|package| {
+            let package = self.fetch_from_source(package)?;
+            Ok((
+                Package {
+                    package_id: package.package_id(),
+                },
+                package.root().to_owned(),
+            ))
+        });
+        fetched.into_iter().map(Ok).chain(retried).collect()
+    }
+
+    /// Downloads `packages` concurrently, same as [`Self::fetch_many_with_progress`], pushing them to `fetched`
+    /// as they finish.
+    fn download_chunk(&self, packages: &[Package], fetched: &mut Vec<(Package, PathBuf)>) -> Result<(), String> {
+        let _lock = self.lock_package_cache()?;
+        let package_set = self.package_set(packages)?;
+        let package_ids: Vec<PackageId> = package_set.package_ids().collect();
+
+        let mut push = |package: &cargo::core::Package| {
+            fetched.push((
+                Package {
+                    package_id: package.package_id(),
+                },
+                package.root().to_owned(),
+            ))
+        };
+
+        let mut downloads = package_set.enable_download().map_err(|e| e.to_string())?;
+        for package_id in package_ids {
+            if let Some(package) = downloads.start(package_id).map_err(|e| e.to_string())? {
+                push(package);
+            }
+        }
+        while downloads.remaining() > 0 {
+            push(downloads.wait().map_err(|e| e.to_string())?);
+        }
+        Ok(())
+    }
+}
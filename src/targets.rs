@@ -0,0 +1,79 @@
+use crate::{Package, PackageFetcher};
+use std::path::{Path, PathBuf};
+
+/// Targets of a package, returned by [`PackageFetcher::target_kinds`].
+///
+/// Names of the targets are listed in the order they are declared in, or discovered by cargo.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TargetKinds {
+    /// Whether the package has a library target.
+    pub lib: bool,
+    /// Whether the library target of the package is a procedural macro.
+    pub proc_macro: bool,
+    /// Names of the binary targets, the package can be installed with `cargo install` if there are any.
+    pub bins: Vec<String>,
+    /// Names of the example targets.
+    pub examples: Vec<String>,
+    /// Names of the integration test targets.
+    pub tests: Vec<String>,
+    /// Names of the benchmark targets.
+    pub benches: Vec<String>,
+}
+
+impl PackageFetcher {
+    /// Fetches a package, and returns the kinds of targets it has.
+    ///
+    /// Targets are taken from the package manifest, including the ones cargo discovers automatically
+    /// (e.g. `src/main.rs` or files in `src/bin`), same as when building the package.
+    pub fn target_kinds(&mut self, package: Package) -> Result<TargetKinds, String> {
+        let package = self.fetch_package(package)?;
+
+        let mut kinds = TargetKinds::default();
+        for target in package.targets() {
+            let name = target.name().to_owned();
+            if target.is_lib() {
+                kinds.lib = true;
+                kinds.proc_macro = target.proc_macro();
+            } else if target.is_bin() {
+                kinds.bins.push(name);
+            } else if target.is_example() {
+                kinds.examples.push(name);
+            } else if target.is_test() {
+                kinds.tests.push(name);
+            } else if target.is_bench() {
+                kinds.benches.push(name);
+            }
+        }
+
+        Ok(kinds)
+    }
+
+    /// Fetches a package, and returns whether its library target is a procedural macro, declared with
+    /// `proc-macro = true` in the `[lib]` section of the manifest, same as [`TargetKinds::proc_macro`].
+    pub fn is_proc_macro(&mut self, package: Package) -> Result<bool, String> {
+        Ok(self.fetch_package(package)?.proc_macro())
+    }
+
+    /// Fetches a package, and returns the path to its build script, or [`None`], if it doesn't have one.
+    ///
+    /// The build script is found the same way cargo does, from the `build` key of the manifest, or
+    /// `build.rs` in the root of the package, unless `build = false` is set.
+    pub fn build_script(&mut self, package: Package) -> Result<Option<PathBuf>, String> {
+        let package = self.fetch_package(package)?;
+        Ok(package
+            .targets()
+            .iter()
+            .find(|target| target.is_custom_build())
+            .and_then(|target| target.src_path().path())
+            .map(Path::to_path_buf))
+    }
+
+    /// Fetches a package, and returns the binary target `cargo run` runs by default, set with `default-run` in the
+    /// manifest, or [`None`], if it's not set.
+    ///
+    /// Packages with a single binary target run it without `default-run`, see [`TargetKinds::bins`].
+    pub fn default_run(&mut self, package: Package) -> Result<Option<String>, String> {
+        Ok(self.fetch_package(package)?.manifest().default_run().map(str::to_owned))
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{DependencyKind, Features, Package, PackageFetcher, ResolveGraph};
+use std::{collections::HashSet, fmt::Write};
+
+/// Options of [`PackageFetcher::tree`], mirroring the flags of `cargo tree`.
+///
+/// Default value renders the same as `cargo tree` without flags.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeOptions {
+    /// Renders the packages depending on this package instead, with it as the root, same as `--invert`.
+    ///
+    /// Unlike `cargo tree`, a package built both for the target and for the host is rendered once, with the
+    /// packages depending on it in both.
+    pub invert: Option<Package>,
+    /// Maximum depth of the rendered dependencies, same as `--depth`, all of them are rendered if it's [`None`].
+    pub depth: Option<usize>,
+    /// Renders every occurrence of a package with its dependencies, same as `--no-dedupe`.
+    ///
+    /// Otherwise, dependencies of a package are only rendered the first time, and its later occurrences are
+    /// marked with `(*)`.
+    pub no_dedupe: bool,
+    /// Draws the tree with ASCII characters, same as `--charset ascii`.
+    pub ascii: bool,
+    /// Appends the features enabled on each package, separated by commas, same as `--format "{p} {f}"`.
+    pub features: bool,
+}
+
+/// Characters the tree is drawn with, same as cargo's.
+struct Symbols {
+    down: &'static str,
+    tee: &'static str,
+    ell: &'static str,
+    right: &'static str,
+}
+
+const UTF8_SYMBOLS: Symbols = Symbols {
+    down: "│",
+    tee: "├",
+    ell: "└",
+    right: "─",
+};
+
+const ASCII_SYMBOLS: Symbols = Symbols {
+    down: "|",
+    tee: "|",
+    ell: "`",
+    right: "-",
+};
+
+impl PackageFetcher {
+    /// Resolves the dependency graph of `root`, same as [`Self::resolve_graph`], and renders it in the format of
+    /// `cargo tree`, with a line for each package, ending with a newline.
+    ///
+    /// Packages are rendered as `<name> v<version>`, followed by `(proc-macro)` for proc-macros, and the source for
+    /// packages not from crates.io, same as cargo does. Build dependencies are listed after the normal ones, under
+    /// `[build-dependencies]`. Development dependencies aren't included, see [`Self::resolve_graph`]. The packages
+    /// of the graph are fetched, to find out which of them are proc-macros.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Features, Package, PackageFetcher, PackageSource, TreeOptions};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let serde = Package::from_str_ver("serde_json", "1.0.0", &PackageSource::crates_io())?;
+    /// let options = TreeOptions {
+    ///     depth: Some(1),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// print!("{}", fetcher.tree(serde, &Features::default(), &options)?);
+    /// # Ok::<(), String>(())
+    /// ```
+    ///
+    /// Errors, if the package [`TreeOptions::invert`] is set to isn't a part of the graph.
+    pub fn tree(&mut self, root: Package, features: &Features, options: &TreeOptions) -> Result<String, String> {
+        let graph = self.resolve_graph(root, features)?;
+        let tree_root = options.invert.unwrap_or(root);
+        if graph.node(&tree_root).is_none() {
+            return Err(format!(
+                "`{}` is not a dependency of `{}`",
+                tree_root.to_spec_string(),
+                root.to_spec_string()
+            ));
+        }
+
+        let packages: Vec<Package> = graph.nodes().iter().map(|node| node.package).collect();
+        let _lock = self.lock_package_cache()?;
+        let package_set = self.package_set(&packages)?;
+        let fetched = package_set
+            .get_many(package_set.package_ids())
+            .map_err(|e| self.report_failure(&packages, e))?;
+        let proc_macros = fetched
+            .into_iter()
+            .filter(|package| package.proc_macro())
+            .map(|package| Package {
+                package_id: package.package_id(),
+            })
+            .collect();
+
+        let mut tree = Tree {
+            graph: &graph,
+            options,
+            symbols: if options.ascii { &ASCII_SYMBOLS } else { &UTF8_SYMBOLS },
+            proc_macros,
+            visited: HashSet::new(),
+            levels_continue: vec![],
+            stack: vec![],
+            out: String::new(),
+        };
+        tree.node(tree_root, false);
+        Ok(tree.out)
+    }
+}
+
+/// State of rendering a [`ResolveGraph`], same as `cargo tree` renders it.
+struct Tree<'a> {
+    graph: &'a ResolveGraph,
+    options: &'a TreeOptions,
+    symbols: &'static Symbols,
+    proc_macros: HashSet<Package>,
+    /// Packages whose dependencies were already rendered, along with whether they are built for the host.
+    visited: HashSet<(Package, bool)>,
+    /// Whether each level of the tree above the current line continues below it.
+    levels_continue: Vec<bool>,
+    /// Packages from the root to the current one, to detect cycles.
+    stack: Vec<(Package, bool)>,
+    out: String,
+}
+
+impl Tree<'_> {
+    /// Renders `package`, and then its dependencies, `host` is whether it's built for the host, selecting
+    /// the features it's built with.
+    fn node(&mut self, package: Package, host: bool) {
+        let new = self.visited.insert((package, host)) || self.options.no_dedupe;
+        if let Some((last_continues, rest)) = self.levels_continue.split_last() {
+            for continues in rest {
+                let c = if *continues { self.symbols.down } else { " " };
+                let _ = write!(self.out, "{c}   ");
+            }
+            let c = if *last_continues {
+                self.symbols.tee
+            } else {
+                self.symbols.ell
+            };
+            let _ = write!(self.out, "{c}{0}{0} ", self.symbols.right);
+        }
+
+        let in_cycle = self.stack.contains(&(package, host));
+        let has_deps = [DependencyKind::Normal, DependencyKind::Build]
+            .into_iter()
+            .any(|kind| !self.edges(package, host, kind).is_empty());
+        self.label(package, host);
+        // Same as cargo, packages without dependencies aren't marked, as nothing is left out.
+        if (!new || in_cycle) && has_deps {
+            self.out += " (*)";
+        }
+        self.out += "\n";
+
+        if !new || in_cycle {
+            return;
+        }
+        self.stack.push((package, host));
+        for kind in [DependencyKind::Normal, DependencyKind::Build] {
+            self.dependencies(package, host, kind);
+        }
+        self.stack.pop();
+    }
+
+    /// Renders the dependencies of `package` of the given `kind`.
+    fn dependencies(&mut self, package: Package, host: bool, kind: DependencyKind) {
+        let edges = self.edges(package, host, kind);
+        if edges.is_empty() {
+            return;
+        }
+        if kind == DependencyKind::Build {
+            for continues in &self.levels_continue {
+                let c = if *continues { self.symbols.down } else { " " };
+                let _ = write!(self.out, "{c}   ");
+            }
+            self.out += "[build-dependencies]\n";
+        }
+        if self
+            .options
+            .depth
+            .is_some_and(|depth| self.levels_continue.len() + 1 > depth)
+        {
+            return;
+        }
+
+        let mut edges = edges.into_iter().peekable();
+        while let Some((dependency, host)) = edges.next() {
+            self.levels_continue.push(edges.peek().is_some());
+            self.node(dependency, host);
+            self.levels_continue.pop();
+        }
+    }
+
+    /// Returns the packages `package` depends on with the given `kind`, sorted, along with whether they are built
+    /// for the host, or the packages depending on it, if the tree is inverted.
+    fn edges(&self, package: Package, host: bool, kind: DependencyKind) -> Vec<(Package, bool)> {
+        let mut edges: Vec<(Package, bool)> = if self.options.invert.is_some() {
+            self.graph
+                .nodes()
+                .iter()
+                .filter(|node| {
+                    let dependencies = &node.dependencies;
+                    dependencies
+                        .iter()
+                        .any(|edge| edge.package == package && edge.kind == kind)
+                })
+                .map(|node| (node.package, false))
+                .collect()
+        } else {
+            // Build dependencies, and dependencies of proc-macros are built for the host.
+            let host = host || kind == DependencyKind::Build || self.proc_macros.contains(&package);
+            self.graph
+                .dependencies(&package)
+                .iter()
+                .filter(|edge| edge.kind == kind)
+                .map(|edge| (edge.package, host))
+                .collect()
+        };
+        edges.sort_unstable();
+        edges.dedup();
+        edges
+    }
+
+    /// Renders `package` the way `cargo tree` does, without the prefix.
+    fn label(&mut self, package: Package, host: bool) {
+        let package_id = package.package_id;
+        let _ = write!(self.out, "{} v{}", package_id.name(), package_id.version());
+        if self.proc_macros.contains(&package) {
+            self.out += " (proc-macro)";
+        }
+        if !package_id.source_id().is_crates_io() {
+            let _ = write!(self.out, " ({})", package_id.source_id());
+        }
+
+        if self.options.features {
+            let node = self
+                .graph
+                .node(&package)
+                .expect("packages of the tree are in the graph");
+            // Packages only built for the host don't have the features of the target build.
+            let features = if host || node.features.is_empty() {
+                &node.host_features
+            } else {
+                &node.features
+            };
+            let _ = write!(self.out, " {}", features.join(","));
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use crate::{Package, PackageFetcher};
+use cargo::core::{GitReference, SourceId};
+use cargo_util::Sha256;
+use semver::Version;
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs, io,
+    path::{Component, Path},
+};
+
+/// Name of the directory source the vendored sources are replaced with.
+const VENDORED_SOURCES: &str = "vendored-sources";
+
+impl PackageFetcher {
+    /// Fetches packages, and writes them into `dest` in the format of a directory source, same as `cargo vendor`,
+    /// returning the cargo config snippet, that replaces their sources with `dest`.
+    ///
+    /// Each package is copied into `dest/<name>`, or `dest/<name>-<version>` if a newer version of it is vendored
+    /// too, along with a `.cargo-checksum.json` with the hashes of its files. For registry packages, it also has
+    /// the checksum of the package archive, git packages don't have one, so cargo doesn't verify it.
+    ///
+    /// Path packages are skipped, as cargo doesn't replace path sources. Packages from local registries and
+    /// directories, as well as the same version of a package from different sources, can't be vendored.
+    /// Already existing directories in `dest` with the same names are replaced, `dest` is used in the snippet as is.
+    /// With the snippet in the cargo config, cargo only replaces git sources for packages locked to a commit, e.g.
+    /// the resolved ones, and can't resolve from the git sources themselves anymore.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let serde = fetcher.resolve_first("serde", Some("1"), &PackageSource::crates_io(), None)?;
+    ///
+    /// let snippet = fetcher.vendor(&[serde], "vendor")?;
+    /// std::fs::write(".cargo/config.toml", snippet).unwrap();
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn vendor<P: AsRef<Path>>(&mut self, packages: &[Package], dest: P) -> Result<String, String> {
+        let dest = dest.as_ref();
+        let packages: Vec<Package> = packages
+            .iter()
+            .filter(|p| !p.package_id.source_id().is_path())
+            .copied()
+            .collect();
+
+        let mut versions: HashMap<String, BTreeMap<Version, SourceId>> = HashMap::new();
+        for package in &packages {
+            let package_id = package.package_id;
+            let source_id = package_id.source_id();
+            if !source_id.is_remote_registry() && !source_id.is_git() {
+                return Err(format!(
+                    "can't vendor `{package_id}`, packages from {source_id} can't be replaced"
+                ));
+            }
+            let versions = versions.entry(package_id.name().to_string()).or_default();
+            match versions.insert(package_id.version().clone(), source_id) {
+                Some(other) if other != source_id => {
+                    return Err(format!(
+                        "can't vendor `{} v{}` from both {other} and {source_id}",
+                        package_id.name(),
+                        package_id.version()
+                    ));
+                },
+                _ => {},
+            }
+        }
+
+        let _lock = self.lock_package_cache()?;
+        let package_set = self.package_set(&packages)?;
+        let fetched = package_set
+            .get_many(package_set.package_ids())
+            .map_err(|e| self.report_failure(&packages, e))?;
+
+        let mut sources = BTreeSet::new();
+        for package in fetched {
+            let package_id = package.package_id();
+            let latest = versions[package_id.name().as_str()].keys().next_back();
+            let dir_name = if latest == Some(package_id.version()) {
+                package_id.name().to_string()
+            } else {
+                format!("{}-{}", package_id.name(), package_id.version())
+            };
+
+            vendor_package(package, &dest.join(dir_name), &self.config)
+                .map_err(|e| format!("failed to vendor `{package_id}`: {e}"))?;
+            sources.insert(package_id.source_id());
+        }
+
+        let mut snippet = String::new();
+        for source_id in sources {
+            if source_id.is_crates_io() {
+                snippet.push_str("[source.crates-io]\n");
+            } else if source_id.is_git() {
+                let reference = match source_id.git_reference() {
+                    Some(GitReference::Branch(branch)) => Some(("branch", branch)),
+                    Some(GitReference::Tag(tag)) => Some(("tag", tag)),
+                    Some(GitReference::Rev(rev)) => Some(("rev", rev)),
+                    Some(GitReference::DefaultBranch) | None => None,
+                };
+                let url = source_id.url().as_str();
+                match reference {
+                    // Each reference of a repository is a separate source.
+                    Some((key, value)) => {
+                        let name = format!("{url}?{key}={value}");
+                        snippet.push_str(&format!("[source.{name:?}]\ngit = {url:?}\n{key} = {value:?}\n"));
+                    },
+                    None => snippet.push_str(&format!("[source.{url:?}]\ngit = {url:?}\n")),
+                }
+            } else {
+                let url = source_id.url().as_str();
+                snippet.push_str(&format!("[source.{url:?}]\nregistry = {url:?}\n"));
+            }
+            snippet.push_str(&format!("replace-with = {VENDORED_SOURCES:?}\n\n"));
+        }
+        snippet.push_str(&format!(
+            "[source.{VENDORED_SOURCES}]\ndirectory = {:?}\n",
+            dest.to_string_lossy().replace('\\', "/")
+        ));
+        Ok(snippet)
+    }
+
+    /// Fetches a package, and verifies that its files match the hashes in its `.cargo-checksum.json`, e.g. of
+    /// a package vendored with [`Self::vendor`], or by `cargo vendor`.
+    ///
+    /// Only the files listed in it are verified, files that aren't listed aren't reported. Errors, naming all
+    /// the files that don't match, or are missing, or if the package doesn't have a `.cargo-checksum.json`,
+    /// which packages extracted from registries don't. Paths in it have to be relative to the package, without
+    /// `..`, so files outside of the package aren't read.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{PackageFetcher, PackageSource};
+    /// let mut fetcher = PackageFetcher::new()?;
+    /// let serde = fetcher.resolve_first("serde", None, &PackageSource::path("vendor/serde")?, None)?;
+    ///
+    /// fetcher.verify_contents(&serde)?;
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn verify_contents(&mut self, package: &Package) -> Result<(), String> {
+        let package_id = package.package_id;
+        let root = self.fetch(*package)?;
+        let path = root.join(".cargo-checksum.json");
+        let checksums = match fs::read(&path) {
+            Ok(contents) => serde_json::from_slice::<Value>(&contents)
+                .map_err(|e| format!("failed to parse `.cargo-checksum.json` of `{package_id}`: {e}"))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(format!("`{package_id}` doesn't have a `.cargo-checksum.json`"))
+            },
+            Err(e) => return Err(format!("failed to read `.cargo-checksum.json` of `{package_id}`: {e}")),
+        };
+        let files = checksums["files"]
+            .as_object()
+            .ok_or_else(|| format!("`.cargo-checksum.json` of `{package_id}` doesn't list its files"))?;
+
+        let mut mismatches = String::new();
+        for (file, expected) in files {
+            // Only files inside of the package are verified, not any file the checksums point to.
+            if !Path::new(file)
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+            {
+                return Err(format!(
+                    "`.cargo-checksum.json` of `{package_id}` lists unexpected path `{file}`"
+                ));
+            }
+            let hash = match fs::File::open(root.join(file)) {
+                Ok(file) => Sha256::new().update_file(&file).map(|hash| hash.finish_hex()),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    mismatches.push_str(&format!("\n  `{file}` is missing"));
+                    continue;
+                },
+                Err(e) => Err(e),
+            };
+            let hash = hash.map_err(|e| format!("failed to read `{file}` of `{package_id}`: {e}"))?;
+            if expected.as_str() != Some(hash.as_str()) {
+                mismatches.push_str(&format!("\n  `{file}` doesn't match its checksum"));
+            }
+        }
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "files of `{package_id}` don't match its `.cargo-checksum.json`:{mismatches}"
+            ))
+        }
+    }
+}
+
+/// Copies the files of a package into `dest`, replacing it if it exists, and writes its `.cargo-checksum.json`.
+fn vendor_package(package: &cargo::core::Package, dest: &Path, config: &cargo::Config) -> cargo::CargoResult<()> {
+    if dest.exists() {
+        fs::remove_dir_all(dest)?;
+    }
+
+    let mut files = BTreeMap::new();
+    for relative in crate::bundle::package_files(package, config)? {
+        let name = relative.to_string_lossy().replace('\\', "/");
+        let file_name = relative.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        // Same as `cargo vendor`, files only relevant to version control or patching aren't vendored.
+        if [".gitattributes", ".gitignore", ".git"].contains(&file_name)
+            || name.ends_with(".orig")
+            || name.ends_with(".rej")
+        {
+            continue;
+        }
+
+        let copy = dest.join(&relative);
+        if let Some(parent) = copy.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(package.root().join(&relative), &copy)?;
+        files.insert(name, Sha256::new().update_path(&copy)?.finish_hex());
+    }
+
+    // Only registry packages have a checksum of their archive.
+    let checksum = package
+        .summary()
+        .checksum()
+        .filter(|_| package.package_id().source_id().is_registry());
+    let json = json!({ "files": files, "package": checksum });
+    fs::create_dir_all(dest)?;
+    fs::write(dest.join(".cargo-checksum.json"), json.to_string())?;
+    Ok(())
+}
@@ -0,0 +1,195 @@
+use crate::{query_blocking, registry, Package, PackageFetcher, PackageSource};
+use cargo::{
+    core::{Dependency, PackageId, SourceId},
+    CargoResult,
+};
+use cargo_util::registry::make_dep_path;
+use semver::Version;
+use std::{collections::HashSet, fs};
+
+/// Version of a package, reported by [`PackageFetcher::resolve_each`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct VersionInfo {
+    /// The package at this version.
+    pub package: Package,
+    /// Version of the package.
+    pub version: Version,
+    /// Whether the version is yanked.
+    pub yanked: bool,
+    /// Whether the version is a pre-release, e.g. `1.0.0-alpha.1`.
+    pub prerelease: bool,
+    /// SHA-256 checksum of the `.crate` archive, as hex, for registry packages.
+    pub checksum: Option<String>,
+}
+
+impl PackageFetcher {
+    /// Resolves all versions of a package, including yanked ones, calling `f` with each of them as soon as cargo
+    /// reports it, instead of collecting them first.
+    ///
+    /// Versions are reported in the order of the source, which isn't sorted, versions that aren't yanked first,
+    /// and then the yanked ones, which are read from the index entries of the package cargo cached while resolving.
+    /// If cargo couldn't cache them, e.g. if the cargo home is read-only, yanked versions aren't reported.
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{PackageFetcher, PackageSource};
+    /// let fetcher = PackageFetcher::new()?;
+    ///
+    /// fetcher.resolve_each("serde", &PackageSource::crates_io(), |info| {
+    ///     if !info.yanked {
+    ///         println!("{}", info.version);
+    ///     }
+    /// })?;
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn resolve_each<N: AsRef<str>, F: FnMut(VersionInfo)>(
+        &self,
+        name: N,
+        source: &PackageSource,
+        mut f: F,
+    ) -> Result<(), String> {
+        let name = name.as_ref();
+        let _lock = self.lock_package_cache()?;
+        let source_id = self.source_id(source).map_err(|e| e.to_string())?;
+        let mut src = self.load_source(source_id, &HashSet::new())?;
+        let dep = Dependency::parse(name, None, src.source_id()).map_err(|e| e.to_string())?;
+
+        query_blocking(&mut *src, &dep, &mut |summary| {
+            f(VersionInfo {
+                package: Package {
+                    package_id: summary.package_id(),
+                },
+                version: summary.version().clone(),
+                yanked: false,
+                prerelease: !summary.version().pre.is_empty(),
+                checksum: summary.checksum().map(str::to_owned),
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+        let replaced = src.replaced_source_id();
+        if !replaced.is_registry() {
+            return Ok(());
+        }
+        for (version, checksum) in self.yanked_versions(replaced, name).map_err(|e| e.to_string())? {
+            let package_id = PackageId::new(name, &version, src.source_id()).map_err(|e| e.to_string())?;
+            f(VersionInfo {
+                package: Package { package_id },
+                prerelease: !version.pre.is_empty(),
+                version,
+                yanked: true,
+                checksum,
+            });
+        }
+        Ok(())
+    }
+
+    /// Resolves the newest version of a package, that is semver incompatible with, and newer than the given version,
+    /// or [`None`], if the given version is already of the newest major version.
+    ///
+    /// Same as cargo, the leftmost non-zero part of the version is the major one, so `0.2.0` is a new major version
+    /// of `0.1.0`, and `0.0.2` of `0.0.1`. Versions are resolved from the source of the package, skipping yanked
+    /// versions and pre-releases, same as the newest version `cargo add` would pick, see
+    /// [`Self::resolve_installable`].
+    ///
+    /// ```no_run
+    /// # use cargo_fetch::{Package, PackageFetcher, PackageSource};
+    /// let fetcher = PackageFetcher::new()?;
+    /// let rand = Package::from_str_ver("rand", "0.7.3", &PackageSource::crates_io())?;
+    ///
+    /// if let Some(version) = fetcher.has_newer_major(rand)? {
+    ///     println!("`rand` {version} is available");
+    /// }
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn has_newer_major(&self, package: Package) -> Result<Option<Version>, String> {
+        let _lock = self.lock_package_cache()?;
+        let package_id = package.package_id;
+        let mut src = self.load_source(package_id.source_id(), &HashSet::new())?;
+        let dep = Dependency::parse(package_id.name(), None, src.source_id()).map_err(|e| e.to_string())?;
+
+        let current = compatibility(package_id.version());
+        let mut newest: Option<Version> = None;
+        query_blocking(&mut *src, &dep, &mut |summary| {
+            let version = summary.version();
+            if version.pre.is_empty()
+                && compatibility(version) > current
+                && newest.as_ref().map_or(true, |newest| newest < version)
+            {
+                newest = Some(version.clone());
+            }
+        })
+        .map_err(|e| e.to_string())?;
+        Ok(newest)
+    }
+
+    /// Reads the yanked versions of a package, with their checksums, from the index entries of the package, which
+    /// are cached by cargo for remote registries, or are in the registry itself for local ones.
+    ///
+    /// Package cache lock has to be held by the caller.
+    fn yanked_versions(&self, source_id: SourceId, name: &str) -> CargoResult<Vec<(Version, Option<String>)>> {
+        let dep_path = make_dep_path(&name.to_lowercase(), false);
+        let entries: Vec<Vec<u8>> = if source_id.is_remote_registry() {
+            let cache = self
+                .config
+                .registry_index_path()
+                .join(registry::short_name(source_id))
+                .join(".cache")
+                .join(dep_path);
+            match fs::read(self.config.assert_package_cache_locked(&cache)) {
+                Ok(contents) => cached_entries(&contents),
+                Err(_) => vec![],
+            }
+        } else {
+            let root = source_id
+                .url()
+                .to_file_path()
+                .map_err(|()| anyhow::format_err!("invalid path of {source_id}"))?;
+            match fs::read(root.join("index").join(dep_path)) {
+                Ok(contents) => contents.split(|&b| b == b'\n').map(<[u8]>::to_vec).collect(),
+                Err(_) => vec![],
+            }
+        };
+
+        let mut yanked = vec![];
+        for entry in entries.iter().filter(|entry| !entry.is_empty()) {
+            let Ok(entry) = serde_json::from_slice::<serde_json::Value>(entry) else {
+                continue;
+            };
+            // Cargo skips entries of newer index formats.
+            let format = entry["v"].as_u64().unwrap_or(1);
+            if entry["name"] != name || entry["yanked"] != true || format > 2 {
+                continue;
+            }
+            if let Some(Ok(version)) = entry["vers"].as_str().map(Version::parse) {
+                yanked.push((version, entry["cksum"].as_str().map(str::to_owned)));
+            }
+        }
+        Ok(yanked)
+    }
+}
+
+/// Semver compatibility class of a version, which is its leftmost non-zero part, same as cargo treats caret
+/// requirements.
+fn compatibility(version: &Version) -> (u64, u64, u64) {
+    match (version.major, version.minor) {
+        (0, 0) => (0, 0, version.patch),
+        (0, minor) => (0, minor, 0),
+        (major, _) => (major, 0, 0),
+    }
+}
+
+/// Index entries of a package cargo cached, empty if the cache is of a different cargo version.
+///
+/// The cache is a version byte, the index format version, and the version of the index it was cached from, followed
+/// by the version and the entry of each version of the package, all separated by zero bytes.
+fn cached_entries(contents: &[u8]) -> Vec<Vec<u8>> {
+    const CACHE_VERSION: u8 = 3;
+    const INDEX_FORMAT_VERSION: u32 = 2;
+    let rest = match contents {
+        [CACHE_VERSION, a, b, c, d, rest @ ..] if u32::from_le_bytes([*a, *b, *c, *d]) == INDEX_FORMAT_VERSION => rest,
+        _ => return vec![],
+    };
+    // Skips the version of the index, and then every version of the package.
+    rest.split(|&b| b == 0).skip(2).step_by(2).map(<[u8]>::to_vec).collect()
+}
@@ -0,0 +1,139 @@
+use crate::{GitReference, Package, PackageFetcher, PackageSource};
+use cargo::{
+    core::{find_workspace_root, EitherManifest, SourceId, WorkspaceConfig},
+    util::toml::{read_manifest, TomlDependency},
+    CargoResult,
+};
+use serde_json::Value;
+use std::{collections::BTreeMap, path::Path};
+use url::Url;
+
+/// Dependency declared in `[workspace.dependencies]`, returned by [`PackageFetcher::workspace_dependencies`].
+///
+/// Members inherit it with `dep = { workspace = true }`, adding their own `features` and `optional` to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceDependency {
+    /// Name of the package, if it's different from the name of the dependency (`package` key).
+    pub package: Option<String>,
+    /// Version requirement, if it's declared.
+    pub version: Option<String>,
+    /// Source of the dependency, paths are relative to the workspace root.
+    ///
+    /// Same as cargo, `path` takes precedence over `git`, and `git` over a registry, [`PackageSource::CratesIo`]
+    /// is the default.
+    pub source: PackageSource,
+    /// Features enabled on the dependency.
+    pub features: Vec<String>,
+    /// Whether default features of the dependency are enabled.
+    pub default_features: bool,
+}
+
+impl PackageFetcher {
+    /// Fetches a package, and returns the dependencies declared in `[workspace.dependencies]` of its workspace,
+    /// by the names members refer to them with.
+    ///
+    /// The workspace is found same as cargo does: the package itself, if it's the workspace root, otherwise
+    /// the closest directory above it with a manifest that has `[workspace]` including the package. This is useful
+    /// for git and path packages, registry packages never have a workspace, as inherited fields are inlined into
+    /// their manifests on publish, so there are no dependencies for them.
+    ///
+    /// Registries of the dependencies are looked up by their names in the cargo config of the fetcher, so
+    /// a dependency from a registry that isn't configured is an error.
+    pub fn workspace_dependencies(
+        &mut self,
+        package: Package,
+    ) -> Result<BTreeMap<String, WorkspaceDependency>, String> {
+        let package = self.fetch_package(package)?;
+        let (root, dependencies) = match package.manifest().workspace_config() {
+            WorkspaceConfig::Root(root) => (package.root().to_owned(), root.inheritable().dependencies().ok()),
+            WorkspaceConfig::Member { .. } => {
+                match find_workspace_root(package.manifest_path(), &self.config).map_err(|e| e.to_string())? {
+                    Some(manifest) => (
+                        manifest.parent().expect("manifest is in a directory").to_owned(),
+                        root_dependencies(&manifest, &self.config).map_err(|e| e.to_string())?,
+                    ),
+                    None => return Ok(BTreeMap::new()),
+                }
+            },
+        };
+
+        let Some(dependencies) = dependencies else {
+            return Ok(BTreeMap::new());
+        };
+        dependencies
+            .into_iter()
+            .map(|(name, dependency)| {
+                let dependency = workspace_dependency(&dependency, &root, &self.config)
+                    .map_err(|e| format!("invalid workspace dependency `{name}`: {e}"))?;
+                Ok((name, dependency))
+            })
+            .collect()
+    }
+}
+
+/// Reads `[workspace.dependencies]` of the workspace root manifest at `path`, [`None`] if it doesn't have them.
+fn root_dependencies(path: &Path, config: &cargo::Config) -> CargoResult<Option<BTreeMap<String, TomlDependency>>> {
+    let dir = path.parent().expect("manifest is in a directory");
+    let (manifest, _) = read_manifest(path, SourceId::for_path(dir)?, config)?;
+    let workspace_config = match &manifest {
+        EitherManifest::Real(manifest) => manifest.workspace_config(),
+        EitherManifest::Virtual(manifest) => manifest.workspace_config(),
+    };
+    Ok(workspace_config
+        .inheritable()
+        .and_then(|inheritable| inheritable.dependencies().ok()))
+}
+
+/// Converts a dependency, as it's written in the manifest, relative paths are joined to `root`.
+fn workspace_dependency(
+    dependency: &TomlDependency,
+    root: &Path,
+    config: &cargo::Config,
+) -> CargoResult<WorkspaceDependency> {
+    // Fields of the parsed dependency are private, but it serializes back to the manifest format.
+    let json = serde_json::to_value(dependency)?;
+    let string = |key: &str| json[key].as_str().map(str::to_owned);
+
+    let source = if let Some(path) = string("path") {
+        PackageSource::Path(root.join(path))
+    } else if let Some(git) = string("git") {
+        let git_ref = match (string("branch"), string("tag"), string("rev")) {
+            (Some(branch), _, _) => GitReference::Branch(branch),
+            (_, Some(tag), _) => GitReference::Tag(tag),
+            (_, _, Some(rev)) => GitReference::Revision(rev),
+            _ => GitReference::DefaultBranch,
+        };
+        PackageSource::Git {
+            url: Url::parse(&git)?,
+            git_ref,
+        }
+    } else if let Some(index) = string("registry-index") {
+        PackageSource::RemoteRegistry(Url::parse(&index)?)
+    } else if let Some(registry) = string("registry") {
+        let source_id = SourceId::alt_registry(config, &registry)?;
+        if source_id.is_crates_io() {
+            PackageSource::CratesIo
+        } else {
+            PackageSource::RemoteRegistry(source_id.url().clone())
+        }
+    } else {
+        PackageSource::CratesIo
+    };
+
+    Ok(WorkspaceDependency {
+        package: string("package"),
+        version: match &json {
+            Value::String(version) => Some(version.clone()),
+            _ => string("version"),
+        },
+        source,
+        features: json["features"]
+            .as_array()
+            .map(|features| features.iter().filter_map(|f| f.as_str().map(str::to_owned)).collect())
+            .unwrap_or_default(),
+        default_features: json["default-features"]
+            .as_bool()
+            .or_else(|| json["default_features"].as_bool())
+            .unwrap_or(true),
+    })
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use cargo_fetch::{Package, PackageSource};
+use common::{Fixture, Pkg};
+
+fn sources(fixture: &Fixture) -> (PackageSource, PackageSource) {
+    let missing = url::Url::from_directory_path(fixture.path("missing")).unwrap();
+    let missing = PackageSource::git(missing, None).unwrap();
+    let local = fixture.local_registry("registry", &[Pkg::new("foo", "1.0.0")]);
+    (missing, local)
+}
+
+#[test]
+fn resolve_first_of_skips_failing_sources() {
+    let fixture = Fixture::new();
+    let (missing, local) = sources(&fixture);
+    let fetcher = fixture.fetcher();
+
+    let foo = fetcher
+        .resolve_first_of("foo", None, &[missing.clone(), local.clone()])
+        .unwrap();
+    assert_eq!(foo, Package::from_str_ver("foo", "1.0.0", &local).unwrap());
+
+    let error = fetcher
+        .resolve_first_of("bar", None, &[missing, local.clone()])
+        .unwrap_err();
+    assert!(error.contains("missing") && error.contains("registry"), "{error}");
+    assert!(error.contains("no package named `bar` was found"), "{error}");
+
+    let error = fetcher.resolve_first_of("foo", Some("2"), &[local]).unwrap_err();
+    assert!(
+        error.contains("no version of `foo` matches `2`, available versions: 1.0.0"),
+        "{error}"
+    );
+}
+
+#[test]
+fn resolve_any_of_skips_failing_sources() {
+    let fixture = Fixture::new();
+    let (missing, local) = sources(&fixture);
+    let fetcher = fixture.fetcher();
+
+    // Local registry resolves the package before the repository is even fetched
+    let foo = fetcher
+        .resolve_any_of("foo", None, &[missing.clone(), local.clone()])
+        .unwrap();
+    assert_eq!(foo, Package::from_str_ver("foo", "1.0.0", &local).unwrap());
+
+    let error = fetcher.resolve_any_of("bar", None, &[missing, local]).unwrap_err();
+    assert!(error.contains("missing") && error.contains("registry"), "{error}");
+}
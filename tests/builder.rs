@@ -0,0 +1,147 @@
+mod common;
+
+use cargo_fetch::{Package, PackageFetcherBuilder, PackageSource, ResolveError};
+use common::{commit_all, manifest, Fixture, Pkg};
+use std::{
+    fs,
+    time::{Duration, SystemTime},
+};
+
+#[test]
+fn default_members_only() {
+    let fixture = Fixture::new();
+    let repo = fixture.git_repo(
+        "repo",
+        &[
+            (
+                "Cargo.toml",
+                "[workspace]\nmembers = [\"foo\", \"foo-derive\"]\ndefault-members = [\"foo\"]\n",
+            ),
+            ("foo/Cargo.toml", &manifest("foo", "0.1.0")),
+            ("foo/src/lib.rs", ""),
+            ("foo-derive/Cargo.toml", &manifest("foo-derive", "0.1.0")),
+            ("foo-derive/src/lib.rs", ""),
+        ],
+    );
+    let fetcher = fixture.builder().default_members_only(true).build().unwrap();
+    let source = PackageSource::git(repo, None).unwrap();
+
+    let members = fetcher.git_members(&source).unwrap();
+    assert_eq!(members.len(), 1);
+    assert!(members[0].lock_id().starts_with("foo 0.1.0"));
+    assert!(fetcher.resolve_first("foo-derive", None, &source, None).is_ok());
+}
+
+#[test]
+fn max_crate_size() {
+    let fixture = Fixture::new();
+    let source = fixture.git_registry("registry", &[Pkg::new("foo", "0.1.0")]);
+    let foo = Package::from_str_ver("foo", "0.1.0", &source).unwrap();
+    let too_large = |result| matches!(result, Err(ResolveError::TooLarge { package, limit: 10, .. }) if package == foo);
+
+    assert!(too_large(
+        fixture.builder().max_crate_size(10).build().unwrap().fetch(foo)
+    ));
+    assert!(fixture.fetcher().fetch(foo).is_ok());
+    // The archive is cached now, and checked all the same
+    assert!(too_large(
+        fixture.builder().max_crate_size(10).build().unwrap().fetch(foo)
+    ));
+}
+
+#[test]
+fn registry_auth_required_for_tokens() {
+    let fixture = Fixture::new();
+    let source = PackageSource::remote_with_token("https://registry.example.com/index", "token").unwrap();
+
+    let error = fixture.fetcher().resolve_first("foo", None, &source, None).unwrap_err();
+    assert!(
+        error.to_string().contains("PackageFetcherBuilder::registry_auth"),
+        "{error}"
+    );
+}
+
+#[test]
+fn pin_git_revisions() {
+    let fixture = Fixture::new();
+    let repo = fixture.git_repo("repo", &[("Cargo.toml", &manifest("foo", "0.1.0")), ("src/lib.rs", "")]);
+    let pinned = fixture.builder().pin_git_revisions(true).build().unwrap();
+    let unpinned = fixture.fetcher();
+    let source = PackageSource::git(repo, None).unwrap();
+    let foo = Package::from_str_ver("foo", "0.1.0", &source).unwrap();
+    assert_eq!(pinned.resolve_first("foo", None, &source, None).unwrap(), foo);
+
+    fs::write(fixture.path("repo/Cargo.toml"), manifest("foo", "0.2.0")).unwrap();
+    commit_all(&fixture.path("repo"));
+    let bumped = Package::from_str_ver("foo", "0.2.0", &source).unwrap();
+    assert_eq!(unpinned.resolve_first("foo", None, &source, None).unwrap(), bumped);
+    assert_eq!(pinned.resolve_first("foo", None, &source, None).unwrap(), foo);
+    pinned.clear_source_cache();
+    assert_eq!(pinned.resolve_first("foo", None, &source, None).unwrap(), bumped);
+}
+
+#[test]
+fn normalized_mtime_and_line_endings() {
+    let fixture = Fixture::new();
+    let foo = Pkg::new("foo", "0.1.0")
+        .file("src/lib.rs", "pub fn foo() {}\r\n")
+        .mode("src/lib.rs", 0o444);
+    let source = fixture.local_registry("registry", &[foo]);
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    let mut fetcher = fixture
+        .builder()
+        .normalized_mtime(mtime)
+        .normalize_line_endings(true)
+        .build()
+        .unwrap();
+    let foo = Package::from_str_ver("foo", "0.1.0", &source).unwrap();
+
+    let lib = fetcher.fetch(foo).unwrap().join("src/lib.rs");
+    assert_eq!(fs::read_to_string(&lib).unwrap(), "pub fn foo() {}\n");
+    assert_eq!(fs::metadata(&lib).unwrap().modified().unwrap(), mtime);
+    assert!(fs::metadata(&lib).unwrap().permissions().readonly());
+
+    // Packages in the shared cargo home are never changed
+    assert!(PackageFetcherBuilder::new().normalized_mtime(mtime).build().is_err());
+}
+
+#[test]
+fn minimum_version() {
+    let fixture = Fixture::new();
+    let source = fixture.local_registry(
+        "registry",
+        &[Pkg::new("openssl", "0.10.50"), Pkg::new("openssl", "0.10.55")],
+    );
+    let fetcher = fixture
+        .builder()
+        .minimum_version("openssl", "0.10.55".parse().unwrap())
+        .build()
+        .unwrap();
+
+    assert!(fetcher.resolve_first("openssl", None, &source, None).is_ok());
+    assert_eq!(
+        fetcher.resolve_first("openssl", Some("=0.10.50"), &source, None),
+        Err(ResolveError::BelowMinimum {
+            name: "openssl".into(),
+            selected: "0.10.50".parse().unwrap(),
+            floor: "0.10.55".parse().unwrap(),
+        }),
+    );
+}
+
+#[test]
+fn config_override() {
+    let fixture = Fixture::new();
+    let fetcher = fixture
+        .builder()
+        .config_override("source.crates-io.replace-with = 'mirror'")
+        .config_override("source.mirror.registry = 'https://mirror.example.com/index'")
+        .build()
+        .unwrap();
+    assert_eq!(
+        fetcher.effective_source_url(&PackageSource::CratesIo).unwrap().as_str(),
+        "https://mirror.example.com/index",
+    );
+
+    assert!(fixture.builder().config_override("net.retry = five").build().is_err());
+}
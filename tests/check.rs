@@ -0,0 +1,48 @@
+mod common;
+
+use common::Fixture;
+use url::Url;
+
+#[test]
+fn default_branch_after_url_rewriting() {
+    let fixture = Fixture::new();
+    let path = fixture.path("repo");
+    let repo = git2::Repository::init(&path).unwrap();
+    repo.set_head("refs/heads/trunk").unwrap();
+    let signature = git2::Signature::now("foo", "foo@example.com").unwrap();
+    let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+    repo.commit(Some("HEAD"), &signature, &signature, "foo", &tree, &[])
+        .unwrap();
+
+    let mut fetcher = fixture.fetcher();
+    let mirror = Url::from_directory_path(path).unwrap();
+    fetcher.set_url_rewriter(move |url| match url.host_str() {
+        Some("git.example.com") => mirror.clone(),
+        _ => url.clone(),
+    });
+
+    let url = Url::parse("https://git.example.com/foo").unwrap();
+    assert_eq!(fetcher.default_branch(&url).unwrap(), "trunk");
+}
+
+#[test]
+fn latest_tag_compares_versions() {
+    let fixture = Fixture::new();
+    let path = fixture.path("repo");
+    let repo = git2::Repository::init(&path).unwrap();
+    let signature = git2::Signature::now("foo", "foo@example.com").unwrap();
+    let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+    let commit = repo
+        .commit(Some("HEAD"), &signature, &signature, "foo", &tree, &[])
+        .unwrap();
+    let commit = repo.find_object(commit, None).unwrap();
+    for tag in ["v1.9.0", "v1.10.0", "v2.0.0", "nightly"] {
+        repo.tag_lightweight(tag, &commit, false).unwrap();
+    }
+    let fetcher = fixture.fetcher();
+    let url = Url::from_directory_path(path).unwrap();
+
+    assert_eq!(fetcher.latest_tag(&url, "v1.*").unwrap(), "v1.10.0");
+    assert_eq!(fetcher.latest_tag(&url, "*").unwrap(), "v2.0.0");
+    assert!(fetcher.latest_tag(&url, "release-*").is_err());
+}
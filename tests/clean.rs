@@ -0,0 +1,38 @@
+#![cfg(unix)]
+
+mod common;
+
+use cargo_fetch::CleanOptions;
+use common::Fixture;
+use std::{fs, os::unix::fs::symlink};
+
+#[test]
+fn clean_unlinks_caches_outside_of_cargo_home() {
+    let fixture = Fixture::new();
+    let home = fixture.path("cargo-home");
+    for path in ["cargo-home/registry/index/foo", "outside/src/foo", "outside/git/db/foo"] {
+        fs::create_dir_all(fixture.path(path)).unwrap();
+    }
+    fs::write(home.join("config.toml"), "").unwrap();
+    symlink(fixture.path("outside/src"), home.join("registry/src")).unwrap();
+    symlink(fixture.path("outside/git"), home.join("git")).unwrap();
+    let mut fetcher = fixture.fetcher();
+    let registry_only = CleanOptions {
+        registry: true,
+        git: false,
+    };
+
+    fetcher.clean(&registry_only).unwrap();
+    assert!(!home.join("registry/index").exists());
+    // The link is removed, but not what it points to
+    assert!(home.join("registry/src").symlink_metadata().is_err());
+    assert!(fixture.path("outside/src/foo").exists());
+    assert!(home.join("config.toml").exists());
+
+    // `git` is outside of the cargo home, so nothing is removed from it
+    assert!(fetcher
+        .clean(&CleanOptions::default())
+        .unwrap_err()
+        .contains("outside of the cargo home"));
+    assert!(fixture.path("outside/git/db/foo").exists());
+}
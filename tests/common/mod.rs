@@ -0,0 +1,246 @@
+//! Fixtures shared by the integration tests, which keep everything they write in a temporary directory,
+//! including the cargo home of the fetcher.
+
+#![allow(dead_code)]
+
+use cargo_fetch::{Package, PackageFetcher, PackageFetcherBuilder, PackageSource};
+use serde_json::{json, Value};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tempfile::TempDir;
+use url::Url;
+
+/// Temporary directory with a cargo home, removed once dropped.
+pub struct Fixture {
+    dir: TempDir,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        Self {
+            dir: TempDir::new().unwrap(),
+        }
+    }
+
+    /// Path of `path` in the fixture directory.
+    pub fn path(&self, path: &str) -> PathBuf {
+        self.dir.path().join(path)
+    }
+
+    /// Builder of a fetcher using the cargo home of the fixture.
+    pub fn builder(&self) -> PackageFetcherBuilder {
+        PackageFetcher::builder().cargo_home(self.path("cargo-home"))
+    }
+
+    pub fn fetcher(&self) -> PackageFetcher {
+        self.builder().build().unwrap()
+    }
+
+    /// Writes the `(path, contents)` files into `dir` in the fixture directory.
+    pub fn write(&self, dir: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = self.path(dir);
+        for &(path, contents) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    /// Writes a package with an empty `src/lib.rs` into `dir` in the fixture directory, returning it as a path
+    /// source.
+    pub fn path_package(&self, dir: &str, manifest: &str) -> PackageSource {
+        let dir = self.write(dir, &[("Cargo.toml", manifest), ("src/lib.rs", "")]);
+        PackageSource::path(dir).unwrap()
+    }
+
+    /// Writes path packages named after the `(name, manifest)` pairs, with the rest of their manifests, at version
+    /// 0.1.0.
+    pub fn path_packages(&self, packages: &[(&str, &str)]) {
+        for (name, manifest) in packages {
+            self.path_package(
+                name,
+                &format!("[package]\nname = '{name}'\nversion = '0.1.0'\n{manifest}\n"),
+            );
+        }
+    }
+
+    /// Package written by [`Self::path_packages`].
+    pub fn package(&self, name: &str) -> Package {
+        Package::from_str_ver(name, "0.1.0", &PackageSource::path(self.path(name)).unwrap()).unwrap()
+    }
+
+    /// Writes the `(path, contents)` files into `dir` in the fixture directory, and commits them to a new git
+    /// repository there.
+    pub fn git_repo(&self, dir: &str, files: &[(&str, &str)]) -> Url {
+        let dir = self.write(dir, files);
+        commit_all(&dir);
+        Url::from_directory_path(dir).unwrap()
+    }
+
+    /// Writes a local registry with the packages into `dir` in the fixture directory.
+    pub fn local_registry(&self, dir: &str, packages: &[Pkg]) -> PackageSource {
+        let dir = self.path(dir);
+        write_registry(&dir, &dir.join("index"), packages);
+        PackageSource::local(dir)
+    }
+
+    /// Writes a remote registry with the packages into `dir` in the fixture directory, with a git index, and the
+    /// archives downloaded from `file://` URLs.
+    pub fn git_registry(&self, dir: &str, packages: &[Pkg]) -> PackageSource {
+        let dir = self.path(dir);
+        let index = dir.join("index");
+        write_registry(&dir, &index, packages);
+        let dl = Url::from_directory_path(&dir).unwrap();
+        fs::write(
+            index.join("config.json"),
+            json!({ "dl": format!("{dl}{{crate}}-{{version}}.crate") }).to_string(),
+        )
+        .unwrap();
+        commit_all(&index);
+        PackageSource::remote(Url::from_directory_path(index).unwrap()).unwrap()
+    }
+}
+
+/// Package of a registry fixture, with a manifest without dependencies and an empty `src/lib.rs` by default.
+#[derive(Clone)]
+pub struct Pkg {
+    pub name: String,
+    pub version: String,
+    yanked: bool,
+    deps: Vec<Value>,
+    features: Value,
+    files: Vec<(String, String, u32)>,
+}
+
+impl Pkg {
+    pub fn new(name: &str, version: &str) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+            yanked: false,
+            deps: vec![],
+            features: json!({}),
+            files: vec![
+                ("Cargo.toml".into(), manifest(name, version), 0o644),
+                ("src/lib.rs".into(), String::new(), 0o644),
+            ],
+        }
+    }
+
+    pub fn yanked(mut self) -> Self {
+        self.yanked = true;
+        self
+    }
+
+    /// Adds a dependency to the index entry, as cargo publishes it, e.g. `{"name": "bar", "req": "^1"}`, with the
+    /// rest of the fields filled in.
+    pub fn dep(mut self, dep: Value) -> Self {
+        let mut full = json!({
+            "features": [],
+            "optional": false,
+            "default_features": true,
+            "target": null,
+            "kind": "normal",
+        });
+        full.as_object_mut().unwrap().extend(dep.as_object().unwrap().clone());
+        self.deps.push(full);
+        self
+    }
+
+    /// Sets the features of the index entry.
+    pub fn features(mut self, features: Value) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Adds a file to the archive, replacing the one at the same path.
+    pub fn file(mut self, path: &str, contents: &str) -> Self {
+        self.files.retain(|(file, ..)| file != path);
+        self.files.push((path.into(), contents.into(), 0o644));
+        self
+    }
+
+    /// Sets the mode of a file in the archive.
+    pub fn mode(mut self, path: &str, mode: u32) -> Self {
+        for (file, _, file_mode) in &mut self.files {
+            if file == path {
+                *file_mode = mode;
+            }
+        }
+        self
+    }
+
+    /// Archive of the package, as `cargo package` creates it.
+    pub fn archive(&self) -> Vec<u8> {
+        let encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        let mut archive = tar::Builder::new(encoder);
+        for (path, contents, mode) in &self.files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(*mode);
+            header.set_cksum();
+            let path = format!("{}-{}/{path}", self.name, self.version);
+            archive.append_data(&mut header, path, contents.as_bytes()).unwrap();
+        }
+        archive.into_inner().unwrap().finish().unwrap()
+    }
+}
+
+/// Writes the archives of the packages into `dir`, and their entries into `index`.
+fn write_registry(dir: &Path, index: &Path, packages: &[Pkg]) {
+    for package in packages {
+        let archive = package.archive();
+        let cksum = cargo_util::Sha256::new().update(&archive).finish_hex();
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join(format!("{}-{}.crate", package.name, package.version)), archive).unwrap();
+
+        let entry = json!({
+            "name": package.name,
+            "vers": package.version,
+            "deps": package.deps,
+            "cksum": cksum,
+            "features": package.features,
+            "yanked": package.yanked,
+        });
+        let path = index.join(index_path(&package.name));
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut entries = fs::read_to_string(&path).unwrap_or_default();
+        entries += &entry.to_string();
+        fs::write(path, entries + "\n").unwrap();
+    }
+}
+
+/// Path of the index file of the package `name`, relative to the index root.
+pub fn index_path(name: &str) -> String {
+    let name = name.to_lowercase();
+    match name.len() {
+        1 => format!("1/{name}"),
+        2 => format!("2/{name}"),
+        3 => format!("3/{}/{name}", &name[..1]),
+        _ => format!("{}/{}/{name}", &name[..2], &name[2..4]),
+    }
+}
+
+/// Commits all of the files in `dir`, initializing a git repository there first.
+pub fn commit_all(dir: &Path) {
+    let repo = git2::Repository::init(dir).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = git2::Signature::now("foo", "foo@example.com").unwrap();
+    let parents = match repo.head() {
+        Ok(head) => vec![head.peel_to_commit().unwrap()],
+        Err(_) => vec![],
+    };
+    let parents: Vec<&git2::Commit> = parents.iter().collect();
+    repo.commit(Some("HEAD"), &signature, &signature, "foo", &tree, &parents)
+        .unwrap();
+}
+
+/// Manifest of a package without dependencies.
+pub fn manifest(name: &str, version: &str) -> String {
+    format!("[package]\nname = \"{name}\"\nversion = \"{version}\"\n")
+}
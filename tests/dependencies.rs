@@ -0,0 +1,108 @@
+mod common;
+
+use cargo_fetch::Package;
+use common::{Fixture, Pkg};
+use serde_json::json;
+
+#[test]
+fn direct_dependencies() {
+    let fixture = Fixture::new();
+    let source = fixture.path_package(
+        "foo",
+        r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+
+            [dependencies]
+            serde = { version = "1", optional = true }
+            log = "0.4"
+
+            [target.'cfg(windows)'.dependencies]
+            winapi = "0.3"
+
+            [build-dependencies]
+            cc = "1"
+
+            [features]
+            derive = ["serde/derive"]
+        "#,
+    );
+    let mut fetcher = fixture.fetcher();
+    let foo = Package::from_str_ver("foo", "0.1.0", &source).unwrap();
+
+    let table = fetcher.direct_dependencies(foo).unwrap();
+    let normal: Vec<_> = table
+        .normal
+        .iter()
+        .map(|dep| (dep.name.as_str(), dep.target.as_deref()))
+        .collect();
+    assert_eq!(
+        normal,
+        [("log", None), ("serde", None), ("winapi", Some("cfg(windows)"))]
+    );
+    assert_eq!(table.normal[1].enabled_by, ["derive", "serde"]);
+    assert_eq!(table.build[0].name, "cc");
+    assert!(table.dev.is_empty());
+}
+
+#[test]
+fn dependency_requirements_and_candidates() {
+    let fixture = Fixture::new();
+    let source = fixture.local_registry(
+        "registry",
+        &[
+            Pkg::new("foo", "1.0.0").dep(json!({ "name": "bar", "req": "^1" })),
+            Pkg::new("bar", "1.0.0"),
+            Pkg::new("bar", "1.2.0"),
+            Pkg::new("bar", "2.0.0"),
+        ],
+    );
+    let fetcher = fixture.fetcher();
+    let foo = Package::from_str_ver("foo", "1.0.0", &source).unwrap();
+
+    let table = fetcher.dependency_requirements(foo).unwrap();
+    assert_eq!(
+        (table.normal[0].package.as_str(), table.normal[0].version.as_str()),
+        ("bar", "^1")
+    );
+    assert_eq!(
+        fetcher.candidate_versions(&table.normal[0]).unwrap(),
+        [
+            Package::from_str_ver("bar", "1.2.0", &source).unwrap(),
+            Package::from_str_ver("bar", "1.0.0", &source).unwrap(),
+        ],
+    );
+}
+
+#[test]
+fn optional_dependencies() {
+    let fixture = Fixture::new();
+    let source = fixture.path_package(
+        "foo",
+        r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+
+            [dependencies]
+            serde = { version = "1", optional = true }
+            log = { version = "0.4", optional = true }
+
+            [features]
+            derive = ["serde/derive"]
+            logging = ["dep:log"]
+        "#,
+    );
+    let mut fetcher = fixture.fetcher();
+    let foo = Package::from_str_ver("foo", "0.1.0", &source).unwrap();
+
+    // `log` is only enabled by `logging`, which hides its implicit feature
+    let [log, serde] = <[_; 2]>::try_from(fetcher.optional_dependencies(foo).unwrap()).unwrap();
+    assert_eq!(
+        (log.implicit_feature, log.hidden, log.enabled_by),
+        (None, true, vec!["logging".to_owned()])
+    );
+    assert_eq!(serde.implicit_feature.as_deref(), Some("serde"));
+    assert_eq!(serde.enabled_by, ["derive", "serde"]);
+}
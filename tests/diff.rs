@@ -0,0 +1,48 @@
+mod common;
+
+use cargo_fetch::{FileDiff, PackageSource};
+use common::{manifest, Fixture};
+use std::path::PathBuf;
+
+#[test]
+fn diff() {
+    let fixture = Fixture::new();
+    let old_manifest = manifest("foo", "0.1.0");
+    let new_manifest = manifest("foo", "0.2.0");
+    fixture.write(
+        "old",
+        &[("Cargo.toml", &old_manifest), ("src/lib.rs", ""), ("src/old.rs", "old")],
+    );
+    fixture.write(
+        "new",
+        &[("Cargo.toml", &new_manifest), ("src/lib.rs", ""), ("src/new.rs", "new")],
+    );
+    let hash = |contents: &str| cargo_util::Sha256::new().update(contents.as_bytes()).finish_hex();
+    let mut fetcher = fixture.fetcher();
+    let resolve = |dir| {
+        let source = PackageSource::path(fixture.path(dir)).unwrap();
+        fetcher.resolve_first("foo", None, &source, None).unwrap()
+    };
+    let (old, new) = (resolve("old"), resolve("new"));
+
+    let diff = fetcher.diff(&old, &new).unwrap();
+    let paths: Vec<_> = diff.iter().map(FileDiff::path).collect();
+    assert_eq!(paths, ["Cargo.toml", "src/new.rs", "src/old.rs"]);
+    assert!(matches!(&diff[0], FileDiff::Modified { .. }));
+    assert_eq!(
+        diff[1],
+        FileDiff::Added {
+            path: PathBuf::from("src/new.rs"),
+            hash: hash("new")
+        }
+    );
+    assert_eq!(
+        diff[2],
+        FileDiff::Removed {
+            path: PathBuf::from("src/old.rs"),
+            hash: hash("old")
+        }
+    );
+
+    assert!(fetcher.diff(&old, &old).unwrap().is_empty());
+}
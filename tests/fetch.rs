@@ -0,0 +1,179 @@
+mod common;
+
+use cargo_fetch::{NameFilter, Package, PackageSource};
+use common::{manifest, Fixture, Pkg};
+use std::{fs, path::Path, time::Duration};
+
+#[test]
+fn manifest_metadata() {
+    let fixture = Fixture::new();
+    let source = fixture.path_package(
+        "foo",
+        r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            description = "Parses foo files"
+            license = "MIT OR Apache-2.0"
+            keywords = ["foo", "parser"]
+            categories = ["parser-implementations"]
+        "#,
+    );
+    let mut fetcher = fixture.fetcher();
+    let foo = Package::from_str_ver("foo", "0.1.0", &source).unwrap();
+
+    let metadata = fetcher.manifest_metadata(foo).unwrap();
+    assert_eq!(metadata.description.as_deref(), Some("Parses foo files"));
+    assert_eq!(metadata.keywords, ["foo", "parser"]);
+    assert_eq!(metadata.categories, ["parser-implementations"]);
+    assert_eq!(metadata.repository, None);
+}
+
+#[test]
+fn fetch_many_versions_of_same_package() {
+    let fixture = Fixture::new();
+    let source = fixture.local_registry("registry", &[Pkg::new("foo", "1.0.0"), Pkg::new("foo", "2.0.0")]);
+    let mut fetcher = fixture.fetcher();
+    let old = Package::from_str_ver("foo", "1.0.0", &source).unwrap();
+    let new = Package::from_str_ver("foo", "2.0.0", &source).unwrap();
+
+    let roots = fetcher.fetch_many(&[old, new]).unwrap();
+    assert_eq!(roots.len(), 2);
+    assert!(roots[0].ends_with("foo-1.0.0") && roots[1].ends_with("foo-2.0.0"));
+}
+
+#[test]
+fn fetch_many_order() {
+    let fixture = Fixture::new();
+    let mut roots = vec![];
+    for name in ["a", "b", "c"] {
+        fixture.path_package(name, &manifest(name, "0.1.0"));
+        roots.push(fixture.path(name));
+    }
+    let mut fetcher = fixture.fetcher();
+    let package = |name: &str| {
+        let source = PackageSource::path(fixture.path(name)).unwrap();
+        Package::from_str_ver(name, "0.1.0", &source).unwrap()
+    };
+
+    for _ in 0..3 {
+        assert_eq!(
+            fetcher.fetch_many(&[package("c"), package("a"), package("b")]).unwrap(),
+            roots
+        );
+        assert_eq!(
+            fetcher.fetch_many(&[package("b"), package("c"), package("a")]).unwrap(),
+            roots
+        );
+    }
+}
+
+#[test]
+fn fetch_many_with_progress() {
+    let fixture = Fixture::new();
+    let packages = [Pkg::new("bar", "0.1.0"), Pkg::new("foo", "0.1.0")];
+    let sizes: u64 = packages.iter().map(|package| package.archive().len() as u64).sum();
+    let source = fixture.git_registry("registry", &packages);
+    let mut fetcher = fixture.builder().progress_interval(Duration::ZERO).build().unwrap();
+    let packages = packages.map(|package| Package::from_str_ver(&package.name, "0.1.0", &source).unwrap());
+
+    let mut events = vec![];
+    fetcher
+        .fetch_many_with_progress(&packages, |progress| events.push(progress))
+        .unwrap();
+    assert_eq!(events.len(), 2);
+    assert!(events[0].downloaded_bytes > 0 && events[0].total_bytes.is_none());
+    assert_eq!(
+        (events[1].downloaded_bytes, events[1].total_bytes),
+        (sizes, Some(sizes))
+    );
+
+    // Cached archives aren't downloaded again
+    events.clear();
+    fetcher
+        .fetch_many_with_progress(&packages, |progress| events.push(progress))
+        .unwrap();
+    assert_eq!((events[1].downloaded_bytes, events[1].total_bytes), (0, Some(0)));
+}
+
+#[test]
+fn fetch_detailed() {
+    let fixture = Fixture::new();
+    let path = fixture.path_package("foo", &manifest("foo", "0.1.0"));
+    let bar = Pkg::new("bar", "0.1.0");
+    let size = bar.archive().len() as u64;
+    let registry = fixture.git_registry("registry", &[bar]);
+    let mut fetcher = fixture.fetcher();
+
+    let foo = Package::from_str_ver("foo", "0.1.0", &path).unwrap();
+    let fetched = fetcher.fetch_detailed(foo).unwrap();
+    assert_eq!((fetched.root, fetched.downloaded_bytes), (fixture.path("foo"), 0));
+    assert_eq!(fetcher.downloaded_bytes(), 0);
+
+    let bar = Package::from_str_ver("bar", "0.1.0", &registry).unwrap();
+    assert_eq!(fetcher.fetch_detailed(bar).unwrap().downloaded_bytes, size);
+    assert_eq!(fetcher.fetch_detailed(bar).unwrap().downloaded_bytes, 0);
+    assert_eq!(fetcher.downloaded_bytes(), size);
+}
+
+#[test]
+fn fetch_many_filtered() {
+    let fixture = Fixture::new();
+    for name in ["serde", "serde_json", "serde_derive", "rand"] {
+        fixture.path_package(name, &manifest(name, "0.1.0"));
+    }
+    let mut fetcher = fixture.fetcher();
+    let package = |name: &str| {
+        let source = PackageSource::path(fixture.path(name)).unwrap();
+        Package::from_str_ver(name, "0.1.0", &source).unwrap()
+    };
+    let packages = [
+        package("serde"),
+        package("serde_json"),
+        package("serde_derive"),
+        package("rand"),
+    ];
+
+    let filter = NameFilter {
+        allow: vec!["serde*".into()],
+        deny: vec!["*_derive".into()],
+    };
+    let fetched = fetcher.fetch_many_filtered(&packages, &filter).unwrap();
+    assert_eq!(fetched.roots, [fixture.path("serde"), fixture.path("serde_json")]);
+    assert_eq!(fetched.skipped, [package("serde_derive"), package("rand")]);
+}
+
+#[test]
+fn fetch_reproducible() {
+    let fixture = Fixture::new();
+    let source = fixture.path_package("foo", &manifest("foo", "0.1.0"));
+    let mut fetcher = fixture.fetcher();
+    let foo = Package::from_str_ver("foo", "0.1.0", &source).unwrap();
+
+    let stored = fetcher.fetch_reproducible(foo, fixture.path("store")).unwrap();
+    assert!(fs::metadata(stored.join("src/lib.rs"))
+        .unwrap()
+        .permissions()
+        .readonly());
+    // Copies with the same contents have the same names in any store
+    assert_eq!(stored, fetcher.fetch_reproducible(foo, fixture.path("store")).unwrap());
+    let other = fetcher.fetch_reproducible(foo, fixture.path("other")).unwrap();
+    assert_eq!(stored.file_name(), other.file_name());
+
+    // Lets the fixture remove the read-only copies
+    for store in [fixture.path("store"), fixture.path("other")] {
+        set_writable(&store);
+    }
+}
+
+fn set_writable(path: &Path) {
+    let mut permissions = fs::metadata(path).unwrap().permissions();
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+    fs::set_permissions(path, permissions).unwrap();
+    if path.is_dir() {
+        for entry in fs::read_dir(path).unwrap() {
+            set_writable(&entry.unwrap().path());
+        }
+    }
+}
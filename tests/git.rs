@@ -0,0 +1,107 @@
+mod common;
+
+use cargo_fetch::{Package, PackageSource, ResolveError};
+use common::{manifest, Fixture};
+use url::Url;
+
+#[test]
+fn git_manifest_of_workspace_member() {
+    let fixture = Fixture::new();
+    let repo = fixture.git_repo(
+        "repo",
+        &[
+            ("Cargo.toml", "[workspace]\nmembers = [\"foo\"]\n"),
+            ("foo/Cargo.toml", &manifest("foo", "0.1.0")),
+            ("foo/src/lib.rs", ""),
+        ],
+    );
+    let mut fetcher = fixture.fetcher();
+    let foo = Package::from_str_ver("foo", "0.1.0", &PackageSource::git(repo, None).unwrap()).unwrap();
+
+    assert!(fetcher.git_manifest(foo).unwrap().contains("name = \"foo\""));
+}
+
+#[test]
+fn git_members() {
+    let fixture = Fixture::new();
+    let repo = fixture.git_repo(
+        "repo",
+        &[
+            ("Cargo.toml", "[workspace]\nmembers = [\"foo\", \"foo-derive\"]\n"),
+            ("foo/Cargo.toml", &manifest("foo", "0.1.0")),
+            ("foo/src/lib.rs", ""),
+            ("foo-derive/Cargo.toml", &manifest("foo-derive", "0.1.0")),
+            ("foo-derive/src/lib.rs", ""),
+        ],
+    );
+    let mut fetcher = fixture.fetcher();
+    let source = PackageSource::git(repo, None).unwrap();
+
+    let members = fetcher.git_members(&source).unwrap();
+    let names: Vec<_> = members.iter().map(|member| member.lock_id()).collect();
+    assert_eq!(names.len(), 2);
+    assert!(names[0].starts_with("foo 0.1.0") && names[1].starts_with("foo-derive 0.1.0"));
+
+    let derive = fetcher.resolve_first("foo-derive", None, &source, None).unwrap();
+    assert!(fetcher.fetch(derive).unwrap().ends_with("foo-derive"));
+    assert_eq!(
+        fetcher.resolve_first("foo-macros", None, &source, None),
+        Err(ResolveError::MemberNotFound {
+            name: "foo-macros".into(),
+            members: vec!["foo".into(), "foo-derive".into()],
+        }),
+    );
+}
+
+#[test]
+fn check_repository() {
+    let fixture = Fixture::new();
+    let url = Url::from_file_path(fixture.path("repo")).unwrap();
+    let manifest = |name: &str, repository: &str| {
+        format!("[package]\nname = '{name}'\nversion = '0.1.0'\nrepository = '{repository}'\n")
+    };
+    let repo = fixture.git_repo(
+        "repo",
+        &[
+            ("Cargo.toml", "[workspace]\nmembers = [\"foo\", \"bar\"]\n"),
+            ("foo/Cargo.toml", &manifest("foo", &format!("{url}.git"))),
+            ("foo/src/lib.rs", ""),
+            ("bar/Cargo.toml", &manifest("bar", "https://github.com/rust-lang/bar")),
+            ("bar/src/lib.rs", ""),
+        ],
+    );
+    let mut fetcher = fixture.fetcher();
+    let source = PackageSource::git(repo, None).unwrap();
+
+    let foo = fetcher
+        .check_repository(Package::from_str_ver("foo", "0.1.0", &source).unwrap())
+        .unwrap();
+    assert!(foo.matches);
+    let bar = fetcher
+        .check_repository(Package::from_str_ver("bar", "0.1.0", &source).unwrap())
+        .unwrap();
+    assert_eq!(bar.repository.as_deref(), Some("https://github.com/rust-lang/bar"));
+    assert!(!bar.matches);
+}
+
+#[test]
+fn url_rewriter() {
+    let fixture = Fixture::new();
+    let mut fetcher = fixture.fetcher();
+    fetcher.set_url_rewriter(|url| {
+        let mut url = url.clone();
+        if url.host_str() == Some("github.com") {
+            url.set_host(Some("git.example.com")).unwrap();
+        }
+        url
+    });
+
+    let source = PackageSource::git("https://github.com/serde-rs/serde", None).unwrap();
+    let url = fetcher.effective_source_url(&source).unwrap();
+    assert_eq!(url.as_str(), "https://git.example.com/serde-rs/serde");
+    let path = PackageSource::path(fixture.path("foo")).unwrap();
+    assert_eq!(
+        fetcher.effective_source_url(&path).unwrap(),
+        Url::from_file_path(fixture.path("foo")).unwrap()
+    );
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use cargo_fetch::{Features, Package, PackageSource, TreeOptions};
+use common::{Fixture, Pkg};
+
+#[test]
+fn resolve_graph_applies_workspace_patches() {
+    let fixture = Fixture::new();
+    fixture.local_registry("registry", &[Pkg::new("foo", "1.0.0")]);
+    let config = format!(
+        "[source.crates-io]\nreplace-with = 'local'\n[source.local]\nlocal-registry = '{}'\n",
+        fixture.path("registry").display(),
+    );
+    fixture.write("cargo-home", &[("config.toml", &config)]);
+    fixture.write(
+        "ws",
+        &[
+            (
+                "Cargo.toml",
+                "[workspace]\nmembers = ['app']\n[patch.crates-io]\nfoo = { path = 'foo' }\n",
+            ),
+            (
+                "app/Cargo.toml",
+                "[package]\nname = 'app'\nversion = '0.1.0'\n[dependencies]\nfoo = '1'\n",
+            ),
+            ("app/src/lib.rs", ""),
+            ("foo/Cargo.toml", "[package]\nname = 'foo'\nversion = '1.0.1'\n"),
+            ("foo/src/lib.rs", ""),
+        ],
+    );
+    // Workspace member `app` depends on `foo = "1"` from crates.io, the workspace root patches it with `foo`
+    // from a path
+    let mut fetcher = fixture.fetcher();
+    let app = Package::from_str_ver("app", "0.1.0", &PackageSource::path(fixture.path("ws/app")).unwrap()).unwrap();
+    let patched = Package::from_str_ver("foo", "1.0.1", &PackageSource::path(fixture.path("ws/foo")).unwrap()).unwrap();
+
+    let graph = fetcher.resolve_graph(app, &Features::default()).unwrap();
+    assert_eq!(graph.dependencies(&app)[0].package, patched);
+}
+
+#[test]
+fn resolved_features_are_unified() {
+    let fixture = Fixture::new();
+    fixture.path_packages(&[
+        (
+            "app",
+            "[dependencies]\nfoo = { path = '../foo', features = ['json'] }\nbar = { path = '../bar' }",
+        ),
+        (
+            "bar",
+            "[dependencies]\nfoo = { path = '../foo', default-features = false, features = ['yaml'] }",
+        ),
+        (
+            "foo",
+            "[features]\ndefault = ['std']\nstd = []\njson = []\nyaml = []\ntoml = []",
+        ),
+    ]);
+    let mut fetcher = fixture.fetcher();
+
+    let (app, foo) = (fixture.package("app"), fixture.package("foo"));
+    let features = fetcher.resolved_features(app, &Features::default(), &foo).unwrap();
+    assert_eq!(features, ["default", "json", "std", "yaml"]);
+}
+
+#[test]
+fn flat_dependency_set() {
+    let fixture = Fixture::new();
+    fixture.path_packages(&[
+        ("app", "[dependencies]\na = { path = '../a' }\nb = { path = '../b' }"),
+        ("a", "[dependencies]\nc = { path = '../c' }"),
+        ("b", "[dependencies]\nc = { path = '../c' }"),
+        ("c", ""),
+    ]);
+    let mut fetcher = fixture.fetcher();
+    let package = |name| fixture.package(name);
+
+    let dependencies = fetcher
+        .flat_dependency_set(package("app"), &Features::default(), false)
+        .unwrap();
+    assert_eq!(dependencies, [package("a"), package("b"), package("c")]);
+    let with_root = fetcher
+        .flat_dependency_set(package("a"), &Features::default(), true)
+        .unwrap();
+    assert_eq!(with_root, [package("a"), package("c")]);
+}
+
+#[test]
+fn tree() {
+    let fixture = Fixture::new();
+    fixture.path_packages(&[
+        ("app", "[dependencies]\na = { path = '../a' }\nb = { path = '../b' }"),
+        ("a", "[dependencies]\nc = { path = '../c' }"),
+        ("b", "[dependencies]\nc = { path = '../c' }"),
+        ("c", "[dependencies]\nd = { path = '../d' }"),
+        ("d", ""),
+    ]);
+    let mut fetcher = fixture.fetcher();
+    let package = |name| fixture.package(name);
+    let dir = fixture.path("");
+    let dir = dir.to_str().unwrap().trim_end_matches('/');
+
+    let tree = fetcher
+        .tree(package("app"), &Features::default(), &TreeOptions::default())
+        .unwrap();
+    let expected = "
+app v0.1.0 (/app)
+├── a v0.1.0 (/a)
+│   └── c v0.1.0 (/c)
+│       └── d v0.1.0 (/d)
+└── b v0.1.0 (/b)
+    └── c v0.1.0 (/c) (*)
+";
+    assert_eq!(tree.replace(dir, ""), expected.trim_start());
+
+    let options = TreeOptions {
+        invert: Some(package("d")),
+        depth: Some(2),
+        ascii: true,
+        ..Default::default()
+    };
+    let inverted = fetcher.tree(package("app"), &Features::default(), &options).unwrap();
+    let expected = "
+d v0.1.0 (/d)
+`-- c v0.1.0 (/c)
+    |-- a v0.1.0 (/a)
+    `-- b v0.1.0 (/b)
+";
+    assert_eq!(inverted.replace(dir, ""), expected.trim_start());
+}
@@ -0,0 +1,161 @@
+//! The expected versions are the ones `cargo add` adds, and cargo then locks, for the same index.
+
+mod common;
+
+use cargo_fetch::{Package, PackageSource, ResolveError};
+use common::{manifest, Fixture, Pkg};
+use semver::Version;
+
+fn registry(fixture: &Fixture) -> PackageSource {
+    fixture.local_registry(
+        "registry",
+        &[
+            Pkg::new("foo", "1.0.0"),
+            Pkg::new("foo", "1.1.0").yanked(),
+            Pkg::new("foo", "2.0.0-rc.1"),
+            Pkg::new("bar-baz", "0.1.0-alpha"),
+            Pkg::new("bar-baz", "0.1.0-beta"),
+            Pkg::new("qux-quux", "0.1.0"),
+            Pkg::new("qux-quux", "0.1.1"),
+        ],
+    )
+}
+
+fn versions(versions: &[&str]) -> Vec<Version> {
+    versions.iter().map(|version| version.parse().unwrap()).collect()
+}
+
+#[test]
+fn skips_yanked() {
+    let fixture = Fixture::new();
+    let source = registry(&fixture);
+    let fetcher = fixture.fetcher();
+
+    // `cargo add foo` adds `foo = "1.0.0"`
+    let foo = fetcher.resolve_installable("foo", None, &source).unwrap();
+    assert_eq!(foo, Package::from_str_ver("foo", "1.0.0", &source).unwrap());
+    // `cargo add foo@1.1` fails, as 1.1.0 is yanked
+    assert_eq!(
+        fetcher.resolve_installable("foo", Some("1.1"), &source),
+        Err(ResolveError::NoMatchingVersion {
+            name: "foo".into(),
+            req: "1.1".into(),
+            available: versions(&["1.0.0", "2.0.0-rc.1"]),
+        }),
+    );
+}
+
+#[test]
+fn only_pre_releases() {
+    let fixture = Fixture::new();
+    let source = registry(&fixture);
+    let fetcher = fixture.fetcher();
+
+    // `cargo add bar_baz` adds `bar-baz = "0.1.0-beta"`
+    let bar_baz = fetcher.resolve_installable("bar_baz", None, &source).unwrap();
+    assert_eq!(
+        bar_baz,
+        Package::from_str_ver("bar-baz", "0.1.0-beta", &source).unwrap()
+    );
+    // `cargo add bar-baz@0.1.0-alpha` adds `bar-baz = "0.1.0-alpha"`, which locks the newer pre-release
+    let bar_baz = fetcher
+        .resolve_installable("bar_baz", Some("0.1.0-alpha"), &source)
+        .unwrap();
+    assert_eq!(
+        bar_baz,
+        Package::from_str_ver("bar-baz", "0.1.0-beta", &source).unwrap()
+    );
+    // `cargo add bar-baz@0.1` fails, as the requirement doesn't name a pre-release
+    assert_eq!(
+        fetcher.resolve_installable("bar_baz", Some("0.1"), &source),
+        Err(ResolveError::NoMatchingVersion {
+            name: "bar-baz".into(),
+            req: "0.1".into(),
+            available: versions(&["0.1.0-alpha", "0.1.0-beta"]),
+        }),
+    );
+}
+
+#[test]
+fn pre_release_requirement() {
+    let fixture = Fixture::new();
+    let source = registry(&fixture);
+    let fetcher = fixture.fetcher();
+
+    // `cargo add foo@2.0.0-rc` adds `foo = "2.0.0-rc"`, which locks 2.0.0-rc.1
+    let foo = fetcher.resolve_installable("foo", Some("2.0.0-rc"), &source).unwrap();
+    assert_eq!(foo, Package::from_str_ver("foo", "2.0.0-rc.1", &source).unwrap());
+    // `cargo add foo@2` fails, as pre-releases only match requirements naming them
+    assert_eq!(
+        fetcher.resolve_installable("foo", Some("2"), &source),
+        Err(ResolveError::NoMatchingVersion {
+            name: "foo".into(),
+            req: "2".into(),
+            available: versions(&["1.0.0", "2.0.0-rc.1"]),
+        }),
+    );
+}
+
+#[test]
+fn translates_name_with_requirement() {
+    let fixture = Fixture::new();
+    let source = registry(&fixture);
+    let fetcher = fixture.fetcher();
+
+    let qux_quux = fetcher.resolve_installable("qux_quux", Some("0.1"), &source).unwrap();
+    assert_eq!(qux_quux, Package::from_str_ver("qux-quux", "0.1.1", &source).unwrap());
+    let qux_quux = fetcher
+        .resolve_installable("qux_quux", Some("=0.1.0"), &source)
+        .unwrap();
+    assert_eq!(qux_quux, Package::from_str_ver("qux-quux", "0.1.0", &source).unwrap());
+    assert_eq!(
+        fetcher.resolve_installable("qux_quuz", Some("0.1"), &source),
+        Err(ResolveError::CrateNotFound {
+            name: "qux_quuz".into()
+        }),
+    );
+}
+
+#[test]
+fn path_and_git_sources() {
+    let fixture = Fixture::new();
+    let path = fixture.path_package("foo", &manifest("foo", "0.1.0"));
+    let repo = fixture.git_repo(
+        "repo",
+        &[
+            ("Cargo.toml", "[workspace]\nmembers = [\"foo\", \"bar-baz\"]\n"),
+            ("foo/Cargo.toml", &manifest("foo", "0.1.0")),
+            ("foo/src/lib.rs", ""),
+            ("bar-baz/Cargo.toml", &manifest("bar-baz", "0.2.0")),
+            ("bar-baz/src/lib.rs", ""),
+        ],
+    );
+    let git = PackageSource::git(repo, None).unwrap();
+    let fetcher = fixture.fetcher();
+    let installable = |name, source| fetcher.resolve_installable(name, None, source);
+
+    assert_eq!(
+        installable("foo", &path).unwrap(),
+        Package::from_str_ver("foo", "0.1.0", &path).unwrap()
+    );
+    assert_eq!(
+        installable("nonexistent", &path),
+        Err(ResolveError::CrateNotFound {
+            name: "nonexistent".into()
+        })
+    );
+
+    assert_eq!(
+        installable("foo", &git).unwrap(),
+        Package::from_str_ver("foo", "0.1.0", &git).unwrap()
+    );
+    let bar_baz = Package::from_str_ver("bar-baz", "0.2.0", &git).unwrap();
+    assert_eq!(installable("bar_baz", &git).unwrap(), bar_baz);
+    assert_eq!(
+        installable("nonexistent", &git),
+        Err(ResolveError::MemberNotFound {
+            name: "nonexistent".into(),
+            members: vec!["bar-baz".into(), "foo".into()],
+        }),
+    );
+}
@@ -0,0 +1,29 @@
+mod common;
+
+use cargo_fetch::{Features, LICENSE_FILE_KEY};
+use common::Fixture;
+
+#[test]
+fn license_report() {
+    let fixture = Fixture::new();
+    fixture.path_packages(&[
+        (
+            "app",
+            "license = 'MIT'\n[dependencies]\na = { path = '../a' }\nb = { path = '../b' }",
+        ),
+        (
+            "a",
+            "license = 'MIT OR Apache-2.0'\n[dependencies]\nc = { path = '../c' }",
+        ),
+        ("b", "license-file = 'LICENSE'"),
+        ("c", "license = 'MIT OR Apache-2.0'"),
+    ]);
+    let mut fetcher = fixture.fetcher();
+    let package = |name| fixture.package(name);
+
+    // `app` itself isn't included
+    let report = fetcher.license_report(package("app"), &Features::default()).unwrap();
+    assert_eq!(report.len(), 2);
+    assert_eq!(report["MIT OR Apache-2.0"], [package("a"), package("c")]);
+    assert_eq!(report[LICENSE_FILE_KEY], [package("b")]);
+}
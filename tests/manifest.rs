@@ -0,0 +1,19 @@
+mod common;
+
+use common::{manifest, Fixture};
+use std::fs;
+
+#[test]
+fn validate_path_source() {
+    let fixture = Fixture::new();
+    let source = fixture.path_package("foo", "[package]\nname = \"foo\"\nversion = 0.1.0\n");
+    let fetcher = fixture.fetcher();
+
+    // The version on the third line isn't quoted
+    let error = fetcher.validate_path_source(&source).unwrap_err();
+    assert_eq!(error.path, fixture.path("foo/Cargo.toml"));
+    assert_eq!(error.position, Some((3, 14)));
+
+    fs::write(fixture.path("foo/Cargo.toml"), manifest("foo", "0.1.0")).unwrap();
+    fetcher.validate_path_source(&source).unwrap();
+}
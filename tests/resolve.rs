@@ -0,0 +1,151 @@
+mod common;
+
+use cargo_fetch::{Package, PackageSource, ResolveError};
+use common::{Fixture, Pkg};
+use std::collections::HashSet;
+
+#[test]
+fn resolve_first_skips_yanked_unless_whitelisted() {
+    let fixture = Fixture::new();
+    let source = fixture.local_registry(
+        "registry",
+        &[Pkg::new("foo", "1.0.0"), Pkg::new("foo", "1.1.0").yanked()],
+    );
+    let fetcher = fixture.fetcher();
+    let yanked = Package::from_str_ver("foo", "1.1.0", &source).unwrap();
+
+    assert_eq!(
+        fetcher.resolve_first("foo", None, &source, None).unwrap(),
+        Package::from_str_ver("foo", "1.0.0", &source).unwrap(),
+    );
+    let whitelist = Some(HashSet::from([yanked]));
+    assert_eq!(fetcher.resolve_first("foo", None, &source, whitelist).unwrap(), yanked);
+}
+
+#[test]
+fn resolve_first_not_found() {
+    let fixture = Fixture::new();
+    let source = fixture.local_registry("registry", &[Pkg::new("foo", "1.0.0")]);
+    let fetcher = fixture.fetcher();
+
+    assert_eq!(
+        fetcher.resolve_first("foo", Some("2"), &source, None),
+        Err(ResolveError::NoMatchingVersion {
+            name: "foo".into(),
+            req: "2".into(),
+            available: vec!["1.0.0".parse().unwrap()],
+        }),
+    );
+    assert_eq!(
+        fetcher.resolve_first("bar", None, &source, None),
+        Err(ResolveError::CrateNotFound { name: "bar".into() }),
+    );
+}
+
+#[test]
+fn set_version_ordering() {
+    let fixture = Fixture::new();
+    let versions = ["1.0.0", "1.1.0", "2.0.0"];
+    let source = fixture.local_registry("registry", &versions.map(|version| Pkg::new("foo", version)));
+    let mut fetcher = fixture.fetcher();
+    let foo = |version| Package::from_str_ver("foo", version, &source).unwrap();
+    assert_eq!(
+        fetcher.resolve_first("foo", Some("1"), &source, None).unwrap(),
+        foo("1.1.0")
+    );
+
+    // Selects the oldest matching version instead
+    fetcher.set_version_ordering(|a, b| b.cmp(a));
+    assert_eq!(
+        fetcher.resolve_first("foo", Some("1"), &source, None).unwrap(),
+        foo("1.0.0")
+    );
+    assert_eq!(fetcher.resolve_first("foo", None, &source, None).unwrap(), foo("1.0.0"));
+}
+
+#[test]
+fn resolve_with_precedence() {
+    let fixture = Fixture::new();
+    let private = fixture.local_registry("private", &[Pkg::new("foo", "1.0.0")]);
+    let public = fixture.local_registry("public", &[Pkg::new("foo", "2.0.0"), Pkg::new("bar", "1.0.0")]);
+    let fetcher = fixture.fetcher();
+    let sources = [private.clone(), public.clone()];
+
+    let foo = fetcher.resolve_with_precedence("foo", None, &sources, None).unwrap();
+    assert_eq!(foo.package, Package::from_str_ver("foo", "1.0.0", &private).unwrap());
+    assert_eq!(foo.source, private);
+    let bar = fetcher.resolve_with_precedence("bar", None, &sources, None).unwrap();
+    assert_eq!(bar.source, public);
+    // `foo` isn't resolved from the public registry, even though only it has a matching version
+    assert!(fetcher
+        .resolve_with_precedence("foo", Some("2"), &sources, None)
+        .is_err());
+}
+
+#[test]
+fn open_registry() {
+    let fixture = Fixture::new();
+    let source = fixture.local_registry("registry", &[Pkg::new("foo", "1.0.0"), Pkg::new("bar", "1.0.0")]);
+    let fetcher = fixture.fetcher();
+    let registry = fetcher.open_registry(&source).unwrap();
+
+    assert_eq!(
+        registry.resolve("foo", None).unwrap(),
+        Package::from_str_ver("foo", "1.0.0", &source).unwrap()
+    );
+    assert_eq!(
+        registry.resolve("bar", Some("1")).unwrap(),
+        Package::from_str_ver("bar", "1.0.0", &source).unwrap()
+    );
+    assert!(fetcher
+        .open_registry(&PackageSource::path(fixture.path("registry")).unwrap())
+        .is_err());
+}
+
+#[test]
+fn resolve_each() {
+    let fixture = Fixture::new();
+    let source = fixture.local_registry(
+        "registry",
+        &[
+            Pkg::new("foo", "1.0.0"),
+            Pkg::new("foo", "1.1.0").yanked(),
+            Pkg::new("foo", "2.0.0-rc.1"),
+        ],
+    );
+    let fetcher = fixture.fetcher();
+
+    let mut versions = vec![];
+    fetcher
+        .resolve_each("foo", &source, |info| {
+            versions.push((info.version.to_string(), info.yanked, info.prerelease))
+        })
+        .unwrap();
+    versions.sort();
+    assert_eq!(
+        versions,
+        [
+            ("1.0.0".into(), false, false),
+            ("1.1.0".into(), true, false),
+            ("2.0.0-rc.1".into(), false, true)
+        ],
+    );
+}
+
+#[test]
+fn has_newer_major() {
+    let fixture = Fixture::new();
+    let versions = ["0.1.0", "0.1.5", "0.2.0", "0.4.0-rc.1"];
+    let mut packages = versions.map(|version| Pkg::new("foo", version)).to_vec();
+    packages.push(Pkg::new("foo", "0.3.0").yanked());
+    let source = fixture.local_registry("registry", &packages);
+    let fetcher = fixture.fetcher();
+    let foo = |version| Package::from_str_ver("foo", version, &source).unwrap();
+
+    // Skips the yanked 0.3.0 and the pre-release
+    assert_eq!(
+        fetcher.has_newer_major(foo("0.1.0")).unwrap(),
+        Some("0.2.0".parse().unwrap())
+    );
+    assert_eq!(fetcher.has_newer_major(foo("0.2.0")).unwrap(), None);
+}
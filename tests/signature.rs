@@ -0,0 +1,93 @@
+#![cfg(feature = "gpg")]
+
+mod common;
+
+use cargo_fetch::{GitReference, Package, PackageSource};
+use common::{manifest, Fixture};
+use std::{
+    fs,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+#[test]
+fn fetch_verified_tag() {
+    let fixture = Fixture::new();
+    let gnupg = fixture.path("gnupg");
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(builder.recursive(true), 0o700);
+    builder.create(&gnupg).unwrap();
+    let gpg = |args: &[&str], input: &str| {
+        let mut gpg = Command::new("gpg")
+            .arg("--homedir")
+            .arg(&gnupg)
+            .args(["--batch", "--quiet", "--passphrase", ""])
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        gpg.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+        String::from_utf8(gpg.wait_with_output().unwrap().stdout).unwrap()
+    };
+    for user in ["maintainer@example.com", "other@example.com"] {
+        gpg(&["--quick-gen-key", user, "ed25519", "sign", "never"], "");
+    }
+    let key = gpg(&["--armor", "--export", "maintainer@example.com"], "");
+    let listed = gpg(&["--with-colons", "--list-keys", "maintainer@example.com"], "");
+    let fingerprint = listed
+        .lines()
+        .find_map(|line| line.strip_prefix("fpr:"))
+        .unwrap()
+        .trim_matches(':');
+
+    let url = fixture.git_repo("foo", &[("Cargo.toml", &manifest("foo", "0.1.0")), ("src/lib.rs", "")]);
+    let repo = git2::Repository::open(fixture.path("foo")).unwrap();
+    let commit = repo.head().unwrap().peel_to_commit().unwrap().id();
+    let tag = |name: &str, header: &str, signer: Option<&str>| {
+        let mut tag = format!("object {commit}\ntype commit\ntag {header}\n");
+        tag += "tagger foo <foo@example.com> 0 +0000\n\nRelease\n";
+        if let Some(signer) = signer {
+            tag += &gpg(&["--armor", "--detach-sign", "--local-user", signer], &tag);
+        }
+        let id = repo
+            .odb()
+            .unwrap()
+            .write(git2::ObjectType::Tag, tag.as_bytes())
+            .unwrap();
+        repo.reference(&format!("refs/tags/{name}"), id, false, "tag").unwrap();
+    };
+    tag("v0.1.0", "v0.1.0", Some("maintainer@example.com"));
+    tag("unsigned", "unsigned", None);
+    tag("untrusted", "untrusted", Some("other@example.com"));
+    // Signed by the trusted key as `v0.1.0`
+    tag("v1.0.0", "v0.1.0", Some("maintainer@example.com"));
+
+    let mut fetcher = fixture.fetcher();
+    let foo = |tag: &str| {
+        let source = PackageSource::git(&url, Some(GitReference::Tag(tag.into()))).unwrap();
+        Package::from_str_ver("foo", "0.1.0", &source).unwrap()
+    };
+    let trusted = [key];
+
+    let verified = fetcher.fetch_verified_tag(foo("v0.1.0"), &trusted, true).unwrap();
+    assert_eq!(verified.signer.as_deref(), Some(fingerprint));
+    assert!(fetcher.fetch_verified_tag(foo("unsigned"), &trusted, true).is_err());
+    assert_eq!(
+        fetcher
+            .fetch_verified_tag(foo("unsigned"), &trusted, false)
+            .unwrap()
+            .signer,
+        None
+    );
+    assert!(fetcher.fetch_verified_tag(foo("untrusted"), &trusted, false).is_err());
+    assert!(fetcher.fetch_verified_tag(foo("v1.0.0"), &trusted, true).is_err());
+    Command::new("gpgconf")
+        .arg("--homedir")
+        .arg(&gnupg)
+        .args(["--kill", "all"])
+        .status()
+        .unwrap();
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use cargo_fetch::{Package, PackageSource};
+use common::{manifest, Fixture, Pkg};
+use serde_json::Value;
+use std::fs;
+
+#[test]
+fn excludes_version_control_files_in_subdirectories() {
+    let fixture = Fixture::new();
+    let repo = fixture.git_repo(
+        "repo",
+        &[
+            ("Cargo.toml", &manifest("foo", "0.1.0")),
+            ("src/lib.rs", ""),
+            (".gitattributes", "* text=auto\n"),
+            ("src/.gitignore", "*.tmp\n"),
+            ("src/.gitattributes", "*.rs text\n"),
+            ("src/lib.rs.orig", ""),
+        ],
+    );
+    let mut fetcher = fixture.fetcher();
+    let foo = fetcher
+        .resolve_first("foo", None, &PackageSource::git(repo, None).unwrap(), None)
+        .unwrap();
+
+    let vendored = fixture.path("vendor");
+    fetcher.vendor(&[foo], &vendored).unwrap();
+    let foo = vendored.join("foo");
+    assert!(foo.join("src/lib.rs").exists());
+    for excluded in [
+        ".gitattributes",
+        "src/.gitignore",
+        "src/.gitattributes",
+        "src/lib.rs.orig",
+    ] {
+        assert!(!foo.join(excluded).exists(), "`{excluded}` was vendored");
+    }
+    let checksums = fs::read_to_string(foo.join(".cargo-checksum.json")).unwrap();
+    assert!(!checksums.contains(".git"));
+}
+
+#[test]
+fn verify_contents_rejects_paths_outside_of_package() {
+    let fixture = Fixture::new();
+    let dir = fixture.path("foo");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("Cargo.toml"), manifest("foo", "0.1.0")).unwrap();
+    fs::write(dir.join("src/lib.rs"), "").unwrap();
+    // Empty file, so its checksum matches the empty `src/lib.rs` too.
+    fs::write(fixture.path("outside"), "").unwrap();
+    let empty = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    let mut fetcher = fixture.fetcher();
+    let foo = fetcher
+        .resolve_first("foo", None, &PackageSource::path(&dir).unwrap(), None)
+        .unwrap();
+    let outside = fixture.path("outside").display().to_string();
+    for file in ["../outside", "src/../../outside", outside.as_str()] {
+        let checksums = serde_json::json!({ "files": { "src/lib.rs": empty, file: empty } });
+        fs::write(dir.join(".cargo-checksum.json"), checksums.to_string()).unwrap();
+        let error = fetcher.verify_contents(&foo).unwrap_err();
+        assert!(error.contains(&format!("unexpected path `{file}`")), "{error}");
+    }
+
+    let checksums = serde_json::json!({ "files": { "src/lib.rs": empty } });
+    fs::write(dir.join(".cargo-checksum.json"), checksums.to_string()).unwrap();
+    fetcher.verify_contents(&foo).unwrap();
+}
+
+#[test]
+fn vendor() {
+    let fixture = Fixture::new();
+    let repo = fixture.git_repo("repo", &[("Cargo.toml", &manifest("foo", "0.1.0")), ("src/lib.rs", "")]);
+    let registry = fixture.git_registry("registry", &[Pkg::new("bar", "0.1.0")]);
+    let local = fixture.local_registry("local-registry", &[Pkg::new("bar", "0.1.0")]);
+    let cksum = cargo_util::Sha256::new()
+        .update(&Pkg::new("bar", "0.1.0").archive())
+        .finish_hex();
+    let mut fetcher = fixture.fetcher();
+    let git = PackageSource::git(repo, None).unwrap();
+    let foo = fetcher.resolve_first("foo", None, &git, None).unwrap();
+    let bar = fetcher.resolve_first("bar", None, &registry, None).unwrap();
+
+    let vendored = fixture.path("vendor");
+    let snippet = fetcher.vendor(&[foo, bar], &vendored).unwrap();
+    let checksums = |name: &str| -> Value {
+        let json = fs::read_to_string(vendored.join(name).join(".cargo-checksum.json")).unwrap();
+        serde_json::from_str(&json).unwrap()
+    };
+    assert_eq!(checksums("foo")["package"], Value::Null);
+    assert_eq!(checksums("bar")["package"], cksum.as_str());
+    assert!(checksums("bar")["files"]["src/lib.rs"].is_string());
+    assert!(snippet.contains("replace-with = \"vendored-sources\""));
+
+    // Local registries can't be replaced
+    let local = Package::from_str_ver("bar", "0.1.0", &local).unwrap();
+    assert!(fetcher.vendor(&[local], &vendored).is_err());
+
+    // The original sources aren't needed anymore with the snippet in the cargo config
+    fs::remove_dir_all(fixture.path("repo")).unwrap();
+    fs::remove_dir_all(fixture.path("registry")).unwrap();
+    fixture.write("cargo-home", &[("config.toml", &snippet)]);
+    let mut fetcher = fixture.fetcher();
+    assert_eq!(fetcher.resolve_first("bar", None, &registry, None).unwrap(), bar);
+    // Git sources are only replaced for packages locked to a commit, as the resolved ones are
+    assert_eq!(fetcher.fetch(foo).unwrap(), vendored.join("foo"));
+    assert_eq!(fetcher.fetch(bar).unwrap(), vendored.join("bar"));
+}
+
+#[test]
+fn verify_contents() {
+    let fixture = Fixture::new();
+    let empty = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+    let checksums = serde_json::json!({ "files": { "src/lib.rs": empty } }).to_string();
+    let dir = fixture.write(
+        "foo",
+        &[
+            ("Cargo.toml", &manifest("foo", "1.0.0")),
+            ("src/lib.rs", ""),
+            (".cargo-checksum.json", &checksums),
+        ],
+    );
+    let mut fetcher = fixture.fetcher();
+    let foo = fetcher
+        .resolve_first("foo", None, &PackageSource::path(&dir).unwrap(), None)
+        .unwrap();
+    fetcher.verify_contents(&foo).unwrap();
+
+    fs::write(dir.join("src/lib.rs"), "pub fn foo() {}").unwrap();
+    assert!(fetcher.verify_contents(&foo).unwrap_err().contains("`src/lib.rs`"));
+    fs::remove_file(dir.join(".cargo-checksum.json")).unwrap();
+    assert!(fetcher.verify_contents(&foo).is_err());
+}